@@ -7,21 +7,25 @@ use scalar_cms::{
     db::{DatabaseFactory, ValidationContext},
     DatabaseConnection,
 };
+use scalar_expr::expression;
 use scalar_surreal::{init, SurrealStore};
 use std::env;
 use surrealdb::engine::remote::ws::{Client, Ws};
 
-use axum::Router;
+use axum::{Extension, Router};
 use axum_macros::FromRef;
 use rgb::{RGB8, RGBA8};
 use s3::{creds::Credentials, Bucket, Region};
 use scalar_axum::{
-    generate_routes,
+    events::EventBus,
+    generate_content_routes, generate_routes,
     oidc::{CoreOidcState, OidcState},
+    webhook::{WebhookEndpoint, Webhooks},
 };
 use scalar_cms::{
     doc_enum,
-    types::{Markdown, MultiLine, Toggle},
+    migrations::Migration,
+    types::{Locales, Localized, Markdown, MultiLine, Reference, RichText, Toggle},
     validations::{ErroredField, Field, Validate, ValidationError},
     DateTime, Document, EditorField, NaiveDate, Utc,
 };
@@ -69,27 +73,48 @@ async fn string_test<DB: DatabaseConnection, D: Document>(
         ))
 }
 
+#[allow(clippy::unused_async)]
+async fn all_types_test<DB: DatabaseConnection, D: Document>(
+    doc: &AllTypes,
+    _ctx: ValidationContext<'_, DB, D>,
+) -> Result<(), Vec<ErroredField>> {
+    if doc.toggle.0.is_some() && doc.integer <= 0 {
+        Err(vec![ErroredField {
+            field: "integer".into(),
+            error: ValidationError::Single("must be positive when toggle is on".into()),
+        }])
+    } else {
+        Ok(())
+    }
+}
+
 #[derive(Document, Debug, Serialize, Deserialize)]
+#[validate(document = all_types_test)]
+#[document(groups(basics, media(fieldset, collapsed)))]
 struct AllTypes {
     #[validate(skip)]
+    #[field(group = "basics")]
     bool: bool,
-    #[validate(with = int_test)]
+    #[validate(range(min = -10, max = 10), with = int_test)]
     integer: i32,
     #[validate(with = float_test)]
     float: f32,
-    #[validate(with = string_test)]
+    #[validate(non_empty, length(max = 100), warn(length(max = 60)), with = string_test)]
     #[field(label)]
     single_line: String,
+    /// Shown under the title in lists.
     #[validate(skip)]
     #[field(sublabel)]
     multi_line: MultiLine,
     #[validate(skip)]
+    #[field(deprecated = "use rich_text instead")]
     markdown: Markdown,
     #[validate(skip)]
     array: Vec<String>,
     #[validate(skip)]
     toggle: Toggle<i32>,
     #[validate(skip)]
+    #[field(readonly)]
     date: NaiveDate,
     #[validate(skip)]
     date_time: DateTime<Utc>,
@@ -98,12 +123,28 @@ struct AllTypes {
     #[validate(skip)]
     color_alpha: RGBA8,
     #[validate(skip)]
+    #[field(group = "media")]
     image: ImageData<ImageInner>,
+    #[field(group = "media", hidden_if = expression!(field:"bool" == false))]
     cropped_image: CroppedImage,
     #[validate(skip)]
+    #[field(group = "media")]
     file: FileData<()>,
     enum_select: TestEnum,
+    #[validate(skip)]
+    link: Link,
     struct_test: StructTest,
+    reference: Reference<Test2>,
+    localized: Localized<String, SiteLocales, false>,
+    rich_text: RichText,
+}
+
+struct SiteLocales;
+
+impl Locales for SiteLocales {
+    const LOCALES: &'static [&'static str] = &["en", "de", "fr", "nl"];
+    const REQUIRED: &'static [&'static str] = &["en"];
+    const FALLBACK: &'static [&'static str] = &["en"];
 }
 
 #[derive(EditorField, Debug, Serialize, Deserialize)]
@@ -132,8 +173,14 @@ struct ImageInner {
     info: String,
 }
 
+const TEST2_MIGRATIONS: &[Migration] = &[Migration::new(1, "rename greeting to hello", |v| {
+    if let Some(greeting) = v.as_object_mut().and_then(|o| o.remove("greeting")) {
+        v["hello"] = greeting;
+    }
+})];
+
 #[derive(Document, Debug, Serialize, Deserialize, Clone)]
-#[document(singleton)]
+#[document(singleton, migrations = TEST2_MIGRATIONS)]
 struct Test2 {
     #[validate(skip)]
     pub hello: String,
@@ -146,15 +193,29 @@ enum TestEnum {
     Struct { eeee: String },
 }
 
+#[doc_enum]
+#[serde(tag = "kind", content = "value", rename_all = "snake_case")]
+#[derive(Debug, Clone)]
+enum Link {
+    None,
+    Url(String),
+    Email(String),
+    Anchor(String, String),
+}
+
 impl Validate for TestEnum {
     async fn validate<DB: DatabaseConnection, D: Document>(
         &self,
         _ctx: ValidationContext<'_, DB, D>,
     ) -> Result<(), scalar_cms::validations::ValidationError> {
         match self {
-            TestEnum::Struct { eeee } if eeee.is_empty() => Err(ValidationError::Single(
-                "eeee must have something in it".into(),
-            )),
+            TestEnum::Struct { eeee } if eeee.is_empty() => Err(ValidationError::Variant {
+                variant: "Struct".into(),
+                error: Box::new(ValidationError::Composite(vec![ErroredField {
+                    field: "eeee".into(),
+                    error: ValidationError::Single("eeee must have something in it".into()),
+                }])),
+            }),
             _ => Ok(()),
         }
     }
@@ -230,8 +291,25 @@ async fn main() -> anyhow::Result<()> {
     [AllTypes, Test2]
     );
 
+    let webhooks = Webhooks::new(env::var("WEBHOOK_URL").ok().map(|url| {
+        WebhookEndpoint::new(
+            url.parse().unwrap(),
+            env::var("WEBHOOK_SECRET").unwrap_or_default(),
+        )
+    }));
+    let events = EventBus::default();
+    webhooks.listen(&events);
+
+    let content_router = generate_content_routes!({ db: SurrealStore<Client> }, [AllTypes, Test2]);
+
     let app = Router::new()
-        .nest("/api", api_router)
+        .nest(
+            "/api",
+            api_router
+                .layer(Extension(events))
+                .layer(Extension(webhooks)),
+        )
+        .merge(content_router)
         .with_state(state)
         .fallback_service(
             ServeDir::new("scalar-cp/build").fallback(ServeFile::new("scalar-cp/build/index.html")),
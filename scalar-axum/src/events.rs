@@ -0,0 +1,87 @@
+use scalar_cms::{db::User, DateTime, Utc};
+use serde::Serialize;
+use tokio::sync::broadcast;
+use tokio_stream::{wrappers::BroadcastStream, Stream, StreamExt};
+
+/// Something that happened to a document, emitted by the handlers after it's been committed.
+#[derive(Serialize, Debug, Clone)]
+#[serde(tag = "kind", content = "info", rename_all = "snake_case")]
+pub enum DocumentEvent {
+    Drafted(EventInfo),
+    Published(EventInfo),
+    /// Published with a `publish_at` in the future. Nothing is emitted when it goes live,
+    /// so anything that cares should look at the item's `published_at`.
+    Scheduled(EventInfo),
+    Unpublished(EventInfo),
+    Deleted(EventInfo),
+}
+
+impl DocumentEvent {
+    #[must_use]
+    pub fn info(&self) -> &EventInfo {
+        match self {
+            Self::Drafted(info)
+            | Self::Published(info)
+            | Self::Scheduled(info)
+            | Self::Unpublished(info)
+            | Self::Deleted(info) => info,
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct EventInfo {
+    /// The [`scalar_cms::Document::IDENTIFIER`] of the document.
+    pub doc: &'static str,
+    pub id: String,
+    /// Who caused the event.
+    pub user: User,
+    pub timestamp: DateTime<Utc>,
+    /// The item as it was after the event happened, or right before it was deleted.
+    pub item: Option<serde_json::Value>,
+}
+
+/// Broadcasts [`DocumentEvent`]s to anything that's subscribed.
+///
+/// This is picked up by the handlers as an axum `Extension`. Subscribers that fall behind by more
+/// than the bus's capacity miss the oldest events.
+#[derive(Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<DocumentEvent>,
+}
+
+impl EventBus {
+    #[must_use]
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            sender: broadcast::Sender::new(capacity),
+        }
+    }
+
+    pub fn emit(&self, event: DocumentEvent) {
+        // an error only means nobody is listening right now
+        let _ = self.sender.send(event);
+    }
+
+    #[must_use]
+    pub fn subscribe(&self) -> broadcast::Receiver<DocumentEvent> {
+        self.sender.subscribe()
+    }
+
+    /// Subscribes as a stream, skipping over any events missed by falling behind.
+    pub fn stream(&self) -> impl Stream<Item = DocumentEvent> + Send + 'static {
+        BroadcastStream::new(self.subscribe()).filter_map(|event| match event {
+            Ok(event) => Some(event),
+            Err(e) => {
+                tracing::warn!(%e, "event subscriber fell behind");
+                None
+            }
+        })
+    }
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new(256)
+    }
+}
@@ -1,30 +1,47 @@
 use std::error::Error;
 
 use axum::{
-    extract::{FromRef, FromRequestParts, Path, Query, State},
+    extract::{Extension, FromRef, FromRequestParts, Path, Query, State},
     http::{self, StatusCode},
     response::IntoResponse,
     Json, Router,
 };
+use events::{DocumentEvent, EventBus, EventInfo};
 use scalar_cms::{
-    db::{Authenticated, Credentials, DatabaseFactory, User, ValidationContext},
-    validations::{Valid, ValidationError},
-    DatabaseConnection, DateTime, Document, Item, Schema, Utc,
+    db::{Action, Authenticated, Credentials, DatabaseFactory, User, ValidationContext},
+    editor_field::restore_readonly,
+    query::{Cursor, ListQuery, Page, QueryError, SortKey},
+    validations::{PathError, Valid, ValidationError},
+    DatabaseConnection, DateTime, Document, IncomingReference, Item, Revision, Schema, Utc,
 };
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use webhook::{Delivery, Webhooks};
 
+pub mod events;
 pub mod expire_map;
 #[cfg(feature = "img")]
 pub mod img;
 #[cfg(feature = "oidc")]
 pub mod oidc;
+pub mod openapi;
+pub mod webhook;
 
+/// Responds with every error in a [`ValidationError`], flattened into [`PathError`]s.
 pub struct ValidationFailiure(pub ValidationError);
 
 impl IntoResponse for ValidationFailiure {
+    fn into_response(self) -> axum::response::Response {
+        (StatusCode::UNPROCESSABLE_ENTITY, Json(self.0.flatten())).into_response()
+    }
+}
+
+/// Returned when a document can't be deleted or unpublished because other documents still reference it.
+pub struct ReferencedBy(pub Vec<IncomingReference>);
+
+impl IntoResponse for ReferencedBy {
     fn into_response(self) -> axum::response::Response {
         let mut response = Json(self.0).into_response();
-        *response.status_mut() = StatusCode::NOT_ACCEPTABLE;
+        *response.status_mut() = StatusCode::CONFLICT;
         response
     }
 }
@@ -80,7 +97,10 @@ macro_rules! crud_routes__ {
             .route(&drafts_path, ::axum::routing::put(::scalar_axum::update_draft::<$doc, $db>))
             .route(&drafts_path, ::axum::routing::delete(::scalar_axum::delete_draft::<$doc, $db>))
             .route(&id_path, ::axum::routing::delete(::scalar_axum::delete_doc::<$doc, $db>))
-            .route(&format!("{path}/schema"), ::axum::routing::get(::scalar_axum::get_schema::<$doc>));
+            .route(&format!("{id_path}/references"), ::axum::routing::get(::scalar_axum::get_incoming_references::<$doc, $db>))
+            .route(&format!("{path}/schema"), ::axum::routing::get(::scalar_axum::get_schema::<$doc>))
+            .route(&format!("{path}/json-schema"), ::axum::routing::get(::scalar_axum::get_json_schema::<$doc>))
+            .route(&format!("{path}/stale"), ::axum::routing::get(::scalar_axum::get_stale_docs::<$doc, $db>));
     };
 
     ($router:ident, $db:ty, $($doc:ty),+) => {
@@ -103,6 +123,23 @@ macro_rules! publish_routes__ {
     };
 }
 
+#[macro_export]
+#[doc(hidden)]
+macro_rules! revision_routes__ {
+    ($router:ident, $db:ty, $doc:ty) => {
+        let path = format!("/docs/{}/{{id}}/revisions", <$doc>::IDENTIFIER);
+        let revision_path = format!("{path}/{{revision_id}}");
+        $router = $router
+            .route(&path, ::axum::routing::get(::scalar_axum::get_revisions::<$doc, $db>))
+            .route(&revision_path, ::axum::routing::get(::scalar_axum::get_revision::<$doc, $db>))
+            .route(&format!("{revision_path}/restore"), ::axum::routing::post(::scalar_axum::restore_revision::<$doc, $db>));
+    };
+
+    ($router:ident, $db:ty, $($doc:ty),+) => {
+        $(::scalar_axum::revision_routes__!($router, $db, $doc);)*
+    };
+}
+
 #[macro_export]
 #[doc(hidden)]
 macro_rules! validate_routes__ {
@@ -120,21 +157,28 @@ macro_rules! validate_routes__ {
 #[cfg(feature = "oidc")]
 macro_rules! generate_routes {
     ({ db: $db:ty }, [$($doc:ty),+]) => {
+        ::scalar_axum::generate_routes!(@base { db: $db }, ::scalar_axum::openapi::OpenApi::new(), [$($doc),+])
+    };
+
+    (@base { db: $db:ty }, $openapi:expr, [$($doc:ty),+]) => {
         {
             let mut router = ::axum::Router::new();
             ::scalar_axum::crud_routes__!(router, $db, $($doc),+);
             ::scalar_axum::publish_routes__!(router, $db, $($doc),+);
+            ::scalar_axum::revision_routes__!(router, $db, $($doc),+);
             async fn get_docs() -> ::axum::Json<Vec<::scalar_cms::DocInfo>> {
                 ::axum::Json(vec![
-                    $(::scalar_cms::DocInfo {
-                        identifier: <$doc>::IDENTIFIER,
-                        title: <$doc>::TITLE
-                    }),+
+                    $(<$doc as ::scalar_cms::Document>::info()),+
                 ])
             }
             router = router.route("/docs", ::axum::routing::get(get_docs));
+            async fn get_openapi() -> impl ::axum::response::IntoResponse {
+                ::axum::Json($openapi$(.with_doc::<$doc>())+.to_json())
+            }
+            router = router.route("/openapi.json", ::axum::routing::get(get_openapi));
 
             router = router.route("/me", ::axum::routing::get(::scalar_axum::me::<$db>));
+            router = router.route("/webhooks/deliveries", ::axum::routing::get(::scalar_axum::get_webhook_deliveries::<$db>));
             router = ::scalar_axum::add_image_routes__::<_, $db>(router);
             router = router.route("/signin", ::axum::routing::post(::scalar_axum::signin::<$db>));
 
@@ -146,7 +190,7 @@ macro_rules! generate_routes {
 
     ({ db: $db:ty, oidc: { state: $oidc:ty, response_type: $response_type:ty, oidc_only: $only:expr } }, [$($doc:ty),+]) => {
         {
-            let mut router = ::scalar_axum::generate_routes!({db: $db}, [$($doc),+]);
+            let mut router = ::scalar_axum::generate_routes!(@base { db: $db }, ::scalar_axum::openapi::OpenApi::new().with_oidc(), [$($doc),+]);
 
             async fn is_auto() -> String {
                 bool::to_string(&$only)
@@ -168,27 +212,49 @@ macro_rules! generate_routes {
             let mut router = ::axum::Router::new();
             ::scalar_axum::crud_routes__!(router, $db, $($doc),+);
             ::scalar_axum::publish_routes__!(router, $db, $($doc),+);
+            ::scalar_axum::revision_routes__!(router, $db, $($doc),+);
             async fn get_docs() -> ::axum::Json<Vec<::scalar_cms::DocInfo>> {
                 ::axum::Json(vec![
-                    $(::scalar_cms::DocInfo {
-                        identifier: <$doc>::IDENTIFIER,
-                        title: <$doc>::TITLE
-                    }),+
+                    $(<$doc as ::scalar_cms::Document>::info()),+
                 ])
             }
             router = router.route("/docs", ::axum::routing::get(get_docs));
+            async fn get_openapi() -> impl ::axum::response::IntoResponse {
+                ::axum::Json(::scalar_axum::openapi::OpenApi::new()$(.with_doc::<$doc>())+.to_json())
+            }
+            router = router.route("/openapi.json", ::axum::routing::get(get_openapi));
 
             router = router.route("/me", ::axum::routing::get(::scalar_axum::me::<$db>));
+            router = router.route("/webhooks/deliveries", ::axum::routing::get(::scalar_axum::get_webhook_deliveries::<$db>));
             router = ::scalar_axum::add_image_routes__::<_, $db>(router);
             router = router.route("/signin", ::axum::routing::post(::scalar_axum::signin::<$db>));
 
-            ::scalar_axum::validate_routes__!(router, $($doc),+);
+            ::scalar_axum::validate_routes__!($db, router, $($doc),+);
 
             router
         }
     };
 }
 
+/// Generates unauthenticated, read only routes for published content, meant to be used by frontends.
+/// Every document gets a `/content/{doc}` route listing it's published items, and a `/content/{doc}/{id}` route.
+/// Both take an optional `locale` query parameter to resolve localized fields.
+#[macro_export]
+macro_rules! generate_content_routes {
+    ({ db: $db:ty }, [$($doc:ty),+]) => {
+        {
+            let mut router = ::axum::Router::new();
+            $(
+                let path = format!("/content/{}", <$doc>::IDENTIFIER);
+                router = router
+                    .route(&path, ::axum::routing::get(::scalar_axum::get_published_docs::<$doc, $db>))
+                    .route(&format!("{path}/{{id}}"), ::axum::routing::get(::scalar_axum::get_published_doc::<$doc, $db>));
+            )+
+            router
+        }
+    };
+}
+
 pub struct AuthenticatedConnection<F: DatabaseFactory>(Authenticated<F::Connection>);
 
 impl<F: DatabaseFactory, S> FromRequestParts<S> for AuthenticatedConnection<F>
@@ -247,6 +313,23 @@ where
     }
 }
 
+/// Makes sure the authenticated user's role allows them to perform an action on a document type.
+fn authorize<D: Document, DB: DatabaseConnection>(
+    conn: &Authenticated<DB>,
+    action: Action,
+) -> Result<(), StatusCode> {
+    if conn.can::<D>(action) {
+        Ok(())
+    } else {
+        tracing::debug!(
+            ?action,
+            doc = D::IDENTIFIER,
+            "user isn't allowed to do this"
+        );
+        Err(StatusCode::FORBIDDEN)
+    }
+}
+
 /// Endpoint that signs a user in with a username and password.
 ///
 /// # Errors
@@ -289,27 +372,38 @@ pub async fn get_schema<D: Document>() -> Json<Schema> {
     Json(D::schema())
 }
 
+/// Endpoint that describes a document's content as a JSON Schema, see [`Schema::to_json_schema`].
+#[allow(clippy::unused_async)]
+// this has to be async for axum
+pub async fn get_json_schema<D: Document>() -> Json<serde_json::Value> {
+    Json(D::schema().to_json_schema())
+}
+
 #[derive(Deserialize)]
 pub struct ValidateQueryParams {
     id: String,
 }
 
-/// Endpoint to validate a document.
+/// Endpoint to validate a document. Responds with the document's warnings if it's valid.
 ///
 /// # Errors
 ///
-/// This function will return an error if [`Document::validate`] returns an error.
+/// This function will return an error if [`Document::validate`] returns an error that isn't just warnings.
 #[allow(clippy::unused_async)]
 // this has to be async for axum
 pub async fn validate<F: DatabaseFactory, D: Document + Send + Sync>(
     AuthenticatedConnection(conn): AuthenticatedConnection<F>,
     Query(ValidateQueryParams { id }): Query<ValidateQueryParams>,
     Json(doc): Json<D>,
-) -> Result<(), (StatusCode, Json<ValidationError>)> {
+) -> Result<Json<Vec<PathError>>, axum::response::Response> {
+    authorize::<D, _>(&conn, Action::Read).map_err(IntoResponse::into_response)?;
+
     let ctx = ValidationContext::<'_, _, D>::new(conn.inner(), &id);
-    doc.validate(ctx)
-        .await
-        .map_err(|e| (StatusCode::UNPROCESSABLE_ENTITY, Json(e)))
+    match doc.validate(ctx).await {
+        Ok(()) => Ok(Json(Vec::new())),
+        Err(e) if e.has_errors() => Err(ValidationFailiure(e).into_response()),
+        Err(warnings) => Ok(Json(warnings.flatten())),
+    }
 }
 
 #[allow(clippy::unused_async)]
@@ -320,7 +414,24 @@ pub async fn me<F: DatabaseFactory>(
     Json(state.me())
 }
 
-/// Endpoint that updates a draft.
+/// Gets what's stored for a document, to compare writes against.
+async fn stored<D: Document + DeserializeOwned + Send, DB: DatabaseConnection>(
+    conn: &DB,
+    id: &str,
+) -> Result<Option<serde_json::Value>, StatusCode>
+where
+    DB::Error: 'static,
+{
+    conn.get_by_id::<D>(id)
+        .await
+        .map(|item| item.map(|item| item.inner))
+        .map_err(|e| {
+            tracing::error!(cause = &e as &dyn Error, "couldn't get stored document");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })
+}
+
+/// Endpoint that updates a draft. Changes to read only fields are undone, see [`scalar_cms::EditorField::readonly`].
 ///
 /// # Errors
 ///
@@ -328,20 +439,35 @@ pub async fn me<F: DatabaseFactory>(
 pub async fn update_draft<D: Document + Serialize + DeserializeOwned + Send, F: DatabaseFactory>(
     AuthenticatedConnection(state): AuthenticatedConnection<F>,
     Path(id): Path<String>,
-    Json(data): Json<serde_json::Value>,
+    events: Option<Extension<EventBus>>,
+    Json(mut data): Json<serde_json::Value>,
 ) -> Result<Json<Item<serde_json::Value>>, StatusCode>
 where
     <<F as scalar_cms::db::DatabaseFactory>::Connection as scalar_cms::DatabaseConnection>::Error:
         'static,
 {
-    Ok(Json(
-        DatabaseConnection::draft::<D>(&state, &id, data)
-            .await
-            .map_err(|e| {
-                tracing::error!(cause = &e as &dyn Error, "couldn't update draft");
-                StatusCode::INTERNAL_SERVER_ERROR
-            })?,
-    ))
+    authorize::<D, _>(&state, Action::Draft)?;
+
+    if let Some(stored) = stored::<D, _>(state.inner(), &id).await? {
+        restore_readonly(D::fields(), &mut data, &stored);
+    }
+
+    let item = DatabaseConnection::draft::<D>(&state, &id, data)
+        .await
+        .map_err(|e| {
+            tracing::error!(cause = &e as &dyn Error, "couldn't update draft");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    if let Some(Extension(events)) = events {
+        events.emit(DocumentEvent::Drafted(event_info::<D, _>(
+            &state,
+            id,
+            Some(to_value(&item)),
+        )));
+    }
+
+    Ok(Json(item))
 }
 
 /// Endpoint that deletes a draft.
@@ -352,42 +478,143 @@ where
 pub async fn delete_draft<D: Document + Serialize + DeserializeOwned + Send, F: DatabaseFactory>(
     AuthenticatedConnection(state): AuthenticatedConnection<F>,
     Path(id): Path<String>,
+    events: Option<Extension<EventBus>>,
 ) -> Result<Json<Item<serde_json::Value>>, StatusCode>
 where
     <<F as scalar_cms::db::DatabaseFactory>::Connection as scalar_cms::DatabaseConnection>::Error:
         'static,
 {
-    Ok(Json(
-        DatabaseConnection::delete_draft::<D>(&state, &id)
-            .await
-            .map_err(|e| {
-                tracing::error!(cause = &e as &dyn Error, "couldn't delete draft");
-                StatusCode::INTERNAL_SERVER_ERROR
-            })?,
-    ))
+    authorize::<D, _>(&state, Action::Draft)?;
+
+    let item = DatabaseConnection::delete_draft::<D>(&state, &id)
+        .await
+        .map_err(|e| {
+            tracing::error!(cause = &e as &dyn Error, "couldn't delete draft");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    if let Some(Extension(events)) = events {
+        // without the draft, the item is whatever was published, if anything
+        let item = state.inner().get_by_id::<D>(&id).await.unwrap_or_else(|e| {
+            tracing::error!(
+                cause = &e as &dyn Error,
+                "couldn't get document after deleting it's draft"
+            );
+            None
+        });
+        events.emit(DocumentEvent::Drafted(event_info::<D, _>(
+            &state,
+            id,
+            item.as_ref().map(to_value),
+        )));
+    }
+
+    Ok(Json(item))
+}
+
+#[derive(Deserialize)]
+pub struct ForceParams {
+    /// Skips the check for documents referencing this one.
+    #[serde(default)]
+    force: bool,
 }
 
-/// Endpoint that deletes a docs.
+/// Makes sure nothing references a document before it goes away.
+/// When `published_only` is set, only references from published documents are considered.
+///
+/// This is best-effort: it runs as its own query before the document goes away, so a reference
+/// written in between isn't caught and ends up pointing at nothing, like it would with `force`.
+async fn ensure_unreferenced<D: Document + Send, DB: DatabaseConnection>(
+    conn: &DB,
+    id: &str,
+    published_only: bool,
+) -> Result<(), axum::response::Response>
+where
+    DB::Error: 'static,
+{
+    let references = conn
+        .get_incoming_references::<D>(id)
+        .await
+        .map_err(|e| {
+            tracing::error!(cause = &e as &dyn Error, "couldn't get incoming references");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        })?
+        .into_iter()
+        .filter(|reference| !published_only || reference.published)
+        .collect::<Vec<_>>();
+
+    if references.is_empty() {
+        Ok(())
+    } else {
+        Err(ReferencedBy(references).into_response())
+    }
+}
+
+/// Endpoint that deletes a docs. Fails with a conflict listing the referencing documents if
+/// anything still references it, unless `force` is set.
 ///
 /// # Errors
 ///
-/// This function will return an error if updating the draft fails, usually by database errors.
+/// This function will return an error if the document is still referenced, or deleting it fails, usually by database errors.
 pub async fn delete_doc<D: Document + Serialize + DeserializeOwned + Send, F: DatabaseFactory>(
     AuthenticatedConnection(state): AuthenticatedConnection<F>,
     Path(id): Path<String>,
-) -> Result<Json<Option<Item<serde_json::Value>>>, StatusCode>
+    Query(ForceParams { force }): Query<ForceParams>,
+    events: Option<Extension<EventBus>>,
+) -> Result<Json<Option<Item<serde_json::Value>>>, axum::response::Response>
 where
     <<F as scalar_cms::db::DatabaseFactory>::Connection as scalar_cms::DatabaseConnection>::Error:
         'static,
 {
-    Ok(Json(
-        DatabaseConnection::delete::<D>(&state, &id)
-            .await
-            .map_err(|e| {
-                tracing::error!(cause = &e as &dyn Error, "couldn't delete draft");
-                StatusCode::INTERNAL_SERVER_ERROR
-            })?,
-    ))
+    authorize::<D, _>(&state, Action::Delete).map_err(IntoResponse::into_response)?;
+
+    if !force {
+        ensure_unreferenced::<D, _>(state.inner(), &id, false).await?;
+    }
+
+    let item = DatabaseConnection::delete::<D>(&state, &id)
+        .await
+        .map_err(|e| {
+            tracing::error!(cause = &e as &dyn Error, "couldn't delete draft");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        })?;
+
+    if let Some(Extension(events)) = events {
+        events.emit(DocumentEvent::Deleted(event_info::<D, _>(
+            &state,
+            id,
+            item.as_ref().map(to_value),
+        )));
+    }
+
+    Ok(Json(item))
+}
+
+/// Endpoint that lists every document referencing a document.
+///
+/// # Errors
+///
+/// This function will return an error if the database fails to look up references for whatever reason.
+pub async fn get_incoming_references<D: Document + Send, F: DatabaseFactory>(
+    AuthenticatedConnection(state): AuthenticatedConnection<F>,
+    Path(id): Path<String>,
+) -> Result<Json<Vec<IncomingReference>>, StatusCode>
+where
+    <<F as scalar_cms::db::DatabaseFactory>::Connection as scalar_cms::DatabaseConnection>::Error:
+        'static,
+{
+    authorize::<D, _>(&state, Action::Read)?;
+
+    let references = state
+        .inner()
+        .get_incoming_references::<D>(&id)
+        .await
+        .map_err(|e| {
+            tracing::error!(cause = &e as &dyn Error, "couldn't get incoming references");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(references))
 }
 
 #[derive(Deserialize)]
@@ -396,7 +623,8 @@ pub struct PublishParams<D> {
     doc: D,
 }
 
-/// Endpoint that publishes the document, if it's valid.
+/// Endpoint that publishes the document, if it's valid. Warnings don't stop it from being published, they're returned instead.
+/// Changes to read only fields are undone, see [`scalar_cms::EditorField::readonly`].
 ///
 /// # Errors
 ///
@@ -407,76 +635,342 @@ pub async fn publish_doc<
 >(
     Path(id): Path<String>,
     AuthenticatedConnection(state): AuthenticatedConnection<F>,
+    events: Option<Extension<EventBus>>,
     Json(PublishParams { publish_at, doc }): Json<PublishParams<D>>,
-) -> Result<(), StatusCode>
+) -> Result<Json<Vec<PathError>>, axum::response::Response>
 where
     <<F as scalar_cms::db::DatabaseFactory>::Connection as scalar_cms::DatabaseConnection>::Error:
         'static,
 {
+    authorize::<D, _>(&state, Action::Publish).map_err(IntoResponse::into_response)?;
+
+    let doc = match stored::<D, _>(state.inner(), &id)
+        .await
+        .map_err(IntoResponse::into_response)?
+    {
+        Some(stored) => {
+            // what's stored might not fit the current schema, until it's migrated
+            let restored = serde_json::to_value(&doc).and_then(|mut data| {
+                restore_readonly(D::fields(), &mut data, &stored);
+                serde_json::from_value(data)
+            });
+            restored.map_err(|e| {
+                tracing::error!(
+                    cause = &e as &dyn Error,
+                    "couldn't restore read only fields"
+                );
+                StatusCode::INTERNAL_SERVER_ERROR.into_response()
+            })?
+        }
+        None => doc,
+    };
+
     let ctx = ValidationContext::new(state.inner(), &id);
-    DatabaseConnection::publish(
-        &state,
-        &id,
-        publish_at,
-        Valid::new(doc, ctx)
-            .await
-            .map_err(|_| StatusCode::UNPROCESSABLE_ENTITY)?,
-    )
-    .await
-    .map_err(|e| {
-        tracing::error!(cause = &e as &dyn Error, "couldn't publish document");
-        StatusCode::INTERNAL_SERVER_ERROR
-    })?;
+    let valid = Valid::new(doc, ctx)
+        .await
+        .map_err(|e| ValidationFailiure(e).into_response())?;
+    let warnings = valid.warnings().to_vec();
+    let item = DatabaseConnection::publish(&state, &id, publish_at, valid)
+        .await
+        .map_err(|e| {
+            tracing::error!(cause = &e as &dyn Error, "couldn't publish document");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        })?;
 
-    Ok(())
+    if let Some(Extension(events)) = events {
+        let info = event_info::<D, _>(&state, id, Some(to_value(&item)));
+        events.emit(if item.published_at.is_some_and(|at| at > Utc::now()) {
+            DocumentEvent::Scheduled(info)
+        } else {
+            DocumentEvent::Published(info)
+        });
+    }
+
+    Ok(Json(warnings))
 }
 
-/// Endpoint that unpublishes the document, and puts the current version as a draft.
+/// Endpoint that unpublishes the document, and puts the current version as a draft. Fails with a conflict
+/// listing the referencing documents if any published documents still reference it, unless `force` is set.
 ///
 /// # Errors
 ///
-/// This function will return an error if the database fails to commit the publish.
+/// This function will return an error if the document is still referenced, or the database fails to commit the unpublish.
 pub async fn unpublish_doc<
     D: Document + Serialize + DeserializeOwned + Send + 'static,
     F: DatabaseFactory,
 >(
     Path(id): Path<String>,
     AuthenticatedConnection(state): AuthenticatedConnection<F>,
-) -> Result<(), StatusCode>
+    Query(ForceParams { force }): Query<ForceParams>,
+    events: Option<Extension<EventBus>>,
+) -> Result<(), axum::response::Response>
 where
     <<F as scalar_cms::db::DatabaseFactory>::Connection as scalar_cms::DatabaseConnection>::Error:
         'static,
 {
+    authorize::<D, _>(&state, Action::Unpublish).map_err(IntoResponse::into_response)?;
+
+    if !force {
+        ensure_unreferenced::<D, _>(state.inner(), &id, true).await?;
+    }
+
     DatabaseConnection::unpublish::<D>(&state, &id)
         .await
         .map_err(|e| {
             tracing::error!(cause = &e as &dyn Error, "couldn't unpublish document");
-            StatusCode::INTERNAL_SERVER_ERROR
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
         })?;
 
+    if let Some(Extension(events)) = events {
+        // the unpublished version is now the draft
+        // it's already unpublished, so a failed lookup shouldn't fail the request
+        let item = state.inner().get_by_id::<D>(&id).await.unwrap_or_else(|e| {
+            tracing::error!(
+                cause = &e as &dyn Error,
+                "couldn't get unpublished document"
+            );
+            None
+        });
+        events.emit(DocumentEvent::Unpublished(event_info::<D, _>(
+            &state,
+            id,
+            item.as_ref().map(to_value),
+        )));
+    }
+
     Ok(())
 }
 
-/// Endpoint that gets all documents of a certain type.
+fn to_value<T: Serialize>(item: &Item<T>) -> serde_json::Value {
+    serde_json::to_value(item).expect("documents should always serialize")
+}
+
+fn event_info<D: Document, DB: DatabaseConnection>(
+    conn: &Authenticated<DB>,
+    id: String,
+    item: Option<serde_json::Value>,
+) -> EventInfo {
+    EventInfo {
+        doc: D::IDENTIFIER,
+        id,
+        user: conn.me(),
+        timestamp: Utc::now(),
+        item,
+    }
+}
+
+/// Endpoint that lists the most recent webhook deliveries. Only admins can see these.
 ///
 /// # Errors
 ///
-/// This function will return an error if the database fails to get a document for whatever reason.
+/// This function will return an error if the user isn't an admin.
+#[allow(clippy::unused_async)]
+// this has to be async for axum
+pub async fn get_webhook_deliveries<F: DatabaseFactory>(
+    AuthenticatedConnection(state): AuthenticatedConnection<F>,
+    webhooks: Option<Extension<Webhooks>>,
+) -> Result<Json<Vec<Delivery>>, StatusCode> {
+    if !state.me().admin() {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    Ok(Json(
+        webhooks
+            .map(|Extension(webhooks)| webhooks.deliveries())
+            .unwrap_or_default(),
+    ))
+}
+
+#[derive(Deserialize)]
+pub struct ListParams {
+    /// A JSON encoded filter expression.
+    filter: Option<String>,
+    /// A comma separated list of fields to sort by. Fields prefixed with `-` are sorted in descending order.
+    sort: Option<String>,
+    cursor: Option<String>,
+    limit: Option<usize>,
+}
+
+impl ListParams {
+    fn into_query<D: Document>(self) -> Result<ListQuery, QueryError> {
+        let query = ListQuery {
+            filter: self
+                .filter
+                .map(|filter| serde_json::from_str(&filter))
+                .transpose()
+                .map_err(QueryError::MalformedFilter)?,
+            sort: self
+                .sort
+                .map(|sort| SortKey::parse_list::<D>(&sort))
+                .transpose()?
+                .unwrap_or_default(),
+            cursor: self.cursor.as_deref().map(Cursor::decode).transpose()?,
+            limit: self.limit,
+        };
+        query.check::<D>()?;
+
+        Ok(query)
+    }
+}
+
+/// Endpoint that gets all documents of a certain type, optionally filtered, sorted and paginated.
+///
+/// # Errors
+///
+/// This function will return an error if the query is malformed, or the database fails to get a document for whatever reason.
 pub async fn get_all_docs<D: Document + Serialize + DeserializeOwned + Send, F: DatabaseFactory>(
     AuthenticatedConnection(state): AuthenticatedConnection<F>,
-) -> Result<Json<Vec<Item<serde_json::Value>>>, StatusCode>
+    Query(params): Query<ListParams>,
+) -> Result<Json<Page<Item<serde_json::Value>>>, (StatusCode, String)>
 where
     <<F as scalar_cms::db::DatabaseFactory>::Connection as scalar_cms::DatabaseConnection>::Error:
         'static,
 {
-    let items = state.inner().get_all::<D>().await.map_err(|e| {
+    authorize::<D, _>(&state, Action::Read).map_err(|status| (status, String::new()))?;
+
+    let query = params
+        .into_query::<D>()
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+
+    let page = state.inner().list::<D>(query).await.map_err(|e| {
         tracing::error!(cause = &e as &dyn Error, "couldn't get documents");
+        (StatusCode::INTERNAL_SERVER_ERROR, String::new())
+    })?;
+
+    Ok(Json(page))
+}
+
+/// Endpoint that gets every document last written under an older schema.
+///
+/// # Errors
+///
+/// This function will return an error if the database fails to get the documents for whatever reason.
+pub async fn get_stale_docs<D: Document + Send, F: DatabaseFactory>(
+    AuthenticatedConnection(state): AuthenticatedConnection<F>,
+) -> Result<Json<Vec<Item<serde_json::Value>>>, StatusCode>
+where
+    <<F as scalar_cms::db::DatabaseFactory>::Connection as scalar_cms::DatabaseConnection>::Error:
+        'static,
+{
+    authorize::<D, _>(&state, Action::Read)?;
+
+    let items = state.inner().get_stale::<D>().await.map_err(|e| {
+        tracing::error!(cause = &e as &dyn Error, "couldn't get stale documents");
         StatusCode::INTERNAL_SERVER_ERROR
     })?;
 
     Ok(Json(items))
 }
 
+#[derive(Deserialize)]
+pub struct ContentParams {
+    /// Resolves localized fields to this locale, see [`scalar_cms::types::Localized`].
+    locale: Option<String>,
+}
+
+fn localize_item<D: Document + Serialize>(
+    item: Item<D>,
+    locale: Option<&str>,
+) -> Item<serde_json::Value> {
+    let mut inner = serde_json::to_value(item.inner).expect("documents should always serialize");
+    if let Some(locale) = locale {
+        scalar_cms::types::localize(&mut inner, D::fields(), locale);
+    }
+
+    Item {
+        id: item.id,
+        created_at: item.created_at,
+        modified_at: item.modified_at,
+        published_at: item.published_at,
+        schema_hash: item.schema_hash,
+        inner,
+    }
+}
+
+/// Public endpoint that gets every published document of a certain type.
+///
+/// # Errors
+///
+/// This function will return an error if the database fails to get a document for whatever reason.
+///
+/// # Panics
+///
+/// Panics if a document fails to serialize.
+pub async fn get_published_docs<
+    D: Document + Serialize + DeserializeOwned + Send,
+    F: DatabaseFactory,
+>(
+    State(factory): State<F>,
+    Query(ContentParams { locale }): Query<ContentParams>,
+) -> Result<Json<Vec<Item<serde_json::Value>>>, StatusCode>
+where
+    <F as scalar_cms::db::DatabaseFactory>::Error: 'static,
+    <<F as scalar_cms::db::DatabaseFactory>::Connection as scalar_cms::DatabaseConnection>::Error:
+        'static,
+{
+    let connection = factory.init().await.map_err(|e| {
+        tracing::error!(
+            cause = &e as &dyn Error,
+            "failed to init a database connection"
+        );
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let items = connection.get_all_published::<D>().await.map_err(|e| {
+        tracing::error!(cause = &e as &dyn Error, "couldn't get published documents");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(
+        items
+            .into_iter()
+            .map(|item| localize_item(item, locale.as_deref()))
+            .collect(),
+    ))
+}
+
+/// Public endpoint that gets a published document by id.
+///
+/// # Errors
+///
+/// This function will return an error if the document isn't published, or some other database error occurs.
+///
+/// # Panics
+///
+/// Panics if the document fails to serialize.
+pub async fn get_published_doc<
+    D: Document + Serialize + DeserializeOwned + Send,
+    F: DatabaseFactory,
+>(
+    State(factory): State<F>,
+    Path(id): Path<String>,
+    Query(ContentParams { locale }): Query<ContentParams>,
+) -> Result<Json<Item<serde_json::Value>>, StatusCode>
+where
+    <F as scalar_cms::db::DatabaseFactory>::Error: 'static,
+    <<F as scalar_cms::db::DatabaseFactory>::Connection as scalar_cms::DatabaseConnection>::Error:
+        'static,
+{
+    let connection = factory.init().await.map_err(|e| {
+        tracing::error!(
+            cause = &e as &dyn Error,
+            "failed to init a database connection"
+        );
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let item = connection
+        .get_published::<D>(&id)
+        .await
+        .map_err(|e| {
+            tracing::error!(cause = &e as &dyn Error, "couldn't get published document");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(localize_item(item, locale.as_deref())))
+}
+
 /// Endpoint that gets a document by id.
 ///
 /// # Errors
@@ -490,6 +984,8 @@ where
     <<F as scalar_cms::db::DatabaseFactory>::Connection as scalar_cms::DatabaseConnection>::Error:
         'static,
 {
+    authorize::<D, _>(&state, Action::Read)?;
+
     state
         .inner()
         .get_by_id::<D>(id.as_str())
@@ -501,3 +997,88 @@ where
         .map(Json)
         .ok_or(StatusCode::NOT_FOUND)
 }
+
+/// Endpoint that lists every revision of a document.
+///
+/// # Errors
+///
+/// This function will return an error if the database fails to get the revisions.
+pub async fn get_revisions<D: Document + Send, F: DatabaseFactory>(
+    AuthenticatedConnection(state): AuthenticatedConnection<F>,
+    Path(id): Path<String>,
+) -> Result<Json<Vec<Revision<serde_json::Value>>>, StatusCode>
+where
+    <<F as scalar_cms::db::DatabaseFactory>::Connection as scalar_cms::DatabaseConnection>::Error:
+        'static,
+{
+    authorize::<D, _>(&state, Action::Read)?;
+
+    let revisions = state.inner().get_revisions::<D>(&id).await.map_err(|e| {
+        tracing::error!(cause = &e as &dyn Error, "couldn't get revisions");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(revisions))
+}
+
+/// Endpoint that gets a single revision of a document.
+///
+/// # Errors
+///
+/// This function will return an error if the revision isn't found, or some other database error occurs.
+pub async fn get_revision<D: Document + Send, F: DatabaseFactory>(
+    AuthenticatedConnection(state): AuthenticatedConnection<F>,
+    Path((id, revision_id)): Path<(String, String)>,
+) -> Result<Json<Revision<serde_json::Value>>, StatusCode>
+where
+    <<F as scalar_cms::db::DatabaseFactory>::Connection as scalar_cms::DatabaseConnection>::Error:
+        'static,
+{
+    authorize::<D, _>(&state, Action::Read)?;
+
+    state
+        .inner()
+        .get_revision::<D>(&id, &revision_id)
+        .await
+        .map_err(|e| {
+            tracing::error!(cause = &e as &dyn Error, "couldn't get revision");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Endpoint that restores a revision into the document's draft.
+///
+/// # Errors
+///
+/// This function will return an error if the revision isn't found, or some other database error occurs.
+pub async fn restore_revision<D: Document + Send, F: DatabaseFactory>(
+    AuthenticatedConnection(state): AuthenticatedConnection<F>,
+    Path((id, revision_id)): Path<(String, String)>,
+    events: Option<Extension<EventBus>>,
+) -> Result<Json<Item<serde_json::Value>>, StatusCode>
+where
+    <<F as scalar_cms::db::DatabaseFactory>::Connection as scalar_cms::DatabaseConnection>::Error:
+        'static,
+{
+    authorize::<D, _>(&state, Action::Draft)?;
+
+    let item = DatabaseConnection::restore_revision::<D>(&state, &id, &revision_id)
+        .await
+        .map_err(|e| {
+            tracing::error!(cause = &e as &dyn Error, "couldn't restore revision");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    if let Some(Extension(events)) = events {
+        events.emit(DocumentEvent::Drafted(event_info::<D, _>(
+            &state,
+            id,
+            Some(to_value(&item)),
+        )));
+    }
+
+    Ok(Json(item))
+}
@@ -0,0 +1,741 @@
+use scalar_cms::{
+    query::{DEFAULT_LIMIT, MAX_LIMIT},
+    Document,
+};
+use serde_json::{json, Map, Value};
+
+/// The `OpenAPI` version of the generated document.
+pub const OPENAPI_VERSION: &str = "3.1.0";
+
+/// Describes the routes mounted by `generate_routes!` as an `OpenAPI` 3.1 document.
+///
+/// Every document type gets a component schema for it's content (see [`scalar_cms::Schema::to_json_schema`]),
+/// which the request and response bodies of it's routes refer to.
+#[derive(Debug, Clone)]
+pub struct OpenApi {
+    title: String,
+    version: String,
+    docs: Vec<(&'static str, Value)>,
+    oidc: bool,
+}
+
+impl Default for OpenApi {
+    fn default() -> Self {
+        Self {
+            title: "scalar".into(),
+            version: env!("CARGO_PKG_VERSION").into(),
+            docs: Vec::new(),
+            oidc: false,
+        }
+    }
+}
+
+impl OpenApi {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the title and version in the document's `info`. Defaults to scalar's.
+    #[must_use]
+    pub fn with_info(mut self, title: impl Into<String>, version: impl Into<String>) -> Self {
+        self.title = title.into();
+        self.version = version.into();
+        self
+    }
+
+    #[must_use]
+    pub fn with_doc<D: Document>(mut self) -> Self {
+        let mut content = D::schema().to_json_schema();
+        if let Value::Object(content) = &mut content {
+            // component schemas use the document's dialect
+            content.remove("$schema");
+        }
+        self.docs.push((D::IDENTIFIER, content));
+        self
+    }
+
+    /// Includes the OIDC sign in routes.
+    #[must_use]
+    pub fn with_oidc(mut self) -> Self {
+        self.oidc = true;
+        self
+    }
+
+    #[must_use]
+    pub fn to_json(&self) -> Value {
+        let mut paths = Map::new();
+        let mut schemas = common_schemas();
+
+        for (identifier, content) in &self.docs {
+            schemas.insert(content_name(identifier), content.clone());
+            schemas.insert(
+                item_name(identifier),
+                json!({
+                    "allOf": [
+                        reference("Item"),
+                        { "properties": { "content": reference(&content_name(identifier)) } },
+                    ],
+                }),
+            );
+            doc_paths(&mut paths, identifier);
+        }
+
+        paths.insert(
+            "/docs".into(),
+            json!({
+                "get": {
+                    "summary": "List every document type",
+                    "tags": ["meta"],
+                    "responses": { "200": json_response("The document types", array_of(reference("DocInfo"))) },
+                },
+            }),
+        );
+        paths.insert(
+            "/me".into(),
+            json!({
+                "get": authenticated(json!({
+                    "summary": "Get the signed in user",
+                    "tags": ["auth"],
+                    "responses": { "200": json_response("The signed in user", reference("User")) },
+                })),
+            }),
+        );
+        paths.insert(
+            "/webhooks/deliveries".into(),
+            json!({
+                "get": authenticated(json!({
+                    "summary": "List the most recent webhook deliveries, newest first",
+                    "tags": ["webhooks"],
+                    "responses": { "200": json_response("The deliveries", array_of(reference("Delivery"))) },
+                })),
+            }),
+        );
+        paths.insert(
+            "/signin".into(),
+            json!({
+                "post": {
+                    "summary": "Sign in with an email and password",
+                    "tags": ["auth"],
+                    "requestBody": json_body(reference("Credentials")),
+                    "responses": {
+                        "200": text_response("A token to use as a bearer token"),
+                        "401": status("The credentials are wrong"),
+                        "500": status("Something went wrong"),
+                    },
+                },
+            }),
+        );
+        paths.insert(
+            "/openapi.json".into(),
+            json!({
+                "get": {
+                    "summary": "Get this document",
+                    "tags": ["meta"],
+                    "responses": { "200": json_response("An OpenAPI document", json!({ "type": "object" })) },
+                },
+            }),
+        );
+
+        if cfg!(feature = "img") {
+            upload_paths(&mut paths);
+        }
+        if self.oidc {
+            oidc_paths(&mut paths);
+        }
+
+        json!({
+            "openapi": OPENAPI_VERSION,
+            "info": { "title": self.title, "version": self.version },
+            "paths": paths,
+            "components": {
+                "schemas": schemas,
+                "securitySchemes": {
+                    "bearer": {
+                        "type": "http",
+                        "scheme": "bearer",
+                        "description": "A token from `/signin`",
+                    },
+                },
+            },
+        })
+    }
+}
+
+fn content_name(identifier: &str) -> String {
+    format!("docs.{identifier}")
+}
+
+fn item_name(identifier: &str) -> String {
+    format!("docs.{identifier}.Item")
+}
+
+fn reference(name: &str) -> Value {
+    json!({ "$ref": format!("#/components/schemas/{name}") })
+}
+
+fn array_of(items: Value) -> Value {
+    let mut schema = json!({ "type": "array" });
+    schema["items"] = items;
+    schema
+}
+
+fn nullable(schema: Value) -> Value {
+    let mut nullable = json!({ "anyOf": [null, { "type": "null" }] });
+    nullable["anyOf"][0] = schema;
+    nullable
+}
+
+fn status(description: &str) -> Value {
+    json!({ "description": description })
+}
+
+fn json_response(description: &str, schema: Value) -> Value {
+    let mut response = json!({
+        "description": description,
+        "content": { "application/json": {} },
+    });
+    response["content"]["application/json"]["schema"] = schema;
+    response
+}
+
+fn text_response(description: &str) -> Value {
+    json!({
+        "description": description,
+        "content": { "text/plain": { "schema": { "type": "string" } } },
+    })
+}
+
+fn json_body(schema: Value) -> Value {
+    let mut body = json!({
+        "required": true,
+        "content": { "application/json": {} },
+    });
+    body["content"]["application/json"]["schema"] = schema;
+    body
+}
+
+fn path_param(name: &str) -> Value {
+    json!({ "name": name, "in": "path", "required": true, "schema": { "type": "string" } })
+}
+
+fn query_param(name: &str, schema: Value, description: &str) -> Value {
+    let mut param = json!({ "name": name, "in": "query", "description": description });
+    param["schema"] = schema;
+    param
+}
+
+fn force_param() -> Value {
+    query_param(
+        "force",
+        json!({ "type": "boolean", "default": false }),
+        "Skips the check for documents referencing this one",
+    )
+}
+
+/// Marks an operation as needing a bearer token, along with the responses every authenticated route has.
+fn authenticated(mut operation: Value) -> Value {
+    operation["security"] = json!([{ "bearer": [] }]);
+    let responses = operation["responses"]
+        .as_object_mut()
+        .expect("responses is always an object");
+    responses.insert("401".into(), status("The token is missing or invalid"));
+    responses.insert(
+        "403".into(),
+        status("The user's role doesn't allow this action"),
+    );
+    responses.insert("500".into(), status("Something went wrong"));
+    operation
+}
+
+#[allow(clippy::too_many_lines)]
+fn doc_paths(paths: &mut Map<String, Value>, identifier: &str) {
+    let path = format!("/docs/{identifier}");
+    let id_path = format!("{path}/{{id}}");
+    let tags = json!([identifier]);
+    let content = reference(&content_name(identifier));
+    let item = reference(&item_name(identifier));
+    let conflict = json_response(
+        "Other documents still reference this one",
+        array_of(reference("IncomingReference")),
+    );
+
+    paths.insert(
+        path.clone(),
+        json!({
+            "get": authenticated(json!({
+                "summary": "List documents",
+                "tags": tags,
+                "parameters": [
+                    query_param("filter", json!({ "type": "string" }), "A JSON encoded filter expression"),
+                    query_param(
+                        "sort",
+                        json!({ "type": "string" }),
+                        "A comma separated list of fields to sort by, prefixed with `-` to sort in descending order",
+                    ),
+                    query_param("cursor", json!({ "type": "string" }), "The cursor of the page to get"),
+                    query_param(
+                        "limit",
+                        json!({ "type": "integer", "minimum": 1, "maximum": MAX_LIMIT, "default": DEFAULT_LIMIT }),
+                        "The maximum number of items to get",
+                    ),
+                ],
+                "responses": {
+                    "200": json_response("A page of documents", json!({
+                        "type": "object",
+                        "properties": {
+                            "items": array_of(item.clone()),
+                            "next_cursor": { "type": ["string", "null"] },
+                        },
+                        "required": ["items", "next_cursor"],
+                    })),
+                    "400": text_response("The query is malformed"),
+                },
+            })),
+        }),
+    );
+    paths.insert(
+        id_path.clone(),
+        json!({
+            "parameters": [path_param("id")],
+            "get": authenticated(json!({
+                "summary": "Get a document, with it's draft if there is one",
+                "tags": tags,
+                "responses": {
+                    "200": json_response("The document", item.clone()),
+                    "404": status("The document doesn't exist"),
+                },
+            })),
+            "delete": authenticated(json!({
+                "summary": "Delete a document",
+                "tags": tags,
+                "parameters": [force_param()],
+                "responses": {
+                    "200": json_response("The document as it was before it was deleted", nullable(item.clone())),
+                    "409": conflict.clone(),
+                },
+            })),
+        }),
+    );
+    paths.insert(
+        format!("{id_path}/drafts"),
+        json!({
+            "parameters": [path_param("id")],
+            "put": authenticated(json!({
+                "summary": "Update a document's draft",
+                "tags": tags,
+                "requestBody": json_body(json!({
+                    "type": "object",
+                    "description": "The draft's content, which doesn't have to be valid yet",
+                })),
+                "responses": { "200": json_response("The document with it's new draft", item.clone()) },
+            })),
+            "delete": authenticated(json!({
+                "summary": "Discard a document's draft",
+                "tags": tags,
+                "responses": { "200": json_response("The document without it's draft", item.clone()) },
+            })),
+        }),
+    );
+    paths.insert(
+        format!("{id_path}/references"),
+        json!({
+            "parameters": [path_param("id")],
+            "get": authenticated(json!({
+                "summary": "List the documents referencing a document",
+                "tags": tags,
+                "responses": {
+                    "200": json_response("The references", array_of(reference("IncomingReference"))),
+                },
+            })),
+        }),
+    );
+    paths.insert(
+        format!("{id_path}/publish"),
+        json!({
+            "parameters": [path_param("id")],
+            "post": authenticated(json!({
+                "summary": "Publish a document, if it's valid",
+                "tags": tags,
+                "requestBody": json_body(json!({
+                    "type": "object",
+                    "properties": {
+                        "publish_at": {
+                            "type": ["string", "null"],
+                            "format": "date-time",
+                            "description": "When to publish the document, defaults to now",
+                        },
+                        "doc": content.clone(),
+                    },
+                    "required": ["doc"],
+                })),
+                "responses": {
+                    "200": json_response("The document was published, with it's warnings", array_of(reference("PathError"))),
+                    "422": json_response("Why the document is invalid", array_of(reference("PathError"))),
+                },
+            })),
+            "delete": authenticated(json!({
+                "summary": "Unpublish a document, putting it's current version in it's draft",
+                "tags": tags,
+                "parameters": [force_param()],
+                "responses": {
+                    "200": status("The document was unpublished"),
+                    "409": conflict,
+                },
+            })),
+        }),
+    );
+
+    let revisions_path = format!("{id_path}/revisions");
+    let revision_path = format!("{revisions_path}/{{revision_id}}");
+    let revision = json!({
+        "allOf": [
+            reference("Revision"),
+            { "properties": { "content": content.clone() } },
+        ],
+    });
+    paths.insert(
+        revisions_path,
+        json!({
+            "parameters": [path_param("id")],
+            "get": authenticated(json!({
+                "summary": "List every revision of a document",
+                "tags": tags,
+                "responses": { "200": json_response("The revisions", array_of(revision.clone())) },
+            })),
+        }),
+    );
+    paths.insert(
+        revision_path.clone(),
+        json!({
+            "parameters": [path_param("id"), path_param("revision_id")],
+            "get": authenticated(json!({
+                "summary": "Get a revision of a document",
+                "tags": tags,
+                "responses": {
+                    "200": json_response("The revision", revision),
+                    "404": status("The revision doesn't exist"),
+                },
+            })),
+        }),
+    );
+    paths.insert(
+        format!("{revision_path}/restore"),
+        json!({
+            "parameters": [path_param("id"), path_param("revision_id")],
+            "post": authenticated(json!({
+                "summary": "Restore a revision into a document's draft",
+                "tags": tags,
+                "responses": {
+                    "200": json_response("The document with the restored draft", item.clone()),
+                    "404": status("The revision doesn't exist"),
+                },
+            })),
+        }),
+    );
+
+    paths.insert(
+        format!("{path}/validate"),
+        json!({
+            "post": authenticated(json!({
+                "summary": "Validate a document without saving it",
+                "tags": tags,
+                "parameters": [
+                    { "name": "id", "in": "query", "required": true, "schema": { "type": "string" } },
+                ],
+                "requestBody": json_body(content),
+                "responses": {
+                    "200": json_response("The document is valid, with it's warnings", array_of(reference("PathError"))),
+                    "422": json_response("Why the document is invalid", array_of(reference("PathError"))),
+                },
+            })),
+        }),
+    );
+    paths.insert(
+        format!("{path}/schema"),
+        json!({
+            "get": {
+                "summary": "Get the schema the editor is built from",
+                "tags": tags,
+                "responses": { "200": json_response("The editor schema", json!({ "type": "object" })) },
+            },
+        }),
+    );
+    paths.insert(
+        format!("{path}/json-schema"),
+        json!({
+            "get": {
+                "summary": "Get the document's content as a JSON Schema",
+                "tags": tags,
+                "responses": { "200": json_response("A JSON Schema", json!({ "type": "object" })) },
+            },
+        }),
+    );
+    paths.insert(
+        format!("{path}/stale"),
+        json!({
+            "get": authenticated(json!({
+                "summary": "List the documents last written under an older schema",
+                "tags": tags,
+                "responses": { "200": json_response("The stale documents", array_of(item)) },
+            })),
+        }),
+    );
+}
+
+fn upload_paths(paths: &mut Map<String, Value>) {
+    let tags = json!(["uploads"]);
+
+    paths.insert(
+        "/images/upload".into(),
+        json!({
+            "put": authenticated(json!({
+                "summary": "Upload an image",
+                "tags": tags,
+                "requestBody": {
+                    "required": true,
+                    "content": { "image/*": { "schema": { "type": "string", "contentMediaType": "image/*" } } },
+                },
+                "responses": { "200": text_response("The image's url") },
+            })),
+        }),
+    );
+    paths.insert(
+        "/files/upload".into(),
+        json!({
+            "put": authenticated(json!({
+                "summary": "Upload a file",
+                "tags": tags,
+                "requestBody": {
+                    "required": true,
+                    "content": {
+                        "multipart/form-data": {
+                            "schema": {
+                                "type": "object",
+                                "properties": { "file": { "type": "string", "contentMediaType": "application/octet-stream" } },
+                            },
+                        },
+                    },
+                },
+                "responses": { "200": text_response("The file's url") },
+            })),
+        }),
+    );
+    paths.insert(
+        "/images/list".into(),
+        json!({
+            "get": authenticated(json!({
+                "summary": "List every uploaded image",
+                "tags": tags,
+                "responses": { "200": json_response("The images' urls", array_of(json!({ "type": "string" }))) },
+            })),
+        }),
+    );
+    paths.insert(
+        "/files/list".into(),
+        json!({
+            "get": authenticated(json!({
+                "summary": "List every uploaded file",
+                "tags": tags,
+                "responses": { "200": json_response("The files' urls", array_of(json!({ "type": "string" }))) },
+            })),
+        }),
+    );
+}
+
+fn oidc_paths(paths: &mut Map<String, Value>) {
+    let tags = json!(["auth"]);
+
+    paths.insert(
+        "/signin/oidc".into(),
+        json!({
+            "get": {
+                "summary": "Begin signing in with OIDC",
+                "tags": tags,
+                "responses": { "303": status("Redirects to the identity provider") },
+            },
+        }),
+    );
+    paths.insert(
+        "/signin/oidc/is_auto".into(),
+        json!({
+            "get": {
+                "summary": "Whether OIDC is the only way to sign in",
+                "tags": tags,
+                "responses": { "200": text_response("`true` or `false`") },
+            },
+        }),
+    );
+    paths.insert(
+        "/signin/oidc/complete".into(),
+        json!({
+            "get": {
+                "summary": "Complete signing in with OIDC",
+                "tags": tags,
+                "parameters": [
+                    { "name": "code", "in": "query", "required": true, "schema": { "type": "string" } },
+                    { "name": "state", "in": "query", "required": true, "schema": { "type": "string" } },
+                ],
+                "responses": {
+                    "200": text_response("A token to use as a bearer token"),
+                    "400": status("The identity provider's response is malformed"),
+                    "401": status("The state is unknown or expired, or the identity provider rejected the sign in"),
+                    "500": status("Something went wrong"),
+                },
+            },
+        }),
+    );
+}
+
+/// Schemas shared by every document type.
+#[allow(clippy::too_many_lines)]
+fn common_schemas() -> Map<String, Value> {
+    let date_time = json!({ "type": "string", "format": "date-time" });
+    let role = json!({ "enum": ["viewer", "editor", "publisher", "admin"] });
+
+    let schemas = json!({
+        "Role": role,
+        "User": {
+            "type": "object",
+            "properties": {
+                "email": { "type": "string" },
+                "name": { "type": "string" },
+                "profile_picture_url": { "type": "string" },
+                "role": reference("Role"),
+                "doc_roles": { "type": "object", "additionalProperties": reference("Role") },
+            },
+            "required": ["email", "name", "profile_picture_url", "role"],
+        },
+        "Credentials": {
+            "type": "object",
+            "properties": {
+                "email": { "type": "string" },
+                "password": { "type": "string" },
+            },
+            "required": ["email", "password"],
+        },
+        "DocInfo": {
+            "type": "object",
+            "properties": {
+                "identifier": { "type": "string" },
+                "title": { "type": "string" },
+                "schema_hash": { "type": "string" },
+            },
+            "required": ["identifier", "title", "schema_hash"],
+        },
+        "Item": {
+            "type": "object",
+            "properties": {
+                "__sc_id": { "type": "string" },
+                "__sc_created_at": date_time,
+                "__sc_modified_at": date_time,
+                "__sc_published_at": nullable(date_time.clone()),
+                "__sc_schema_hash": { "type": ["string", "null"] },
+                "content": {},
+            },
+            "required": ["__sc_id", "__sc_created_at", "__sc_modified_at", "__sc_published_at", "content"],
+        },
+        "Revision": {
+            "type": "object",
+            "properties": {
+                "__sc_revision_id": { "type": "string" },
+                "__sc_id": { "type": "string" },
+                "__sc_created_at": date_time,
+                "__sc_created_by": reference("User"),
+                "content": {},
+            },
+            "required": ["__sc_revision_id", "__sc_id", "__sc_created_at", "__sc_created_by", "content"],
+        },
+        "IncomingReference": {
+            "type": "object",
+            "properties": {
+                "__sc_doc": { "type": "string" },
+                "__sc_id": { "type": "string" },
+                "published": { "type": "boolean" },
+            },
+            "required": ["__sc_doc", "__sc_id", "published"],
+        },
+        "PathError": {
+            "type": "object",
+            "properties": {
+                "path": { "type": "string", "description": "A JSON pointer to the invalid value" },
+                "segments": array_of(reference("PathSegment")),
+                "code": { "type": "string" },
+                "message": { "type": "string" },
+                "severity": { "enum": ["error", "warning"] },
+            },
+            "required": ["path", "segments", "code", "message", "severity"],
+        },
+        "PathSegment": {
+            "oneOf": [
+                {
+                    "type": "object",
+                    "properties": {
+                        "kind": { "enum": ["field", "variant"] },
+                        "value": { "type": "string" },
+                    },
+                    "required": ["kind", "value"],
+                },
+                {
+                    "type": "object",
+                    "properties": {
+                        "kind": { "const": "index" },
+                        "value": { "type": "integer", "minimum": 0 },
+                    },
+                    "required": ["kind", "value"],
+                },
+            ],
+        },
+        "Delivery": {
+            "type": "object",
+            "properties": {
+                "id": { "type": "integer", "minimum": 0 },
+                "url": { "type": "string" },
+                "event": { "enum": ["published", "scheduled", "unpublished", "deleted", null] },
+                "doc": { "type": ["string", "null"] },
+                "doc_id": { "type": ["string", "null"] },
+                "attempts": { "type": "integer", "minimum": 0 },
+                "status": {
+                    "oneOf": [
+                        {
+                            "type": "object",
+                            "properties": { "status": { "const": "pending" } },
+                            "required": ["status"],
+                        },
+                        {
+                            "type": "object",
+                            "properties": {
+                                "status": { "const": "delivered" },
+                                "status_code": { "type": "integer" },
+                            },
+                            "required": ["status", "status_code"],
+                        },
+                        {
+                            "type": "object",
+                            "properties": {
+                                "status": { "const": "failed" },
+                                "error": { "type": "string" },
+                            },
+                            "required": ["status", "error"],
+                        },
+                        {
+                            "type": "object",
+                            "properties": {
+                                "status": { "const": "missed" },
+                                "events": { "type": "integer", "minimum": 0 },
+                            },
+                            "required": ["status", "events"],
+                        },
+                    ],
+                },
+                "created_at": date_time,
+            },
+            "required": ["id", "url", "event", "doc", "doc_id", "attempts", "status", "created_at"],
+        },
+    });
+
+    match schemas {
+        Value::Object(schemas) => schemas,
+        _ => unreachable!(),
+    }
+}
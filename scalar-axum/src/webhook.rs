@@ -0,0 +1,411 @@
+use std::{
+    collections::VecDeque,
+    error::Error,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use hmac::{Hmac, Mac};
+use reqwest::{header::CONTENT_TYPE, StatusCode, Url};
+use scalar_cms::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::events::{DocumentEvent, EventBus};
+
+/// Header containing the hex encoded HMAC-SHA256 of the request body, prefixed with `sha256=`.
+pub const SIGNATURE_HEADER: &str = "x-scalar-signature";
+/// Header containing the [`WebhookEvent`] that triggered the request.
+pub const EVENT_HEADER: &str = "x-scalar-event";
+/// Header containing the id of the delivery, which stays the same between retries.
+pub const DELIVERY_HEADER: &str = "x-scalar-delivery";
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEvent {
+    Published,
+    /// See [`DocumentEvent::Scheduled`].
+    Scheduled,
+    Unpublished,
+    Deleted,
+}
+
+impl WebhookEvent {
+    /// The webhook event for a document event, if there is one. Drafts don't send webhooks.
+    #[must_use]
+    pub fn from_document_event(event: &DocumentEvent) -> Option<Self> {
+        match event {
+            DocumentEvent::Drafted(_) => None,
+            DocumentEvent::Published(_) => Some(Self::Published),
+            DocumentEvent::Scheduled(_) => Some(Self::Scheduled),
+            DocumentEvent::Unpublished(_) => Some(Self::Unpublished),
+            DocumentEvent::Deleted(_) => Some(Self::Deleted),
+        }
+    }
+
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Published => "published",
+            Self::Scheduled => "scheduled",
+            Self::Unpublished => "unpublished",
+            Self::Deleted => "deleted",
+        }
+    }
+}
+
+/// Somewhere webhooks get sent to. By default, every event for every document is sent.
+#[derive(Debug, Clone)]
+pub struct WebhookEndpoint {
+    url: Url,
+    secret: Arc<str>,
+    docs: Option<Vec<&'static str>>,
+    events: Option<Vec<WebhookEvent>>,
+}
+
+impl WebhookEndpoint {
+    pub fn new(url: Url, secret: impl Into<String>) -> Self {
+        Self {
+            url,
+            secret: secret.into().into(),
+            docs: None,
+            events: None,
+        }
+    }
+
+    /// Only send events for these documents, by identifier.
+    #[must_use]
+    pub fn with_docs(mut self, docs: impl IntoIterator<Item = &'static str>) -> Self {
+        self.docs = Some(docs.into_iter().collect());
+        self
+    }
+
+    /// Only send these events.
+    #[must_use]
+    pub fn with_events(mut self, events: impl IntoIterator<Item = WebhookEvent>) -> Self {
+        self.events = Some(events.into_iter().collect());
+        self
+    }
+
+    fn matches(&self, event: WebhookEvent, doc: &str) -> bool {
+        self.docs.as_ref().is_none_or(|docs| docs.contains(&doc))
+            && self
+                .events
+                .as_ref()
+                .is_none_or(|events| events.contains(&event))
+    }
+}
+
+/// The body of every webhook request.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct WebhookPayload {
+    pub event: WebhookEvent,
+    pub doc: String,
+    pub id: String,
+    /// The item as it was after the event happened, or right before it was deleted.
+    pub item: Option<serde_json::Value>,
+    pub sent_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum DeliveryStatus {
+    Pending,
+    Delivered {
+        status_code: u16,
+    },
+    Failed {
+        error: String,
+    },
+    /// The webhooks fell behind the event bus, so these events were never sent.
+    Missed {
+        events: u64,
+    },
+}
+
+/// An entry in the delivery log. The event and document are unknown for missed deliveries.
+#[derive(Serialize, Debug, Clone)]
+pub struct Delivery {
+    pub id: u64,
+    pub url: String,
+    pub event: Option<WebhookEvent>,
+    pub doc: Option<String>,
+    pub doc_id: Option<String>,
+    pub attempts: u32,
+    pub status: DeliveryStatus,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Sends webhooks to every matching endpoint, retrying failed deliveries with exponential backoff.
+/// The most recent deliveries are kept in memory, see [`Webhooks::deliveries`].
+///
+/// Use [`Webhooks::listen`] to send webhooks for the events on an [`EventBus`], and add it as an
+/// axum `Extension` to expose the delivery log.
+#[derive(Clone)]
+pub struct Webhooks {
+    client: reqwest::Client,
+    endpoints: Arc<[WebhookEndpoint]>,
+    max_attempts: u32,
+    backoff: Duration,
+    log_size: usize,
+    log: Arc<Mutex<VecDeque<Delivery>>>,
+    next_id: Arc<AtomicU64>,
+}
+
+impl Webhooks {
+    pub fn new(endpoints: impl IntoIterator<Item = WebhookEndpoint>) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            endpoints: endpoints.into_iter().collect(),
+            max_attempts: 5,
+            backoff: Duration::from_secs(1),
+            log_size: 100,
+            log: Arc::default(),
+            next_id: Arc::default(),
+        }
+    }
+
+    #[must_use]
+    pub fn with_client(mut self, client: reqwest::Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// How many times a delivery is attempted before giving up. Defaults to 5.
+    #[must_use]
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// How long to wait before the first retry, doubling after every failed attempt. Defaults to 1 second.
+    #[must_use]
+    pub fn with_backoff(mut self, backoff: Duration) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// How many deliveries the log keeps. Defaults to 100.
+    #[must_use]
+    pub fn with_log_size(mut self, log_size: usize) -> Self {
+        self.log_size = log_size;
+        self
+    }
+
+    /// The most recent deliveries, newest first.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the delivery log's lock is poisoned.
+    #[must_use]
+    pub fn deliveries(&self) -> Vec<Delivery> {
+        self.log
+            .lock()
+            .expect("delivery log lock poisoned")
+            .iter()
+            .rev()
+            .cloned()
+            .collect()
+    }
+
+    /// Sends webhooks for every event on the bus that has a [`WebhookEvent`], until the bus is dropped.
+    pub fn listen(&self, events: &EventBus) {
+        let webhooks = self.clone();
+        let mut receiver = events.subscribe();
+
+        tokio::spawn(async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(event) => {
+                        if let Some(webhook_event) = WebhookEvent::from_document_event(&event) {
+                            let info = event.info();
+                            webhooks.dispatch(webhook_event, info.doc, &info.id, info.item.clone());
+                        }
+                    }
+                    Err(RecvError::Lagged(missed)) => {
+                        tracing::warn!(missed, "webhooks fell behind, some events weren't sent");
+                        webhooks.record_missed(missed);
+                    }
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        });
+    }
+
+    /// Sends an event to every endpoint interested in it. Deliveries happen in the background,
+    /// so this never blocks on the endpoints.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the payload fails to serialize, which should never happen.
+    pub fn dispatch(
+        &self,
+        event: WebhookEvent,
+        doc: &str,
+        id: &str,
+        item: Option<serde_json::Value>,
+    ) {
+        let payload = WebhookPayload {
+            event,
+            doc: doc.to_owned(),
+            id: id.to_owned(),
+            item,
+            sent_at: Utc::now(),
+        };
+        let body: Arc<[u8]> = serde_json::to_vec(&payload)
+            .expect("webhook payloads should always serialize")
+            .into();
+
+        for endpoint in self.endpoints.iter().filter(|e| e.matches(event, doc)) {
+            let delivery = Delivery {
+                id: self.next_id.fetch_add(1, Ordering::Relaxed),
+                url: endpoint.url.to_string(),
+                event: Some(event),
+                doc: Some(payload.doc.clone()),
+                doc_id: Some(payload.id.clone()),
+                attempts: 0,
+                status: DeliveryStatus::Pending,
+                created_at: payload.sent_at,
+            };
+            let delivery_id = delivery.id;
+            self.record(delivery);
+
+            tokio::spawn(
+                self.clone()
+                    .deliver(endpoint.clone(), delivery_id, event, body.clone()),
+            );
+        }
+    }
+
+    async fn deliver(
+        self,
+        endpoint: WebhookEndpoint,
+        delivery_id: u64,
+        event: WebhookEvent,
+        body: Arc<[u8]>,
+    ) {
+        let signature = sign(endpoint.secret.as_bytes(), &body);
+        let mut backoff = self.backoff;
+
+        for attempt in 1..=self.max_attempts {
+            let result = self
+                .client
+                .post(endpoint.url.clone())
+                .header(CONTENT_TYPE, "application/json")
+                .header(SIGNATURE_HEADER, &signature)
+                .header(EVENT_HEADER, event.as_str())
+                .header(DELIVERY_HEADER, delivery_id.to_string())
+                .body(body.to_vec())
+                .send()
+                .await;
+
+            let (status, retry) = match result {
+                Ok(response) if response.status().is_success() => (
+                    DeliveryStatus::Delivered {
+                        status_code: response.status().as_u16(),
+                    },
+                    false,
+                ),
+                Ok(response) => {
+                    let status = response.status();
+                    // other client errors won't fix themselves
+                    let retry = status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS;
+                    (
+                        DeliveryStatus::Failed {
+                            error: format!("endpoint responded with {status}"),
+                        },
+                        retry,
+                    )
+                }
+                Err(e) => {
+                    tracing::warn!(
+                        cause = &e as &dyn Error,
+                        url = %endpoint.url,
+                        attempt,
+                        "couldn't deliver webhook"
+                    );
+                    (
+                        DeliveryStatus::Failed {
+                            error: e.to_string(),
+                        },
+                        true,
+                    )
+                }
+            };
+
+            self.update(delivery_id, attempt, status);
+
+            if !retry || attempt == self.max_attempts {
+                break;
+            }
+
+            tokio::time::sleep(backoff).await;
+            backoff = backoff.saturating_mul(2);
+        }
+    }
+
+    /// Logs missed events for every endpoint, since there's no telling which ones they were for.
+    fn record_missed(&self, missed: u64) {
+        let now = Utc::now();
+        for endpoint in self.endpoints.iter() {
+            self.record(Delivery {
+                id: self.next_id.fetch_add(1, Ordering::Relaxed),
+                url: endpoint.url.to_string(),
+                event: None,
+                doc: None,
+                doc_id: None,
+                attempts: 0,
+                status: DeliveryStatus::Missed { events: missed },
+                created_at: now,
+            });
+        }
+    }
+
+    fn record(&self, delivery: Delivery) {
+        let mut log = self.log.lock().expect("delivery log lock poisoned");
+        log.push_back(delivery);
+        while log.len() > self.log_size {
+            log.pop_front();
+        }
+    }
+
+    fn update(&self, delivery_id: u64, attempts: u32, status: DeliveryStatus) {
+        let mut log = self.log.lock().expect("delivery log lock poisoned");
+        // the delivery might have been pushed out of the log already
+        if let Some(delivery) = log.iter_mut().find(|d| d.id == delivery_id) {
+            delivery.attempts = attempts;
+            delivery.status = status;
+        }
+    }
+}
+
+/// Signs a webhook body, the result is what gets sent in the [`SIGNATURE_HEADER`].
+///
+/// # Panics
+///
+/// Panics if the secret can't be used as an HMAC key, which should never happen.
+#[must_use]
+pub fn sign(secret: &[u8], body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("hmac accepts keys of any size");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Checks a signature from the [`SIGNATURE_HEADER`] against a body, in constant time.
+///
+/// # Panics
+///
+/// Panics if the secret can't be used as an HMAC key, which should never happen.
+#[must_use]
+pub fn verify(secret: &[u8], body: &[u8], signature: &str) -> bool {
+    let Some(Ok(signature)) = signature.strip_prefix("sha256=").map(hex::decode) else {
+        return false;
+    };
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("hmac accepts keys of any size");
+    mac.update(body);
+    mac.verify_slice(&signature).is_ok()
+}
@@ -0,0 +1,89 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use axum::{body::Bytes, extract::State, http::HeaderMap, http::StatusCode, routing::post, Router};
+use scalar_axum::webhook::{
+    verify, DeliveryStatus, WebhookEndpoint, WebhookEvent, WebhookPayload, Webhooks,
+    DELIVERY_HEADER, EVENT_HEADER, SIGNATURE_HEADER,
+};
+
+const SECRET: &str = "hunter2";
+
+#[derive(Clone, Default)]
+struct Receiver {
+    requests: Arc<Mutex<Vec<(HeaderMap, Bytes)>>>,
+}
+
+/// Fails the first request, then accepts the rest.
+async fn receive(State(receiver): State<Receiver>, headers: HeaderMap, body: Bytes) -> StatusCode {
+    let mut requests = receiver.requests.lock().unwrap();
+    requests.push((headers, body));
+    if requests.len() == 1 {
+        StatusCode::SERVICE_UNAVAILABLE
+    } else {
+        StatusCode::OK
+    }
+}
+
+#[tokio::test]
+async fn delivers_signed_webhooks_and_retries_server_errors() {
+    let receiver = Receiver::default();
+    let app = Router::new()
+        .route("/hook", post(receive))
+        .with_state(receiver.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/hook", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, app).await });
+
+    let webhooks = Webhooks::new([WebhookEndpoint::new(url.parse().unwrap(), SECRET)])
+        .with_backoff(Duration::from_millis(10));
+    webhooks.dispatch(
+        WebhookEvent::Published,
+        "post",
+        "hello-world",
+        Some(serde_json::json!({ "title": "hello world" })),
+    );
+
+    let delivery = tokio::time::timeout(Duration::from_secs(5), async {
+        loop {
+            let deliveries = webhooks.deliveries();
+            if let [delivery] = deliveries.as_slice() {
+                if matches!(delivery.status, DeliveryStatus::Delivered { .. }) {
+                    break delivery.clone();
+                }
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("webhook should be delivered");
+
+    assert_eq!(delivery.url, url);
+    assert_eq!(delivery.event, Some(WebhookEvent::Published));
+    assert_eq!(delivery.doc.as_deref(), Some("post"));
+    assert_eq!(delivery.doc_id.as_deref(), Some("hello-world"));
+    assert_eq!(delivery.attempts, 2);
+    assert!(matches!(
+        delivery.status,
+        DeliveryStatus::Delivered { status_code: 200 }
+    ));
+
+    let requests = receiver.requests.lock().unwrap();
+    assert_eq!(requests.len(), 2);
+    for (headers, body) in requests.iter() {
+        let signature = headers[SIGNATURE_HEADER].to_str().unwrap();
+        assert!(verify(SECRET.as_bytes(), body, signature));
+        assert!(!verify(b"wrong secret", body, signature));
+        assert_eq!(headers[EVENT_HEADER], "published");
+        assert_eq!(headers[DELIVERY_HEADER], delivery.id.to_string().as_str());
+
+        let payload: WebhookPayload = serde_json::from_slice(body).unwrap();
+        assert_eq!(payload.event, WebhookEvent::Published);
+        assert_eq!(payload.doc, "post");
+        assert_eq!(payload.id, "hello-world");
+    }
+    // retries send the same body
+    assert_eq!(requests[0].1, requests[1].1);
+}
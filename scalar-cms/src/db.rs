@@ -1,13 +1,20 @@
-use std::fmt::Debug;
+use std::collections::BTreeMap;
+use std::fmt::{Debug, Display};
 use std::marker::PhantomData;
+use std::str::FromStr;
 use std::{error::Error, sync::Arc};
 
 use chrono::{DateTime, Utc};
 use scalar_expr::Expression;
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use thiserror::Error;
+use ts_rs::TS;
 
-use crate::{validations::Valid, Document, Item};
+use crate::{
+    query::{ListQuery, Page},
+    validations::Valid,
+    Document, IncomingReference, Item, Revision,
+};
 
 #[derive(Error, Debug)]
 pub enum AuthenticationError<DE: Error> {
@@ -46,12 +53,89 @@ impl Debug for Credentials {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+/// What a user is allowed to do, each role can do everything the roles before it can.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export)]
+pub enum Role {
+    /// Can read documents.
+    Viewer,
+    /// Can read and draft documents.
+    Editor,
+    /// Can publish, unpublish and delete documents.
+    Publisher,
+    /// Can do anything, including managing users.
+    Admin,
+}
+
+impl Role {
+    pub const ALL: [Self; 4] = [Self::Viewer, Self::Editor, Self::Publisher, Self::Admin];
+
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Viewer => "viewer",
+            Self::Editor => "editor",
+            Self::Publisher => "publisher",
+            Self::Admin => "admin",
+        }
+    }
+
+    /// Whether this role is allowed to perform an action.
+    #[must_use]
+    pub fn allows(self, action: Action) -> bool {
+        let required = match action {
+            Action::Read => Self::Viewer,
+            Action::Draft => Self::Editor,
+            Action::Publish | Action::Unpublish | Action::Delete => Self::Publisher,
+        };
+        self >= required
+    }
+}
+
+impl Display for Role {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Error, Debug)]
+#[error("unknown role {0:?}")]
+pub struct UnknownRole(String);
+
+impl FromStr for Role {
+    type Err = UnknownRole;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|role| role.as_str() == s)
+            .ok_or_else(|| UnknownRole(s.to_owned()))
+    }
+}
+
+/// Something a user can do to a document.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export)]
+pub enum Action {
+    Read,
+    Draft,
+    Publish,
+    Unpublish,
+    Delete,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, TS)]
+#[ts(export)]
 pub struct User {
     email: Arc<str>,
     name: Arc<str>,
     profile_picture_url: Arc<str>,
-    admin: bool,
+    role: Role,
+    /// Roles for specific documents, by identifier. These take precedence over `role`.
+    #[serde(default)]
+    doc_roles: BTreeMap<String, Role>,
 }
 
 impl User {
@@ -59,16 +143,23 @@ impl User {
         email: impl Into<String>,
         name: impl Into<String>,
         profile_picture_url: impl Into<String>,
-        admin: bool,
+        role: Role,
     ) -> Self {
         Self {
             email: email.into().into(),
             name: name.into().into(),
             profile_picture_url: profile_picture_url.into().into(),
-            admin,
+            role,
+            doc_roles: BTreeMap::new(),
         }
     }
 
+    #[must_use]
+    pub fn with_doc_roles(mut self, doc_roles: BTreeMap<String, Role>) -> Self {
+        self.doc_roles = doc_roles;
+        self
+    }
+
     #[must_use]
     pub fn name(&self) -> &str {
         &self.name
@@ -81,7 +172,24 @@ impl User {
 
     #[must_use]
     pub fn admin(&self) -> bool {
-        self.admin
+        self.role == Role::Admin
+    }
+
+    #[must_use]
+    pub fn role(&self) -> Role {
+        self.role
+    }
+
+    /// The role this user has for a document, taking per document roles into account.
+    #[must_use]
+    pub fn role_for(&self, doc: &str) -> Role {
+        self.doc_roles.get(doc).copied().unwrap_or(self.role)
+    }
+
+    /// Whether this user is allowed to perform an action on a document.
+    #[must_use]
+    pub fn can(&self, action: Action, doc: &str) -> bool {
+        self.role_for(doc).allows(action)
     }
 }
 
@@ -147,6 +255,18 @@ where
             .vctx_any::<D>(self.excluded_id, self.field_name, expr)
             .await
     }
+
+    /// Gets a document of any type by id, for validations that need to look at other documents.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the database fails to get the document for whatever reason.
+    pub async fn get_by_id<T: Document + DeserializeOwned + Send>(
+        &self,
+        id: &str,
+    ) -> Result<Option<Item<serde_json::Value>>, DB::Error> {
+        self.conn.get_by_id::<T>(id).await
+    }
 }
 
 #[derive(Debug)]
@@ -178,6 +298,12 @@ impl<DB: DatabaseConnection> Authenticated<DB> {
     pub fn inner(&self) -> &DB {
         &self.conn
     }
+
+    /// Whether the authenticated user is allowed to perform an action on a document type.
+    #[must_use]
+    pub fn can<D: Document>(&self, action: Action) -> bool {
+        self.user.can(action, D::IDENTIFIER)
+    }
 }
 
 #[trait_variant::make(Send + Sized)]
@@ -235,6 +361,59 @@ pub trait DatabaseConnection {
         id: &str,
     ) -> Result<Option<Item<serde_json::Value>>, Self::Error>;
 
+    /// Gets a published document by id, deserialized into `D`. Documents that are scheduled to
+    /// be published in the future aren't returned. This doesn't need an authenticated connection.
+    async fn get_published<D: Document + DeserializeOwned + Send>(
+        &self,
+        id: &str,
+    ) -> Result<Option<Item<D>>, Self::Error>;
+    /// Gets every published document of a type, deserialized into `D`. Documents that are scheduled to
+    /// be published in the future aren't returned. This doesn't need an authenticated connection.
+    async fn get_all_published<D: Document + DeserializeOwned + Send>(
+        &self,
+    ) -> Result<Vec<Item<D>>, Self::Error>;
+
+    /// Gets every document that references the given document, in either it's draft or published version.
+    async fn get_incoming_references<D: Document + Send>(
+        &self,
+        id: &str,
+    ) -> Result<Vec<IncomingReference>, Self::Error>;
+
+    /// Gets a page of documents matching the query. The query should be checked with
+    /// [`ListQuery::check`] beforehand.
+    async fn list<D: Document + Send>(
+        &self,
+        query: ListQuery,
+    ) -> Result<Page<Item<serde_json::Value>>, Self::Error>;
+
+    /// Gets every revision of a document, newest first.
+    async fn get_revisions<D: Document + Send>(
+        &self,
+        id: &str,
+    ) -> Result<Vec<Revision<serde_json::Value>>, Self::Error>;
+    async fn get_revision<D: Document + Send>(
+        &self,
+        id: &str,
+        revision_id: &str,
+    ) -> Result<Option<Revision<serde_json::Value>>, Self::Error>;
+    /// Puts the contents of a revision into the document's draft.
+    /// Returns `None` if the revision doesn't exist.
+    async fn restore_revision<D: Document + Send>(
+        conn: &Authenticated<Self>,
+        id: &str,
+        revision_id: &str,
+    ) -> Result<Option<Item<serde_json::Value>>, Self::Error>;
+
+    /// Gets every item that was last written under a different schema than the document's current one.
+    async fn get_stale<D: Document + Send>(
+        &self,
+    ) -> Result<Vec<Item<serde_json::Value>>, Self::Error>;
+
+    /// Runs every migration of a document that hasn't been run on this database yet,
+    /// over its drafts, published items and revisions. Returns the versions that were run.
+    /// This should be run with a system connection, usually at startup.
+    async fn run_migrations<D: Document + Send>(&self) -> Result<Vec<u32>, Self::Error>;
+
     async fn vctx_all<D: Document>(
         &self,
         excl_id: &str,
@@ -1,10 +1,17 @@
+use std::num::{
+    NonZeroI16, NonZeroI32, NonZeroI64, NonZeroI8, NonZeroU16, NonZeroU32, NonZeroU64, NonZeroU8,
+};
+
 use chrono::{DateTime, NaiveDate, TimeZone};
+use scalar_expr::Expression;
 use serde::Serialize;
 use ts_rs::TS;
 
 use crate::{
-    types::{Markdown, MultiLine, Slug, Toggle},
-    EditorType,
+    editor_type::NumberKind,
+    types::{Locales, Localized, Markdown, MultiLine, Reference, Slug, Toggle},
+    validations::rules::Rule,
+    Document, EditorType,
 };
 
 #[derive(Serialize, TS)]
@@ -13,11 +20,90 @@ pub struct EditorField {
     pub name: &'static str,
     pub title: &'static str,
     pub placeholder: Option<&'static str>,
+    /// Help text for the editor, from the field's doc comment or `#[field(description = "...")]`.
+    pub description: Option<&'static str>,
+    /// Why the field is deprecated, if it is. Deprecated fields can still have data.
+    pub deprecated: Option<&'static str>,
     pub validator: Option<&'static str>,
+    /// Rules the editor can check before anything is sent to the server, see [`crate::validations::rules`].
+    pub rules: Vec<Rule>,
+    /// Like `rules`, but these only warn, they don't stop the document from being published.
+    pub warnings: Vec<Rule>,
+    /// Hides the field when this is true, evaluated against the field's siblings.
+    /// Documents skip validating hidden fields.
+    pub hidden_if: Option<Expression>,
+    /// Read only fields can be set when a document is created, after that the server undoes changes to them.
+    pub readonly: bool,
+    /// Makes the field read only when this is true, evaluated like `hidden_if` against what's stored.
+    pub readonly_if: Option<Expression>,
     pub required: bool,
     pub field_type: EditorType,
 }
 
+impl EditorField {
+    /// A required field without rules, conditions or docs, the derives fill those in afterwards.
+    #[must_use]
+    pub fn new(
+        name: &'static str,
+        title: &'static str,
+        placeholder: Option<&'static str>,
+        validator: Option<&'static str>,
+        field_type: EditorType,
+    ) -> Self {
+        Self {
+            name,
+            title,
+            placeholder,
+            description: None,
+            deprecated: None,
+            validator,
+            rules: Vec::new(),
+            warnings: Vec::new(),
+            hidden_if: None,
+            readonly: false,
+            readonly_if: None,
+            required: true,
+            field_type,
+        }
+    }
+}
+
+/// Puts back read only fields that were changed, so only the server can change them. Fields of nested
+/// structs are followed, but not items of arrays or enums. Conditions are evaluated against what's stored,
+/// so changing a condition's field doesn't unlock a field in the same write.
+pub fn restore_readonly(
+    fields: &[EditorField],
+    data: &mut serde_json::Value,
+    stored: &serde_json::Value,
+) {
+    let Some(data) = data.as_object_mut() else {
+        return;
+    };
+    for field in fields {
+        let current = stored.get(field.name);
+        let readonly = field.readonly
+            || field
+                .readonly_if
+                .as_ref()
+                .is_some_and(|condition| condition.evaluate(stored, current));
+
+        match (readonly, current, &field.field_type) {
+            (true, Some(current), _) => {
+                data.insert(field.name.into(), current.clone());
+            }
+            (true, None, _) => {
+                data.remove(field.name);
+            }
+            (false, Some(current), EditorType::Struct { fields, .. }) => {
+                if let Some(value) = data.get_mut(field.name) {
+                    restore_readonly(fields, value, current);
+                }
+            }
+            (false, _, _) => {}
+        }
+    }
+}
+
 /// Convert an input type into a `scalar_cms::EditorField`
 pub trait ToEditorField {
     fn to_editor_field(
@@ -44,16 +130,15 @@ impl ToEditorField for () {
     where
         Self: std::marker::Sized,
     {
-        EditorField {
+        EditorField::new(
             name,
             title,
             placeholder,
-            required: true,
             validator,
-            field_type: crate::EditorType::Null {
+            crate::EditorType::Null {
                 component_key: component_key.map(Into::into),
             },
-        }
+        )
     }
 }
 
@@ -69,21 +154,100 @@ impl ToEditorField for bool {
     where
         Self: std::marker::Sized,
     {
-        EditorField {
+        EditorField::new(
             name,
             title,
             placeholder,
-            required: true,
             validator,
-            field_type: crate::EditorType::Bool {
+            crate::EditorType::Bool {
                 default: default.map(Into::into),
                 component_key: component_key.map(Into::into),
             },
-        }
+        )
     }
 }
 
-impl ToEditorField for i32 {
+macro_rules! integer {
+    ($($ty:ty => $kind:ident),+) => {
+        $(integer!(@impl $ty, $kind, false, |value| value);)+
+    };
+    (non_zero $($ty:ty => $kind:ident),+) => {
+        $(integer!(@impl $ty, $kind, true, |value| value.get());)+
+    };
+    (@impl $ty:ty, $kind:ident, $non_zero:expr, |$value:ident| $get:expr) => {
+        impl ToEditorField for $ty {
+            fn to_editor_field(
+                default: Option<impl Into<Self>>,
+                name: &'static str,
+                title: &'static str,
+                placeholder: Option<&'static str>,
+                validator: Option<&'static str>,
+                component_key: Option<&'static str>,
+            ) -> EditorField
+            where
+                Self: std::marker::Sized,
+            {
+                let get = |$value: Self| $get;
+
+                EditorField::new(
+                    name,
+                    title,
+                    placeholder,
+                    validator,
+                    crate::EditorType::Integer {
+                        default: default.map(|default| get(default.into()).into()),
+                        kind: NumberKind::$kind,
+                        min: get(<$ty>::MIN).into(),
+                        max: get(<$ty>::MAX).into(),
+                        non_zero: $non_zero,
+                        component_key: component_key.map(Into::into),
+                    },
+                )
+            }
+        }
+    };
+}
+
+integer!(i8 => I8, i16 => I16, i32 => I32, i64 => I64, u8 => U8, u16 => U16, u32 => U32, u64 => U64);
+integer!(non_zero NonZeroI8 => I8, NonZeroI16 => I16, NonZeroI32 => I32, NonZeroI64 => I64,
+    NonZeroU8 => U8, NonZeroU16 => U16, NonZeroU32 => U32, NonZeroU64 => U64);
+
+macro_rules! float {
+    ($($ty:ty => $kind:ident),+) => {
+        $(
+            impl ToEditorField for $ty {
+                fn to_editor_field(
+                    default: Option<impl Into<Self>>,
+                    name: &'static str,
+                    title: &'static str,
+                    placeholder: Option<&'static str>,
+                    validator: Option<&'static str>,
+                    component_key: Option<&'static str>,
+                ) -> EditorField
+                where
+                    Self: std::marker::Sized,
+                {
+                    EditorField::new(
+                        name,
+                        title,
+                        placeholder,
+                        validator,
+                        crate::EditorType::Float {
+                            default: default.map(|default| f64::from(Into::<Self>::into(default))),
+                            kind: NumberKind::$kind,
+                            component_key: component_key.map(Into::into),
+                        },
+                    )
+                }
+            }
+        )+
+    };
+}
+
+float!(f32 => F32, f64 => F64);
+
+#[cfg(feature = "rust_decimal")]
+impl ToEditorField for rust_decimal::Decimal {
     fn to_editor_field(
         default: Option<impl Into<Self>>,
         name: &'static str,
@@ -95,21 +259,20 @@ impl ToEditorField for i32 {
     where
         Self: std::marker::Sized,
     {
-        EditorField {
+        EditorField::new(
             name,
             title,
             placeholder,
-            required: true,
             validator,
-            field_type: crate::EditorType::Integer {
-                default: default.map(Into::into),
+            crate::EditorType::Decimal {
+                default: default.map(|default| default.into().to_string()),
                 component_key: component_key.map(Into::into),
             },
-        }
+        )
     }
 }
 
-impl ToEditorField for f32 {
+impl ToEditorField for String {
     fn to_editor_field(
         default: Option<impl Into<Self>>,
         name: &'static str,
@@ -121,21 +284,20 @@ impl ToEditorField for f32 {
     where
         Self: std::marker::Sized,
     {
-        EditorField {
+        EditorField::new(
             name,
             title,
             placeholder,
-            required: true,
             validator,
-            field_type: crate::EditorType::Float {
+            crate::EditorType::SingleLine {
                 default: default.map(Into::into),
                 component_key: component_key.map(Into::into),
             },
-        }
+        )
     }
 }
 
-impl ToEditorField for String {
+impl ToEditorField for Slug {
     fn to_editor_field(
         default: Option<impl Into<Self>>,
         name: &'static str,
@@ -147,21 +309,20 @@ impl ToEditorField for String {
     where
         Self: std::marker::Sized,
     {
-        EditorField {
+        EditorField::new(
             name,
             title,
             placeholder,
-            required: true,
             validator,
-            field_type: crate::EditorType::SingleLine {
-                default: default.map(Into::into),
+            crate::EditorType::SingleLine {
+                default: default.map(Into::into).map(|v| v.0),
                 component_key: component_key.map(Into::into),
             },
-        }
+        )
     }
 }
 
-impl ToEditorField for Slug {
+impl ToEditorField for MultiLine {
     fn to_editor_field(
         default: Option<impl Into<Self>>,
         name: &'static str,
@@ -173,21 +334,20 @@ impl ToEditorField for Slug {
     where
         Self: std::marker::Sized,
     {
-        EditorField {
+        EditorField::new(
             name,
             title,
             placeholder,
-            required: true,
             validator,
-            field_type: crate::EditorType::SingleLine {
+            crate::EditorType::MultiLine {
                 default: default.map(Into::into).map(|v| v.0),
                 component_key: component_key.map(Into::into),
             },
-        }
+        )
     }
 }
 
-impl ToEditorField for MultiLine {
+impl ToEditorField for Markdown {
     fn to_editor_field(
         default: Option<impl Into<Self>>,
         name: &'static str,
@@ -199,21 +359,20 @@ impl ToEditorField for MultiLine {
     where
         Self: std::marker::Sized,
     {
-        EditorField {
+        EditorField::new(
             name,
             title,
             placeholder,
-            required: true,
             validator,
-            field_type: crate::EditorType::MultiLine {
+            crate::EditorType::Markdown {
                 default: default.map(Into::into).map(|v| v.0),
                 component_key: component_key.map(Into::into),
             },
-        }
+        )
     }
 }
 
-impl ToEditorField for Markdown {
+impl<D: Document, const PUBLISHED: bool> ToEditorField for Reference<D, PUBLISHED> {
     fn to_editor_field(
         default: Option<impl Into<Self>>,
         name: &'static str,
@@ -225,17 +384,18 @@ impl ToEditorField for Markdown {
     where
         Self: std::marker::Sized,
     {
-        EditorField {
+        EditorField::new(
             name,
             title,
             placeholder,
-            required: true,
             validator,
-            field_type: crate::EditorType::Markdown {
-                default: default.map(Into::into).map(|v| v.0),
+            crate::EditorType::Reference {
+                default: default.map(Into::into).map(|v| v.id),
                 component_key: component_key.map(Into::into),
+                to: D::IDENTIFIER,
+                published: PUBLISHED,
             },
-        }
+        )
     }
 }
 
@@ -251,17 +411,16 @@ impl<Z: TimeZone> ToEditorField for DateTime<Z> {
     where
         Self: std::marker::Sized,
     {
-        EditorField {
+        EditorField::new(
             name,
             title,
             placeholder,
             validator,
-            required: true,
-            field_type: EditorType::DateTime {
+            EditorType::DateTime {
                 default: default.map(Into::into).as_ref().map(DateTime::to_utc),
                 component_key: component_key.map(Into::into),
             },
-        }
+        )
     }
 }
 
@@ -277,13 +436,12 @@ impl ToEditorField for NaiveDate {
     where
         Self: std::marker::Sized,
     {
-        EditorField {
+        EditorField::new(
             name,
             title,
             placeholder,
             validator,
-            required: true,
-            field_type: EditorType::Date {
+            EditorType::Date {
                 default: default.map(Into::into).as_ref().map(|d| {
                     d.and_hms_opt(0, 0, 0)
                         .expect("should always be valid")
@@ -291,7 +449,7 @@ impl ToEditorField for NaiveDate {
                 }),
                 component_key: component_key.map(Into::into),
             },
-        }
+        )
     }
 }
 
@@ -317,19 +475,18 @@ impl<T: ToEditorField + Serialize> ToEditorField for Toggle<T> {
         );
         let field_type = dummy_field.field_type;
 
-        EditorField {
+        EditorField::new(
             name,
             title,
             placeholder,
             validator,
-            required: true,
-            field_type: EditorType::Toggle {
+            EditorType::Toggle {
                 component_key: component_key.map(Into::into),
                 default: default
                     .map(|v| serde_json::to_value(v.into()).expect("this should never fail")),
                 value: Box::new(field_type),
             },
-        }
+        )
     }
 }
 
@@ -383,19 +540,18 @@ where
         );
         let field_type = dummy_field.field_type;
 
-        EditorField {
+        EditorField::new(
             name,
             title,
             placeholder,
-            required: true,
             validator,
-            field_type: EditorType::Array {
+            EditorType::Array {
                 default: default
                     .map(|v| serde_json::to_value(v.into()).expect("this should never fail")),
                 component_key: component_key.map(Into::into),
                 of: Box::new(field_type),
             },
-        }
+        )
     }
 }
 
@@ -412,17 +568,16 @@ impl ToEditorField for url::Url {
     where
         Self: std::marker::Sized,
     {
-        EditorField {
+        EditorField::new(
             name,
             title,
             placeholder,
-            required: true,
             validator,
-            field_type: crate::EditorType::SingleLine {
+            crate::EditorType::SingleLine {
                 default: default.map(Into::into).map(Into::into),
                 component_key: component_key.map(Into::into).or_else(|| Some("url".into())),
             },
-        }
+        )
     }
 }
 
@@ -441,45 +596,24 @@ impl ToEditorField for rgb::RGB8 {
     {
         let default = default.map(Into::into);
 
-        super::EditorField {
+        super::EditorField::new(
             name,
             title,
             placeholder,
             validator,
-            required: true,
-            field_type: crate::EditorType::Struct {
+            crate::EditorType::Struct {
                 component_key: component_key.map(Into::into).or(Some("color-input".into())),
+                name: Some("RGB8"),
                 default: default
                     .map(serde_json::to_value)
                     .map(|v| v.expect("rgb values should always serialize correctly")),
                 fields: vec![
-                    i32::to_editor_field(
-                        default.map(|c| i32::from(c.r)),
-                        "r",
-                        "",
-                        None,
-                        None,
-                        None,
-                    ),
-                    i32::to_editor_field(
-                        default.map(|c| i32::from(c.g)),
-                        "g",
-                        "",
-                        None,
-                        None,
-                        None,
-                    ),
-                    i32::to_editor_field(
-                        default.map(|c| i32::from(c.b)),
-                        "b",
-                        "",
-                        None,
-                        None,
-                        None,
-                    ),
+                    u8::to_editor_field(default.map(|c| c.r), "r", "", None, None, None),
+                    u8::to_editor_field(default.map(|c| c.g), "g", "", None, None, None),
+                    u8::to_editor_field(default.map(|c| c.b), "b", "", None, None, None),
                 ],
             },
-        }
+        )
     }
 }
 
@@ -498,52 +632,68 @@ impl ToEditorField for rgb::RGBA8 {
     {
         let default = default.map(Into::into);
 
-        super::EditorField {
+        super::EditorField::new(
             name,
             title,
             placeholder,
             validator,
-            required: true,
-            field_type: crate::EditorType::Struct {
+            crate::EditorType::Struct {
                 component_key: component_key.map(Into::into).or(Some("color-input".into())),
+                name: Some("RGBA8"),
                 default: default
                     .map(serde_json::to_value)
                     .map(|v| v.expect("rgb values should always serialize correctly")),
                 fields: vec![
-                    i32::to_editor_field(
-                        default.map(|c| i32::from(c.r)),
-                        "r",
-                        "",
-                        None,
-                        None,
-                        None,
-                    ),
-                    i32::to_editor_field(
-                        default.map(|c| i32::from(c.g)),
-                        "g",
-                        "",
-                        None,
-                        None,
-                        None,
-                    ),
-                    i32::to_editor_field(
-                        default.map(|c| i32::from(c.b)),
-                        "b",
-                        "",
-                        None,
-                        None,
-                        None,
-                    ),
-                    i32::to_editor_field(
-                        default.map(|c| i32::from(c.a)),
-                        "a",
-                        "",
-                        None,
-                        None,
-                        None,
-                    ),
+                    u8::to_editor_field(default.map(|c| c.r), "r", "", None, None, None),
+                    u8::to_editor_field(default.map(|c| c.g), "g", "", None, None, None),
+                    u8::to_editor_field(default.map(|c| c.b), "b", "", None, None, None),
+                    u8::to_editor_field(default.map(|c| c.a), "a", "", None, None, None),
                 ],
             },
-        }
+        )
+    }
+}
+
+impl<T, L, const VALIDATE: bool> ToEditorField for Localized<T, L, VALIDATE>
+where
+    T: ToEditorField + Serialize,
+    L: Locales,
+{
+    fn to_editor_field(
+        default: Option<impl Into<Localized<T, L, VALIDATE>>>,
+        name: &'static str,
+        title: &'static str,
+        placeholder: Option<&'static str>,
+        validator: Option<&'static str>,
+        component_key: Option<&'static str>,
+    ) -> EditorField
+    where
+        Self: std::marker::Sized,
+    {
+        let dummy_field = T::to_editor_field(
+            None::<T>,
+            name,
+            title,
+            placeholder,
+            validator,
+            component_key,
+        );
+        let field_type = dummy_field.field_type;
+
+        EditorField::new(
+            name,
+            title,
+            placeholder,
+            validator,
+            EditorType::Localized {
+                default: default
+                    .map(|v| serde_json::to_value(v.into()).expect("this should never fail")),
+                component_key: component_key.map(Into::into),
+                locales: L::LOCALES,
+                required: L::REQUIRED,
+                fallback: L::FALLBACK,
+                of: Box::new(field_type),
+            },
+        )
     }
 }
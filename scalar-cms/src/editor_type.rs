@@ -4,6 +4,22 @@ use ts_rs::TS;
 
 use crate::EditorField;
 
+/// The Rust type behind a number, so the editor knows what it can hold.
+#[derive(Serialize, TS, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum NumberKind {
+    I8,
+    I16,
+    I32,
+    I64,
+    U8,
+    U16,
+    U32,
+    U64,
+    F32,
+    F64,
+}
+
 #[derive(Serialize, TS)]
 #[serde(tag = "type", rename_all = "kebab-case")]
 pub enum EditorType {
@@ -17,24 +33,50 @@ pub enum EditorType {
         component_key: Option<String>,
         default: Option<bool>,
     },
+    /// Numbers beyond 2^53 lose precision in JavaScript, keep that in mind with 64 bit kinds.
     Integer {
         component_key: Option<String>,
-        default: Option<i32>,
+        #[ts(type = "number | null")]
+        default: Option<serde_json::Number>,
+        kind: NumberKind,
+        /// The smallest value the kind can hold, inclusive. It's a string so 64 bit bounds stay exact,
+        /// parse it with `BigInt`.
+        #[serde(serialize_with = "to_string")]
+        #[ts(type = "string")]
+        min: serde_json::Number,
+        /// The largest value the kind can hold, inclusive, as a string like `min`.
+        #[serde(serialize_with = "to_string")]
+        #[ts(type = "string")]
+        max: serde_json::Number,
+        /// Whether 0 isn't allowed, e.g. for `NonZeroU32`.
+        non_zero: bool,
     },
     Float {
         component_key: Option<String>,
-        default: Option<f32>,
+        default: Option<f64>,
+        kind: NumberKind,
+    },
+    /// An exact decimal number, stored as a string so it doesn't lose precision.
+    Decimal {
+        component_key: Option<String>,
+        default: Option<String>,
     },
     Struct {
         component_key: Option<String>,
+        /// The name of the Rust type, used to name generated types. `None` for generic types, which get inlined.
+        name: Option<&'static str>,
         #[ts(type = "any | null")]
         default: Option<serde_json::Value>,
         fields: Vec<EditorField>,
     },
     Enum {
         component_key: Option<String>,
+        /// The name of the Rust type, used to name generated types. `None` for generic types, which get inlined.
+        name: Option<&'static str>,
         #[ts(type = "any | null")]
         default: Option<serde_json::Value>,
+        /// How variants are serialized, following serde.
+        representation: EnumRepresentation,
         variants: Vec<EnumVariant>,
     },
     Array {
@@ -63,13 +105,118 @@ pub enum EditorType {
         component_key: Option<String>,
         default: Option<DateTime<Utc>>,
     },
+    Localized {
+        component_key: Option<String>,
+        #[ts(type = "Record<string, any> | null")]
+        default: Option<serde_json::Value>,
+        /// Every locale, in the order they should be shown in.
+        locales: &'static [&'static str],
+        /// Locales that have to have a value.
+        required: &'static [&'static str],
+        /// Locales to fall back on, in order, when a value is missing.
+        fallback: &'static [&'static str],
+        of: Box<EditorType>,
+    },
+    RichText {
+        component_key: Option<String>,
+        #[ts(type = "any[] | null")]
+        default: Option<serde_json::Value>,
+        /// Block styles, the first one is the default.
+        styles: &'static [&'static str],
+        decorators: &'static [&'static str],
+        lists: bool,
+        /// What can be attached to spans.
+        annotation: Box<EditorType>,
+        /// What can be put between blocks, `null` if nothing can.
+        embed: Box<EditorType>,
+    },
+    Reference {
+        component_key: Option<String>,
+        default: Option<String>,
+        /// The identifier of the referenced document.
+        to: &'static str,
+        /// Whether the referenced document has to be published.
+        published: bool,
+    },
     Null {
         component_key: Option<String>,
     },
 }
 
+fn to_string<S: serde::Serializer>(
+    number: &serde_json::Number,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(number)
+}
+
 #[derive(Serialize, TS)]
 pub struct EnumVariant {
+    /// The name the variant is serialized as.
     pub variant_name: &'static str,
+    pub kind: VariantKind,
+    /// `None` for unit variants. Fields of newtype and tuple variants are named by their index.
     pub fields: Option<Vec<EditorField>>,
 }
+
+/// The shape of an enum variant's content.
+#[derive(Serialize, TS, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+#[ts(export)]
+pub enum VariantKind {
+    /// No content.
+    Unit,
+    /// An object of it's fields.
+    Struct,
+    /// It's one field, as is.
+    Newtype,
+    /// An array of it's fields.
+    Tuple,
+}
+
+/// How an enum is serialized, matching serde's enum representations. In each, the content
+/// is the variant's content as described by [`VariantKind`].
+#[derive(Serialize, TS, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+#[ts(export)]
+pub enum EnumRepresentation {
+    /// `{ "Variant": content }`, or just `"Variant"` for unit variants. This is serde's default.
+    External,
+    /// `{ "tag": "Variant", ...content }`, newtype variants have to hold an object.
+    Internal { tag: &'static str },
+    /// `{ "tag": "Variant", "content": content }`.
+    Adjacent {
+        tag: &'static str,
+        content: &'static str,
+    },
+    /// Just the content, or `null` for unit variants.
+    Untagged,
+}
+
+impl EnumRepresentation {
+    /// Finds which variant a value is, along with it's content. Untagged enums and unit variants
+    /// don't have any content to find.
+    pub fn content_mut<'a, 'v>(
+        self,
+        value: &'a mut serde_json::Value,
+        variants: &'v [EnumVariant],
+    ) -> Option<(&'v EnumVariant, &'a mut serde_json::Value)> {
+        let find = |name: &str| variants.iter().find(|variant| variant.variant_name == name);
+
+        match self {
+            Self::External => {
+                let (name, content) = value.as_object_mut()?.iter_mut().next()?;
+                Some((find(name)?, content))
+            }
+            Self::Internal { tag } => {
+                let variant = find(value.get(tag)?.as_str()?)?;
+                Some((variant, value))
+            }
+            Self::Adjacent { tag, content } => {
+                let variant = find(value.get(tag)?.as_str()?)?;
+                Some((variant, value.get_mut(content)?))
+            }
+            Self::Untagged => None,
+        }
+    }
+}
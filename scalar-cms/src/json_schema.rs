@@ -0,0 +1,372 @@
+use serde_json::{json, Map, Value};
+
+use crate::{
+    editor_type::{EnumRepresentation, EnumVariant, VariantKind},
+    validations::rules::Rule,
+    EditorField, EditorType, Schema,
+};
+
+/// The dialect every generated schema declares.
+pub const DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+impl Schema {
+    /// Converts this schema to a JSON Schema (draft 2020-12) describing the document's content,
+    /// as it's stored and served.
+    #[must_use]
+    pub fn to_json_schema(&self) -> Value {
+        let mut schema = object_schema(self.fields);
+        schema.insert("$schema".into(), DIALECT.into());
+        schema.insert("title".into(), self.title.into());
+        Value::Object(schema)
+    }
+}
+
+impl EditorField {
+    /// Converts this field to a JSON Schema, including it's [`EditorField::rules`]. Warnings are left out,
+    /// since they don't make a document invalid.
+    /// Fields that aren't required are nullable, unless they have to be non empty.
+    /// Conditions like [`EditorField::hidden_if`] can't be expressed, so they're left out too.
+    #[must_use]
+    pub fn to_json_schema(&self) -> Value {
+        let mut schema = self.field_type.to_json_schema();
+        let rules = self
+            .rules
+            .iter()
+            .map(Rule::to_json_schema)
+            .collect::<Vec<_>>();
+        if let (Value::Object(schema), false) = (&mut schema, rules.is_empty()) {
+            schema.insert("allOf".into(), rules.into());
+        }
+        if !self.required && !self.rules.contains(&Rule::NonEmpty) {
+            schema = json!({ "anyOf": [schema, { "type": "null" }] });
+        }
+        if let Value::Object(schema) = &mut schema {
+            schema.insert("title".into(), self.title.into());
+            if let Some(description) = self.description {
+                schema.insert("description".into(), description.into());
+            }
+            if self.deprecated.is_some() {
+                schema.insert("deprecated".into(), true.into());
+            }
+            if self.readonly {
+                schema.insert("readOnly".into(), true.into());
+            }
+        }
+        schema
+    }
+}
+
+impl EditorType {
+    /// Converts this type to a JSON Schema, including it's default if there is one.
+    #[must_use]
+    #[allow(clippy::too_many_lines)]
+    pub fn to_json_schema(&self) -> Value {
+        let (mut schema, default) = match self {
+            Self::Toggle { default, value, .. } => (
+                json!({ "anyOf": [value.to_json_schema(), { "type": "null" }] }),
+                default.clone(),
+            ),
+            Self::Bool { default, .. } => (json!({ "type": "boolean" }), default.map(Into::into)),
+            Self::Integer {
+                default,
+                min,
+                max,
+                non_zero,
+                ..
+            } => {
+                let mut schema = json!({ "type": "integer", "minimum": min, "maximum": max });
+                if *non_zero {
+                    schema["not"] = json!({ "const": 0 });
+                }
+                (schema, default.clone().map(Into::into))
+            }
+            Self::Float { default, .. } => (json!({ "type": "number" }), default.map(Into::into)),
+            Self::Decimal { default, .. } => (
+                json!({ "type": "string", "pattern": r"^-?\d+(\.\d+)?$" }),
+                default.clone().map(Into::into),
+            ),
+            Self::Struct {
+                default, fields, ..
+            } => (Value::Object(object_schema(fields)), default.clone()),
+            Self::Enum {
+                default,
+                representation,
+                variants,
+                ..
+            } => {
+                // untagged variants can overlap, and a value matching more than one is still valid
+                let combinator = if *representation == EnumRepresentation::Untagged {
+                    "anyOf"
+                } else {
+                    "oneOf"
+                };
+                let variants = variants
+                    .iter()
+                    .map(|variant| variant_schema(variant, *representation))
+                    .collect::<Vec<_>>();
+                (json!({ combinator: variants }), default.clone())
+            }
+            Self::Array { default, of, .. } => (
+                json!({ "type": "array", "items": of.to_json_schema() }),
+                default.clone(),
+            ),
+            Self::SingleLine { default, .. }
+            | Self::MultiLine { default, .. }
+            | Self::Markdown { default, .. } => {
+                (json!({ "type": "string" }), default.clone().map(Into::into))
+            }
+            Self::Date { default, .. } => (
+                json!({ "type": "string", "format": "date" }),
+                default.map(|d| d.date_naive().to_string().into()),
+            ),
+            Self::DateTime { default, .. } => (
+                json!({ "type": "string", "format": "date-time" }),
+                default.map(|d| d.to_rfc3339().into()),
+            ),
+            Self::Localized {
+                default,
+                locales,
+                required,
+                of,
+                ..
+            } => {
+                let of = of.to_json_schema();
+                (
+                    json!({
+                        "type": "object",
+                        "properties": locales
+                            .iter()
+                            .map(|locale| ((*locale).to_owned(), of.clone()))
+                            .collect::<Map<_, _>>(),
+                        "required": required,
+                        "additionalProperties": false,
+                    }),
+                    default.clone(),
+                )
+            }
+            Self::RichText {
+                default,
+                styles,
+                lists,
+                annotation,
+                embed,
+                ..
+            } => (
+                rich_text_schema(styles, *lists, annotation, embed),
+                default.clone(),
+            ),
+            Self::Reference { default, to, .. } => (
+                json!({
+                    "type": "object",
+                    "properties": {
+                        "__sc_ref": { "type": "string" },
+                        "__sc_doc": { "const": to },
+                    },
+                    "required": ["__sc_ref", "__sc_doc"],
+                }),
+                default
+                    .clone()
+                    .map(|id| json!({ "__sc_ref": id, "__sc_doc": to })),
+            ),
+            // some types stand in for null with an empty array, so databases don't trim them
+            Self::Null { .. } => (json!({ "type": ["null", "array"], "maxItems": 0 }), None),
+        };
+
+        if let (Value::Object(schema), Some(default)) = (&mut schema, default) {
+            schema.insert("default".into(), default);
+        }
+        schema
+    }
+}
+
+impl Rule {
+    /// Converts this rule to a JSON Schema. Keywords only apply to the types they're meant for,
+    /// so this doesn't need to know what type the rule is on.
+    #[must_use]
+    pub fn to_json_schema(&self) -> Value {
+        match self {
+            Self::NonEmpty => json!({ "minLength": 1, "minItems": 1 }),
+            Self::Length { min, max } => bounds("minLength", *min, "maxLength", *max),
+            Self::Items { min, max } => bounds("minItems", *min, "maxItems", *max),
+            Self::Range { min, max } => bounds("minimum", *min, "maximum", *max),
+            // the derive checks regexes work in JavaScript too, see `Rule::Regex`
+            Self::Regex { pattern } => json!({ "pattern": pattern }),
+            Self::Email { pattern } => json!({ "format": "email", "pattern": pattern }),
+            Self::Url { pattern } => json!({ "format": "uri", "pattern": pattern }),
+        }
+    }
+}
+
+fn bounds<T: Into<Value>>(
+    min_keyword: &str,
+    min: Option<T>,
+    max_keyword: &str,
+    max: Option<T>,
+) -> Value {
+    let mut schema = Map::new();
+    if let Some(min) = min {
+        schema.insert(min_keyword.into(), min.into());
+    }
+    if let Some(max) = max {
+        schema.insert(max_keyword.into(), max.into());
+    }
+    Value::Object(schema)
+}
+
+fn object_schema(fields: &[EditorField]) -> Map<String, Value> {
+    let mut schema = Map::new();
+    schema.insert("type".into(), "object".into());
+    schema.insert(
+        "properties".into(),
+        fields
+            .iter()
+            .map(|field| (field.name.to_owned(), field.to_json_schema()))
+            .collect::<Map<_, _>>()
+            .into(),
+    );
+    schema.insert(
+        "required".into(),
+        fields
+            .iter()
+            .filter(|field| field.required)
+            .map(|field| field.name)
+            .collect::<Vec<_>>()
+            .into(),
+    );
+    schema
+}
+
+/// A variant's schema, as it's tagged by the enum's representation.
+fn variant_schema(variant: &EnumVariant, representation: EnumRepresentation) -> Value {
+    let name = variant.variant_name;
+    let content = content_schema(variant);
+    let mut schema = match (representation, content) {
+        (EnumRepresentation::External, None) => json!({ "const": name }),
+        (EnumRepresentation::External, Some(content)) => tagged_schema(&[(name, content)]),
+        (EnumRepresentation::Internal { tag }, Some(content)) => {
+            let fields = variant.fields.as_deref().unwrap_or_default();
+            if variant.kind == VariantKind::Struct {
+                let mut schema = object_schema(fields);
+                schema["properties"]
+                    .as_object_mut()
+                    .expect("properties is always an object")
+                    .insert(tag.into(), json!({ "const": name }));
+                schema["required"]
+                    .as_array_mut()
+                    .expect("required is always an array")
+                    .insert(0, tag.into());
+                Value::Object(schema)
+            } else {
+                // a newtype's fields sit next to the tag
+                json!({ "allOf": [tagged_schema(&[(tag, json!({ "const": name }))]), content] })
+            }
+        }
+        (EnumRepresentation::Internal { tag } | EnumRepresentation::Adjacent { tag, .. }, None) => {
+            tagged_schema(&[(tag, json!({ "const": name }))])
+        }
+        (EnumRepresentation::Adjacent { tag, content: key }, Some(content)) => {
+            tagged_schema(&[(tag, json!({ "const": name })), (key, content)])
+        }
+        (EnumRepresentation::Untagged, None) => json!({ "type": "null" }),
+        (EnumRepresentation::Untagged, Some(content)) => content,
+    };
+
+    if let Value::Object(schema) = &mut schema {
+        schema.insert("title".into(), name.into());
+    }
+    schema
+}
+
+/// The schema of a variant's content, or `None` for unit variants.
+fn content_schema(variant: &EnumVariant) -> Option<Value> {
+    let fields = variant.fields.as_deref().unwrap_or_default();
+    match (variant.kind, fields) {
+        (VariantKind::Unit, _) => None,
+        (VariantKind::Newtype, [field]) => Some(field.to_json_schema()),
+        (VariantKind::Tuple | VariantKind::Newtype, fields) => Some(json!({
+            "type": "array",
+            "prefixItems": fields.iter().map(EditorField::to_json_schema).collect::<Vec<_>>(),
+            "items": false,
+            "minItems": fields.len(),
+            "maxItems": fields.len(),
+        })),
+        (VariantKind::Struct, fields) => Some(Value::Object(object_schema(fields))),
+    }
+}
+
+/// An object with the given properties, all of them required.
+fn tagged_schema(properties: &[(&str, Value)]) -> Value {
+    json!({
+        "type": "object",
+        "properties": properties
+            .iter()
+            .map(|(key, schema)| ((*key).to_owned(), schema.clone()))
+            .collect::<Map<_, _>>(),
+        "required": properties.iter().map(|(key, _)| *key).collect::<Vec<_>>(),
+    })
+}
+
+fn rich_text_schema(
+    styles: &[&str],
+    lists: bool,
+    annotation: &EditorType,
+    embed: &EditorType,
+) -> Value {
+    let mut text_block = json!({
+        "type": "object",
+        "properties": {
+            "_type": { "const": "block" },
+            "_key": { "type": "string" },
+            "style": { "enum": styles },
+            "children": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "text": { "type": "string" },
+                        // annotation keys are allowed too, so decorators can't be an enum
+                        "marks": { "type": "array", "items": { "type": "string" } },
+                    },
+                    "required": ["text"],
+                },
+            },
+            "mark_defs": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "_key": { "type": "string" },
+                        "value": annotation.to_json_schema(),
+                    },
+                    "required": ["_key", "value"],
+                },
+            },
+        },
+        "required": ["_type", "_key", "style", "children"],
+    });
+    if lists {
+        let properties = text_block["properties"]
+            .as_object_mut()
+            .expect("properties is always an object");
+        properties.insert("list".into(), json!({ "enum": ["bullet", "number", null] }));
+        properties.insert(
+            "level".into(),
+            json!({ "type": ["integer", "null"], "minimum": 1 }),
+        );
+    }
+
+    let mut blocks = vec![text_block];
+    if !matches!(embed, EditorType::Null { .. }) {
+        blocks.push(json!({
+            "type": "object",
+            "properties": {
+                "_type": { "const": "embed" },
+                "_key": { "type": "string" },
+                "value": embed.to_json_schema(),
+            },
+            "required": ["_type", "_key", "value"],
+        }));
+    }
+
+    json!({ "type": "array", "items": { "oneOf": blocks } })
+}
@@ -8,6 +8,8 @@ pub use chrono::{DateTime, NaiveDate, Utc};
 pub use nanoid::nanoid;
 #[cfg(feature = "rgb")]
 pub use rgb::RGBA8;
+#[cfg(feature = "rust_decimal")]
+pub use rust_decimal::Decimal;
 #[cfg(feature = "url")]
 pub use url::Url;
 
@@ -16,7 +18,11 @@ pub use db::DatabaseConnection;
 pub mod db;
 pub mod editor_field;
 pub mod editor_type;
+pub mod json_schema;
+pub mod migrations;
+pub mod query;
 pub mod types;
+pub mod typescript;
 pub mod validations;
 
 pub use serde_json;
@@ -27,7 +33,7 @@ use validations::Validate;
 
 pub use scalar_expr as expr;
 
-use crate::db::ValidationContext;
+use crate::db::{User, ValidationContext};
 
 #[derive(Serialize, TS)]
 #[ts(export)]
@@ -38,6 +44,32 @@ pub struct Schema {
     label: Option<&'static str>,
     sub_label: Option<&'static str>,
     fields: &'static [EditorField],
+    /// See [`Document::groups`].
+    groups: &'static [FieldGroup],
+    /// See [`Document::schema_hash`].
+    hash: String,
+}
+
+/// A group of fields in the editor. Groups only change how fields are shown, not how they're stored.
+#[derive(Serialize, TS, Debug)]
+#[ts(export)]
+pub struct FieldGroup {
+    pub id: &'static str,
+    pub title: &'static str,
+    pub kind: GroupKind,
+    pub collapsible: bool,
+    /// Whether the fieldset starts collapsed.
+    pub collapsed: bool,
+    /// The names of the fields in this group, in order.
+    pub fields: &'static [&'static str],
+}
+
+#[derive(Serialize, TS, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+#[ts(export)]
+pub enum GroupKind {
+    Tab,
+    Fieldset,
 }
 
 #[derive(Serialize, TS)]
@@ -45,6 +77,86 @@ pub struct Schema {
 pub struct DocInfo {
     pub identifier: &'static str,
     pub title: &'static str,
+    /// See [`Document::schema_hash`].
+    pub schema_hash: String,
+}
+
+/// Hashes a tree of fields with 64 bit FNV-1a, so it's stable between builds and platforms.
+/// Only what changes how content is stored is hashed, so editing titles, descriptions, defaults, bounds or
+/// rules doesn't make every item stale.
+///
+/// # Panics
+///
+/// Panics if the fields fail to serialize, which should never happen.
+#[must_use]
+pub fn schema_hash(fields: &[EditorField]) -> String {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0100_0000_01b3;
+
+    let shape = fields.iter().map(field_shape).collect::<Vec<_>>();
+    let bytes = serde_json::to_vec(&shape).expect("fields should always serialize");
+    let hash = bytes.iter().fold(OFFSET_BASIS, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(PRIME)
+    });
+
+    format!("{hash:016x}")
+}
+
+/// The parts of a field that change how it's stored.
+fn field_shape(field: &EditorField) -> serde_json::Value {
+    serde_json::json!({
+        "name": field.name,
+        "required": field.required,
+        "field_type": type_shape(&field.field_type),
+    })
+}
+
+fn type_shape(ty: &EditorType) -> serde_json::Value {
+    // everything else, like bounds, defaults or the rust type's name, only matters to the editor
+    const STORED: &[&str] = &["type", "representation", "locales", "to"];
+
+    let mut shape = serde_json::to_value(ty).expect("fields should always serialize");
+    let serde_json::Value::Object(object) = &mut shape else {
+        unreachable!("editor types are tagged, so they're always objects")
+    };
+    object.retain(|key, _| STORED.contains(&key.as_str()));
+
+    match ty {
+        EditorType::Toggle { value, .. } => {
+            object.insert("value".into(), type_shape(value));
+        }
+        EditorType::Array { of, .. } | EditorType::Localized { of, .. } => {
+            object.insert("of".into(), type_shape(of));
+        }
+        EditorType::Struct { fields, .. } => {
+            object.insert("fields".into(), fields.iter().map(field_shape).collect());
+        }
+        EditorType::Enum { variants, .. } => {
+            let variants = variants
+                .iter()
+                .map(|variant| {
+                    serde_json::json!({
+                        "variant_name": variant.variant_name,
+                        "kind": variant.kind,
+                        "fields": variant
+                            .fields
+                            .as_ref()
+                            .map(|fields| fields.iter().map(field_shape).collect::<Vec<_>>()),
+                    })
+                })
+                .collect();
+            object.insert("variants".into(), variants);
+        }
+        EditorType::RichText {
+            annotation, embed, ..
+        } => {
+            object.insert("annotation".into(), type_shape(annotation));
+            object.insert("embed".into(), type_shape(embed));
+        }
+        _ => {}
+    }
+
+    shape
 }
 
 pub trait Document: Validate + Debug {
@@ -55,6 +167,22 @@ pub trait Document: Validate + Debug {
     const SINGLETON: bool;
 
     fn fields() -> &'static [EditorField];
+    /// Migrations for this document's stored content, see [`migrations::Migration`].
+    #[must_use]
+    fn migrations() -> &'static [migrations::Migration] {
+        &[]
+    }
+    /// How fields are grouped in the editor, fields that aren't in a group are shown before any of them.
+    #[must_use]
+    fn groups() -> &'static [FieldGroup] {
+        &[]
+    }
+    /// A hash of this document's fields, which changes whenever the shape of it's content does.
+    /// Items remember the hash they were written under, see [`Item::schema_hash`].
+    #[must_use]
+    fn schema_hash() -> String {
+        schema_hash(Self::fields())
+    }
     #[must_use]
     fn schema() -> Schema {
         Schema {
@@ -64,6 +192,16 @@ pub trait Document: Validate + Debug {
             sub_label: Self::SUB_LABEL,
             singleton: Self::SINGLETON,
             fields: Self::fields(),
+            groups: Self::groups(),
+            hash: Self::schema_hash(),
+        }
+    }
+    #[must_use]
+    fn info() -> DocInfo {
+        DocInfo {
+            identifier: Self::IDENTIFIER,
+            title: Self::TITLE,
+            schema_hash: Self::schema_hash(),
         }
     }
 }
@@ -79,11 +217,44 @@ pub struct Item<D> {
     pub modified_at: DateTime<Utc>,
     #[serde(rename = "__sc_published_at")]
     pub published_at: Option<DateTime<Utc>>,
+    /// The [`Document::schema_hash`] this item was last written under.
+    /// `None` if it was written before schemas were hashed.
+    #[serde(rename = "__sc_schema_hash", default)]
+    pub schema_hash: Option<String>,
     #[serde(rename = "content")]
     #[ts(type = "any")]
     pub inner: D,
 }
 
+/// An immutable snapshot of a document, taken every time it's published.
+#[derive(Serialize, Deserialize, Debug, TS)]
+#[ts(export, concrete(D = String))]
+pub struct Revision<D> {
+    #[serde(rename = "__sc_revision_id")]
+    pub revision_id: String,
+    #[serde(rename = "__sc_id")]
+    pub id: String,
+    #[serde(rename = "__sc_created_at")]
+    pub created_at: DateTime<Utc>,
+    #[serde(rename = "__sc_created_by")]
+    pub created_by: User,
+    #[serde(rename = "content")]
+    #[ts(type = "any")]
+    pub inner: D,
+}
+
+/// A document that references another document, see [`types::Reference`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, TS)]
+#[ts(export)]
+pub struct IncomingReference {
+    #[serde(rename = "__sc_doc")]
+    pub doc: String,
+    #[serde(rename = "__sc_id")]
+    pub id: String,
+    /// Whether the reference is in the published version of the document, rather than it's draft.
+    pub published: bool,
+}
+
 impl<D: Document + Send + Sync> Validate for Item<D> {
     async fn validate<DB: DatabaseConnection + Sync, DD: Document + Sync>(
         &self,
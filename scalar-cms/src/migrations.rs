@@ -0,0 +1,89 @@
+use serde_json::Value;
+use thiserror::Error;
+
+use crate::Document;
+
+/// Transforms a document's stored content, for when its fields are renamed or restructured.
+/// Migrations are registered on a document with `#[document(migrations = MIGRATIONS)]`, where
+/// `MIGRATIONS` is a const or static slice of migrations.
+///
+/// Every migration runs exactly once per database, over drafts, published items and revisions alike.
+#[derive(Debug, Clone, Copy)]
+pub struct Migration {
+    /// Identifies this migration within its document. Versions have to increase along the list,
+    /// so new migrations go at the end with a higher version than any that came before.
+    pub version: u32,
+    pub name: &'static str,
+    pub migrate: fn(&mut Value),
+}
+
+impl Migration {
+    #[must_use]
+    pub const fn new(version: u32, name: &'static str, migrate: fn(&mut Value)) -> Self {
+        Self {
+            version,
+            name,
+            migrate,
+        }
+    }
+}
+
+/// Why a document's migrations can't be run. Running them anyway could apply them differently on every database.
+#[derive(Error, Debug)]
+pub enum MigrationError {
+    #[error("the migrations of {doc} have to be in order of version without duplicates, but {before} comes before {after}")]
+    OutOfOrder {
+        doc: &'static str,
+        before: u32,
+        after: u32,
+    },
+    #[error("migration {version} of {doc} was added before migration {latest}, which already ran")]
+    BeforeRan {
+        doc: &'static str,
+        version: u32,
+        latest: u32,
+    },
+}
+
+/// The migrations of a document that haven't been run yet, in the order they should run in.
+///
+/// # Errors
+///
+/// This function will return an error if the document's migrations have duplicate versions, aren't in
+/// order of version, or if one that hasn't run comes before one that has.
+pub fn pending<D: Document>(ran: &[u32]) -> Result<Vec<&'static Migration>, MigrationError> {
+    let migrations = D::migrations();
+    if let Some(pair) = migrations
+        .windows(2)
+        .find(|pair| pair[0].version >= pair[1].version)
+    {
+        return Err(MigrationError::OutOfOrder {
+            doc: D::IDENTIFIER,
+            before: pair[0].version,
+            after: pair[1].version,
+        });
+    }
+
+    let pending = migrations
+        .iter()
+        .filter(|migration| !ran.contains(&migration.version))
+        .collect::<Vec<_>>();
+    if let (Some(first), Some(&latest)) = (pending.first(), ran.iter().max()) {
+        if first.version < latest {
+            return Err(MigrationError::BeforeRan {
+                doc: D::IDENTIFIER,
+                version: first.version,
+                latest,
+            });
+        }
+    }
+
+    Ok(pending)
+}
+
+/// Runs migrations over a piece of stored content, in order.
+pub fn apply(migrations: &[&Migration], content: &mut Value) {
+    for migration in migrations {
+        (migration.migrate)(content);
+    }
+}
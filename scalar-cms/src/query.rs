@@ -0,0 +1,254 @@
+use std::borrow::Cow;
+
+use scalar_expr::{Expression, Value};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use ts_rs::TS;
+
+use crate::{Document, Item};
+
+#[derive(Error, Debug)]
+pub enum QueryError {
+    #[error("unknown field {0:?}")]
+    UnknownField(String),
+    #[error("$current can't be used outside of validation")]
+    CurrentField,
+    #[error("this document has no {0}")]
+    NoLabel(&'static str),
+    #[error("malformed filter: {0}")]
+    MalformedFilter(serde_json::Error),
+    #[error("malformed cursor")]
+    MalformedCursor,
+    #[error("limit has to be at least 1")]
+    ZeroLimit,
+    #[error("unsupported filter expression")]
+    UnsupportedExpression,
+}
+
+/// Something a document listing can be sorted by.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SortField {
+    Id,
+    CreatedAt,
+    ModifiedAt,
+    PublishedAt,
+    /// A top level field on the document itself.
+    Field(Cow<'static, str>),
+}
+
+impl SortField {
+    /// Parses a sort field, resolving `__sc_label` and `__sc_sub_label` to the document's label fields.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the field doesn't exist on the document.
+    pub fn parse<D: Document>(field: &str) -> Result<Self, QueryError> {
+        match field {
+            "__sc_id" => Ok(Self::Id),
+            "__sc_created_at" => Ok(Self::CreatedAt),
+            "__sc_modified_at" => Ok(Self::ModifiedAt),
+            "__sc_published_at" => Ok(Self::PublishedAt),
+            "__sc_label" => D::LABEL
+                .map(|label| Self::Field(label.into()))
+                .ok_or(QueryError::NoLabel("label")),
+            "__sc_sub_label" => D::SUB_LABEL
+                .map(|label| Self::Field(label.into()))
+                .ok_or(QueryError::NoLabel("sub label")),
+            field => check_field::<D>(field).map(|()| Self::Field(field.to_owned().into())),
+        }
+    }
+
+    /// Gets the value of this field from an item.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a date fails to serialize, which should never happen.
+    #[must_use]
+    pub fn value_of(&self, item: &Item<serde_json::Value>) -> serde_json::Value {
+        match self {
+            Self::Id => item.id.clone().into(),
+            Self::CreatedAt => serde_json::to_value(item.created_at).expect("dates serialize"),
+            Self::ModifiedAt => serde_json::to_value(item.modified_at).expect("dates serialize"),
+            Self::PublishedAt => serde_json::to_value(item.published_at).expect("dates serialize"),
+            Self::Field(field) => item.inner.get(field.as_ref()).cloned().unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortDirection {
+    Ascending,
+    Descending,
+}
+
+/// A field to sort by. Nulls sort before every other value, so they come first when ascending and last
+/// when descending, and backends have to order and page through them the same way.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SortKey {
+    pub field: SortField,
+    pub direction: SortDirection,
+}
+
+impl SortKey {
+    /// Parses a comma separated list of sort keys. Keys prefixed with `-` are sorted in descending order.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if any of the fields don't exist on the document.
+    pub fn parse_list<D: Document>(keys: &str) -> Result<Vec<Self>, QueryError> {
+        keys.split(',')
+            .map(str::trim)
+            .filter(|key| !key.is_empty())
+            .map(|key| {
+                let (direction, field) = match key.strip_prefix('-') {
+                    Some(field) => (SortDirection::Descending, field),
+                    None => (SortDirection::Ascending, key),
+                };
+                Ok(Self {
+                    field: SortField::parse::<D>(field)?,
+                    direction,
+                })
+            })
+            .collect()
+    }
+}
+
+/// A position in a sorted listing. Holds the value of every sort key (plus the id) of the last item on a page.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Cursor(pub Vec<serde_json::Value>);
+
+impl Cursor {
+    /// Creates a cursor pointing right after the given item.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a date fails to serialize, which should never happen.
+    #[must_use]
+    pub fn after(item: &Item<serde_json::Value>, keys: &[SortKey]) -> Self {
+        Self(keys.iter().map(|key| key.field.value_of(item)).collect())
+    }
+
+    /// Decodes a cursor from a string made by [`Cursor::encode`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the cursor is malformed.
+    pub fn decode(cursor: &str) -> Result<Self, QueryError> {
+        serde_json::from_str(cursor).map_err(|_| QueryError::MalformedCursor)
+    }
+
+    /// Encodes this cursor so it can be passed around as a string.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the cursor fails to serialize, which should never happen.
+    #[must_use]
+    pub fn encode(&self) -> String {
+        serde_json::to_string(self).expect("json values should always serialize")
+    }
+}
+
+/// How many items a page has when a listing doesn't ask for a limit.
+pub const DEFAULT_LIMIT: usize = 100;
+
+/// The most items a page can have.
+pub const MAX_LIMIT: usize = 1000;
+
+/// A filtered, sorted and paginated listing of documents.
+#[derive(Debug, Default)]
+pub struct ListQuery {
+    pub filter: Option<Expression>,
+    pub sort: Vec<SortKey>,
+    pub cursor: Option<Cursor>,
+    pub limit: Option<usize>,
+}
+
+impl ListQuery {
+    /// Makes sure every field referenced by this query exists on the document,
+    /// so backends can safely put them in queries.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the query references something it can't.
+    pub fn check<D: Document>(&self) -> Result<(), QueryError> {
+        if let Some(filter) = &self.filter {
+            check_expression(filter)?;
+        }
+        for value in self.filter.iter().flat_map(Expression::values) {
+            match value {
+                Value::CurrentField => return Err(QueryError::CurrentField),
+                Value::Ident(ident) => check_field::<D>(ident)?,
+                Value::Value(_) => {}
+            }
+        }
+        for key in &self.sort {
+            if let SortField::Field(field) = &key.field {
+                check_field::<D>(field)?;
+            }
+        }
+        if self
+            .cursor
+            .as_ref()
+            .is_some_and(|cursor| cursor.0.len() != self.sort_keys().len())
+        {
+            return Err(QueryError::MalformedCursor);
+        }
+        if self.limit == Some(0) {
+            return Err(QueryError::ZeroLimit);
+        }
+
+        Ok(())
+    }
+
+    /// How many items a page should have, [`DEFAULT_LIMIT`] if it isn't given and at most [`MAX_LIMIT`].
+    #[must_use]
+    pub fn page_size(&self) -> usize {
+        self.limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT)
+    }
+
+    /// The sort keys of this query, with the id as a final tie breaker so the order is always stable.
+    #[must_use]
+    pub fn sort_keys(&self) -> Vec<SortKey> {
+        let mut keys = self.sort.clone();
+        if !keys.iter().any(|key| key.field == SortField::Id) {
+            keys.push(SortKey {
+                field: SortField::Id,
+                direction: SortDirection::Ascending,
+            });
+        }
+        keys
+    }
+}
+
+/// Makes sure backends know how to compile every part of an expression.
+fn check_expression(expression: &Expression) -> Result<(), QueryError> {
+    match expression {
+        Expression::Equals { .. }
+        | Expression::NotEquals { .. }
+        | Expression::GreaterThan { .. }
+        | Expression::GreaterThanOrEquals { .. }
+        | Expression::LessThan { .. }
+        | Expression::LessThanOrEquals { .. } => Ok(()),
+        Expression::And { lhs, rhs } | Expression::Or { lhs, rhs } => {
+            check_expression(lhs)?;
+            check_expression(rhs)
+        }
+        _ => Err(QueryError::UnsupportedExpression),
+    }
+}
+
+fn check_field<D: Document>(field: &str) -> Result<(), QueryError> {
+    D::fields()
+        .iter()
+        .any(|f| f.name == field)
+        .then_some(())
+        .ok_or_else(|| QueryError::UnknownField(field.to_owned()))
+}
+
+#[derive(Serialize, Deserialize, Debug, TS)]
+#[ts(export)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub next_cursor: Option<String>,
+}
@@ -1,15 +1,25 @@
-use std::ops::{Deref, DerefMut};
+use std::{
+    collections::BTreeMap,
+    fmt::Debug,
+    marker::PhantomData,
+    ops::{Deref, DerefMut},
+};
 
+use chrono::Utc;
 use scalar_expr::expression;
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize, Serializer};
 
 use crate::{
     db::ValidationContext,
     editor_field::ToEditorField,
-    validations::{Validate, ValidationError},
-    DatabaseConnection, Document,
+    editor_type::VariantKind,
+    validations::{ErroredField, Reason, Validate, ValidationError},
+    DatabaseConnection, Document, EditorField, EditorType,
 };
 
+mod rich_text;
+pub use rich_text::*;
+
 macro_rules! deref {
     ($ty:ty > $target:ty) => {
         impl Deref for $ty {
@@ -69,15 +79,18 @@ impl Validate for Slug {
             .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
             .then_some(())
             .ok_or_else(|| {
-                ValidationError::Single(
-                    "slugs can only contain alphanumeic characters, -, and _.".into(),
-                )
+                ValidationError::Single(Reason::new(
+                    "slug",
+                    "slugs can only contain alphanumeic characters, -, and _.",
+                ))
             })?;
         ctx.none(expression!($current == self.0))
             .await
             .unwrap()
             .then_some(())
-            .ok_or_else(|| ValidationError::Single("slugs must be unique!".into()))
+            .ok_or_else(|| {
+                ValidationError::Single(Reason::new("not_unique", "slugs must be unique!"))
+            })
     }
 }
 
@@ -98,3 +111,317 @@ impl<T: ToEditorField> Default for Toggle<T> {
 }
 
 deref!(generic Toggle > Option<T>);
+
+/// A link to another document. When `PUBLISHED` is set, the referenced document also has to be published.
+pub struct Reference<D: Document, const PUBLISHED: bool = false> {
+    pub id: String,
+    phantom: PhantomData<fn() -> D>,
+}
+
+impl<D: Document, const PUBLISHED: bool> Reference<D, PUBLISHED> {
+    pub fn new(id: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<D: Document, const PUBLISHED: bool> Debug for Reference<D, PUBLISHED> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Reference")
+            .field("doc", &D::IDENTIFIER)
+            .field("id", &self.id)
+            .finish()
+    }
+}
+
+/// What a reference looks like when stored. The document identifier is kept around so
+/// references can be found without knowing the schema.
+#[derive(Serialize, Deserialize)]
+struct RawReference<'a> {
+    #[serde(rename = "__sc_ref")]
+    id: std::borrow::Cow<'a, str>,
+    #[serde(rename = "__sc_doc")]
+    doc: std::borrow::Cow<'a, str>,
+}
+
+/// A reference found in a document's data by [`find_references`].
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct FoundReference<'a> {
+    pub doc: &'a str,
+    pub id: &'a str,
+}
+
+/// Finds every [`Reference`] in a document's data, no matter how deeply it's nested.
+#[must_use]
+pub fn find_references(value: &serde_json::Value) -> Vec<FoundReference<'_>> {
+    fn find<'a>(value: &'a serde_json::Value, found: &mut Vec<FoundReference<'a>>) {
+        match value {
+            serde_json::Value::Object(map) => {
+                if let (Some(serde_json::Value::String(id)), Some(serde_json::Value::String(doc))) =
+                    (map.get("__sc_ref"), map.get("__sc_doc"))
+                {
+                    found.push(FoundReference { doc, id });
+                } else {
+                    map.values().for_each(|value| find(value, found));
+                }
+            }
+            serde_json::Value::Array(values) => values.iter().for_each(|value| find(value, found)),
+            _ => {}
+        }
+    }
+
+    let mut found = Vec::new();
+    find(value, &mut found);
+    found.sort_unstable();
+    found.dedup();
+    found
+}
+
+impl<D: Document, const PUBLISHED: bool> Serialize for Reference<D, PUBLISHED> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        RawReference {
+            id: self.id.as_str().into(),
+            doc: D::IDENTIFIER.into(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de, D: Document, const PUBLISHED: bool> Deserialize<'de> for Reference<D, PUBLISHED> {
+    fn deserialize<De: Deserializer<'de>>(deserializer: De) -> Result<Self, De::Error> {
+        let raw = RawReference::deserialize(deserializer)?;
+        if raw.doc != D::IDENTIFIER {
+            return Err(serde::de::Error::custom(format!(
+                "expected a reference to {}, got a reference to {}",
+                D::IDENTIFIER,
+                raw.doc
+            )));
+        }
+
+        Ok(Self::new(raw.id))
+    }
+}
+
+impl<T: Document + DeserializeOwned + Send + Sync, const PUBLISHED: bool> Validate
+    for Reference<T, PUBLISHED>
+{
+    async fn validate<DB: DatabaseConnection + Sync, D: Document + Sync>(
+        &self,
+        ctx: ValidationContext<'_, DB, D>,
+    ) -> Result<(), ValidationError> {
+        let item = ctx.get_by_id::<T>(&self.id).await.map_err(|e| {
+            tracing::error!(cause = %e, "couldn't look up reference");
+            ValidationError::Single("couldn't check if the referenced document exists".into())
+        })?;
+
+        match item {
+            None => Err(ValidationError::Single(Reason::new(
+                "not_found",
+                format!("referenced {} doesn't exist", T::TITLE),
+            ))),
+            Some(item) if PUBLISHED && item.published_at.is_none_or(|date| date > Utc::now()) => {
+                Err(ValidationError::Single(Reason::new(
+                    "unpublished",
+                    format!("referenced {} isn't published", T::TITLE),
+                )))
+            }
+            Some(_) => Ok(()),
+        }
+    }
+}
+
+/// The locales a [`Localized`] field can have values for.
+pub trait Locales {
+    /// Every locale, in the order the editor should show them.
+    const LOCALES: &'static [&'static str];
+    /// Locales that have to have a value.
+    const REQUIRED: &'static [&'static str];
+    /// Locales to fall back on, in order, when a value for the requested locale is missing.
+    const FALLBACK: &'static [&'static str];
+}
+
+/// A value per locale. The VALIDATE flag works the same way it does for images: when it's unset,
+/// the values themselves aren't validated, but required locales still are.
+#[derive(Serialize, Deserialize)]
+#[serde(
+    transparent,
+    bound(serialize = "T: Serialize", deserialize = "T: Deserialize<'de>")
+)]
+pub struct Localized<T, L: Locales, const VALIDATE: bool = true> {
+    pub values: BTreeMap<String, T>,
+    #[serde(skip)]
+    phantom: PhantomData<fn() -> L>,
+}
+
+impl<T, L: Locales, const VALIDATE: bool> Localized<T, L, VALIDATE> {
+    #[must_use]
+    pub fn new(values: BTreeMap<String, T>) -> Self {
+        Self {
+            values,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Gets the value for a locale, following [`Locales::FALLBACK`] if it's missing.
+    #[must_use]
+    pub fn resolve(&self, locale: &str) -> Option<&T> {
+        std::iter::once(locale)
+            .chain(L::FALLBACK.iter().copied())
+            .find_map(|locale| self.values.get(locale))
+    }
+
+    fn locale_errors(&self) -> Vec<ErroredField> {
+        let missing = L::REQUIRED
+            .iter()
+            .filter(|locale| !self.values.contains_key(**locale))
+            .map(|locale| ErroredField {
+                field: (*locale).into(),
+                error: ValidationError::Single(Reason::new(
+                    "required",
+                    "a value is required for this locale",
+                )),
+            });
+        let unknown = self
+            .values
+            .keys()
+            .filter(|locale| !L::LOCALES.contains(&locale.as_str()))
+            .map(|locale| ErroredField {
+                field: locale.as_str().into(),
+                error: ValidationError::Single(Reason::new("unknown_locale", "unknown locale")),
+            });
+
+        missing.chain(unknown).collect()
+    }
+}
+
+impl<T, L: Locales, const VALIDATE: bool> Default for Localized<T, L, VALIDATE> {
+    fn default() -> Self {
+        Self::new(BTreeMap::new())
+    }
+}
+
+impl<T: Debug, L: Locales, const VALIDATE: bool> Debug for Localized<T, L, VALIDATE> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Localized").field(&self.values).finish()
+    }
+}
+
+impl<T, L: Locales, const VALIDATE: bool> Deref for Localized<T, L, VALIDATE> {
+    type Target = BTreeMap<String, T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.values
+    }
+}
+
+impl<T, L: Locales, const VALIDATE: bool> DerefMut for Localized<T, L, VALIDATE> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.values
+    }
+}
+
+impl<T: Validate + Sync, L: Locales> Validate for Localized<T, L, true> {
+    async fn validate<DB: DatabaseConnection + Sync, D: Document + Sync>(
+        &self,
+        ctx: ValidationContext<'_, DB, D>,
+    ) -> Result<(), ValidationError> {
+        let mut errors = self.locale_errors();
+
+        for (locale, value) in &self.values {
+            if let Err(error) = value.validate(ctx).await {
+                errors.push(ErroredField {
+                    field: locale.as_str().into(),
+                    error,
+                });
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ValidationError::Composite(errors))
+        }
+    }
+}
+
+impl<T: Send + Sync, L: Locales> Validate for Localized<T, L, false> {
+    async fn validate<DB: DatabaseConnection, D: Document>(
+        &self,
+        _ctx: ValidationContext<'_, DB, D>,
+    ) -> Result<(), ValidationError> {
+        let errors = self.locale_errors();
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ValidationError::Composite(errors))
+        }
+    }
+}
+
+/// Resolves every [`Localized`] field in a document's data to the value for `locale`, using the
+/// document's fields to find them. Values that can't be resolved become `null`.
+pub fn localize(value: &mut serde_json::Value, fields: &[EditorField], locale: &str) {
+    let serde_json::Value::Object(map) = value else {
+        return;
+    };
+
+    for field in fields {
+        if let Some(value) = map.get_mut(field.name) {
+            localize_type(value, &field.field_type, locale);
+        }
+    }
+}
+
+fn localize_type(value: &mut serde_json::Value, field_type: &EditorType, locale: &str) {
+    match field_type {
+        EditorType::Localized { fallback, of, .. } => {
+            let resolved = match value {
+                serde_json::Value::Object(map) => std::iter::once(locale)
+                    .chain(fallback.iter().copied())
+                    .find_map(|locale| map.remove(locale)),
+                _ => None,
+            };
+            *value = resolved.unwrap_or_default();
+            localize_type(value, of, locale);
+        }
+        EditorType::Struct { fields, .. } => localize(value, fields, locale),
+        EditorType::Array { of, .. } => {
+            if let serde_json::Value::Array(values) = value {
+                for value in values {
+                    localize_type(value, of, locale);
+                }
+            }
+        }
+        EditorType::Toggle {
+            value: field_type, ..
+        } => localize_type(value, field_type, locale),
+        EditorType::Enum {
+            representation,
+            variants,
+            ..
+        } => {
+            let Some((variant, content)) = representation.content_mut(value, variants) else {
+                return;
+            };
+            let fields = variant.fields.as_deref().unwrap_or_default();
+            match (variant.kind, fields) {
+                (VariantKind::Struct, fields) => localize(content, fields, locale),
+                (VariantKind::Newtype, [field]) => {
+                    localize_type(content, &field.field_type, locale);
+                }
+                (VariantKind::Tuple, fields) => {
+                    if let serde_json::Value::Array(values) = content {
+                        for (value, field) in values.iter_mut().zip(fields) {
+                            localize_type(value, &field.field_type, locale);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        _ => {}
+    }
+}
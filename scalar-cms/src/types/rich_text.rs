@@ -0,0 +1,556 @@
+use std::{collections::HashSet, fmt::Write, marker::PhantomData};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{
+    db::ValidationContext,
+    editor_field::ToEditorField,
+    validations::{ErroredField, ErroredItem, Reason, Validate, ValidationError},
+    DatabaseConnection, Document, EditorField, EditorType,
+};
+
+/// Configures what a [`RichText`] field allows.
+pub trait RichTextConfig {
+    /// Block styles, like `normal` or `h1`. The first one is the default.
+    const STYLES: &'static [&'static str];
+    /// Decorators that can be put on spans, like `strong` or `em`.
+    const DECORATORS: &'static [&'static str];
+    /// Whether blocks can be list items.
+    const LISTS: bool;
+    /// Data that can be attached to spans, like links.
+    type Annotation: ToEditorField + RenderHtml + Serialize + DeserializeOwned + Validate + Sync;
+    /// Custom objects that can be put between blocks. Use `()` to disallow them.
+    type Embed: ToEditorField + RenderHtml + Serialize + DeserializeOwned + Validate + Sync;
+}
+
+/// A reasonable set of styles and decorators, with links and no embeds.
+pub struct DefaultRichText;
+
+impl RichTextConfig for DefaultRichText {
+    const STYLES: &'static [&'static str] = &["normal", "h1", "h2", "h3", "h4", "blockquote"];
+    const DECORATORS: &'static [&'static str] = &["strong", "em", "code", "underline", "strike"];
+    const LISTS: bool = true;
+    type Annotation = Link;
+    type Embed = ();
+}
+
+/// How annotations and embeds get rendered by [`RichText::to_html`].
+pub trait RenderHtml {
+    /// Wraps already rendered html. Used for annotations.
+    fn wrap_html(&self, inner: &str) -> String {
+        inner.to_owned()
+    }
+
+    /// Renders this on it's own. Used for embeds.
+    fn to_html(&self) -> String {
+        String::new()
+    }
+}
+
+impl RenderHtml for () {}
+
+/// Block based rich text, made out of text blocks and embedded objects.
+#[derive(Serialize, Deserialize)]
+#[serde(transparent, bound(serialize = "", deserialize = ""))]
+pub struct RichText<C: RichTextConfig = DefaultRichText> {
+    pub blocks: Vec<Block<C::Annotation, C::Embed>>,
+    #[serde(skip)]
+    phantom: PhantomData<fn() -> C>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "_type", rename_all = "snake_case")]
+pub enum Block<A, E> {
+    #[serde(rename = "block")]
+    Text(TextBlock<A>),
+    Embed(EmbedBlock<E>),
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TextBlock<A> {
+    #[serde(rename = "_key")]
+    pub key: String,
+    pub style: String,
+    #[serde(default)]
+    pub list: Option<ListKind>,
+    /// How deeply nested a list item is, starting at 1.
+    #[serde(default)]
+    pub level: Option<usize>,
+    pub children: Vec<Span>,
+    #[serde(default = "Vec::new")]
+    pub mark_defs: Vec<MarkDef<A>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ListKind {
+    Bullet,
+    Number,
+}
+
+/// A run of text with the same marks.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Span {
+    pub text: String,
+    /// Decorators, or keys of annotations in the block's `mark_defs`.
+    #[serde(default)]
+    pub marks: Vec<String>,
+}
+
+/// An annotation, referenced by it's key from span marks.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct MarkDef<A> {
+    #[serde(rename = "_key")]
+    pub key: String,
+    pub value: A,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct EmbedBlock<E> {
+    #[serde(rename = "_key")]
+    pub key: String,
+    pub value: E,
+}
+
+impl<C: RichTextConfig> RichText<C> {
+    #[must_use]
+    pub fn new(blocks: Vec<Block<C::Annotation, C::Embed>>) -> Self {
+        Self {
+            blocks,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Renders the text blocks to plain text, with a blank line between blocks. Embeds are skipped.
+    #[must_use]
+    pub fn to_plain_text(&self) -> String {
+        self.blocks
+            .iter()
+            .filter_map(|block| match block {
+                Block::Text(block) => Some(
+                    block
+                        .children
+                        .iter()
+                        .map(|span| span.text.as_str())
+                        .collect::<String>(),
+                ),
+                Block::Embed(_) => None,
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+
+    /// Renders to html. All text is escaped, annotations and embeds are rendered with [`RenderHtml`].
+    #[must_use]
+    pub fn to_html(&self) -> String {
+        let mut html = String::new();
+        // every open list has an open <li> in it
+        let mut lists: Vec<ListKind> = Vec::new();
+
+        for block in &self.blocks {
+            if let Block::Text(TextBlock {
+                list: Some(kind),
+                level,
+                ..
+            }) = block
+            {
+                let level = level.unwrap_or(1).max(1);
+                while lists.len() > level || (lists.len() == level && lists.last() != Some(kind)) {
+                    close_list(&mut html, lists.pop());
+                }
+                if lists.len() == level {
+                    html.push_str("</li>");
+                }
+                while lists.len() < level {
+                    html.push_str(list_tag(*kind).0);
+                    lists.push(*kind);
+                }
+                html.push_str("<li>");
+            } else {
+                while !lists.is_empty() {
+                    close_list(&mut html, lists.pop());
+                }
+            }
+
+            match block {
+                Block::Text(block) if block.list.is_some() => {
+                    html.push_str(&render_spans(block));
+                }
+                Block::Text(block) => {
+                    let tag = match block.style.as_str() {
+                        style @ ("h1" | "h2" | "h3" | "h4" | "h5" | "h6" | "blockquote") => style,
+                        _ => "p",
+                    };
+                    let _ = write!(html, "<{tag}>{}</{tag}>", render_spans(block));
+                }
+                Block::Embed(embed) => html.push_str(&embed.value.to_html()),
+            }
+        }
+        while !lists.is_empty() {
+            close_list(&mut html, lists.pop());
+        }
+
+        html
+    }
+}
+
+fn list_tag(kind: ListKind) -> (&'static str, &'static str) {
+    match kind {
+        ListKind::Bullet => ("<ul>", "</ul>"),
+        ListKind::Number => ("<ol>", "</ol>"),
+    }
+}
+
+fn close_list(html: &mut String, kind: Option<ListKind>) {
+    if let Some(kind) = kind {
+        html.push_str("</li>");
+        html.push_str(list_tag(kind).1);
+    }
+}
+
+fn render_spans<A: RenderHtml>(block: &TextBlock<A>) -> String {
+    block
+        .children
+        .iter()
+        .map(|span| {
+            span.marks
+                .iter()
+                .fold(escape_html(&span.text), |inner, mark| {
+                    if let Some(def) = block.mark_defs.iter().find(|def| &def.key == mark) {
+                        return def.value.wrap_html(&inner);
+                    }
+
+                    let tag = match mark.as_str() {
+                        "strong" => "strong",
+                        "em" => "em",
+                        "code" => "code",
+                        "underline" => "u",
+                        "strike" => "s",
+                        _ => {
+                            return format!("<span class=\"{}\">{inner}</span>", escape_html(mark))
+                        }
+                    };
+                    format!("<{tag}>{inner}</{tag}>")
+                })
+        })
+        .collect()
+}
+
+/// Escapes text so it can be safely put in html, including attribute values.
+#[must_use]
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+impl<C: RichTextConfig> Default for RichText<C> {
+    fn default() -> Self {
+        Self::new(Vec::new())
+    }
+}
+
+impl<C: RichTextConfig> std::fmt::Debug for RichText<C>
+where
+    C::Annotation: std::fmt::Debug,
+    C::Embed: std::fmt::Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("RichText").field(&self.blocks).finish()
+    }
+}
+
+fn single(
+    field: impl Into<crate::validations::Field>,
+    code: &str,
+    message: String,
+) -> ErroredField {
+    ErroredField {
+        field: field.into(),
+        error: ValidationError::Single(Reason::new(code, message)),
+    }
+}
+
+/// Nests errors about items of a list under the list's field.
+fn items(field: &'static str, items: Vec<ErroredItem>) -> Option<ErroredField> {
+    (!items.is_empty()).then(|| ErroredField {
+        field: field.into(),
+        error: ValidationError::Items(items),
+    })
+}
+
+impl<C: RichTextConfig> RichText<C> {
+    /// Checks everything about a block that doesn't need the database.
+    fn block_errors(block: &TextBlock<C::Annotation>) -> Vec<ErroredField> {
+        let mut errors = Vec::new();
+
+        if !C::STYLES.contains(&block.style.as_str()) {
+            errors.push(single(
+                "style",
+                "unknown_style",
+                format!("unknown style {:?}", block.style),
+            ));
+        }
+        if block.list.is_some() && !C::LISTS {
+            errors.push(single(
+                "list",
+                "not_allowed",
+                "lists aren't allowed here".into(),
+            ));
+        }
+        if block.level == Some(0) {
+            errors.push(single(
+                "level",
+                "too_small",
+                "list levels start at 1".into(),
+            ));
+        }
+
+        let mut keys = HashSet::new();
+        let duplicates = block
+            .mark_defs
+            .iter()
+            .enumerate()
+            .filter(|(_, def)| !keys.insert(def.key.as_str()))
+            .map(|(index, def)| ErroredItem {
+                index,
+                error: ValidationError::Composite(vec![single(
+                    "_key",
+                    "duplicate_key",
+                    format!("duplicate key {:?}", def.key),
+                )]),
+            })
+            .collect();
+        errors.extend(items("mark_defs", duplicates));
+
+        let unknown_marks = block
+            .children
+            .iter()
+            .enumerate()
+            .filter_map(|(index, span)| {
+                let mark = span.marks.iter().find(|mark| {
+                    !C::DECORATORS.contains(&mark.as_str()) && !keys.contains(mark.as_str())
+                })?;
+                Some(ErroredItem {
+                    index,
+                    error: ValidationError::Composite(vec![single(
+                        "marks",
+                        "unknown_mark",
+                        format!("unknown mark {mark:?}"),
+                    )]),
+                })
+            })
+            .collect();
+        errors.extend(items("children", unknown_marks));
+
+        errors
+    }
+}
+
+impl<C: RichTextConfig> Validate for RichText<C> {
+    async fn validate<DB: DatabaseConnection + Sync, D: Document + Sync>(
+        &self,
+        ctx: ValidationContext<'_, DB, D>,
+    ) -> Result<(), ValidationError> {
+        let mut errors = Vec::new();
+        let mut keys = HashSet::new();
+
+        for (index, block) in self.blocks.iter().enumerate() {
+            let key = match block {
+                Block::Text(block) => &block.key,
+                Block::Embed(block) => &block.key,
+            };
+            let mut block_errors = Vec::new();
+            if !keys.insert(key.as_str()) {
+                block_errors.push(single(
+                    "_key",
+                    "duplicate_key",
+                    format!("duplicate key {key:?}"),
+                ));
+            }
+
+            match block {
+                Block::Text(block) => {
+                    block_errors.extend(Self::block_errors(block));
+                    let mut defs = Vec::new();
+                    for (index, def) in block.mark_defs.iter().enumerate() {
+                        if let Err(error) = def.value.validate(ctx).await {
+                            defs.push(ErroredItem {
+                                index,
+                                error: ValidationError::Composite(vec![ErroredField {
+                                    field: "value".into(),
+                                    error,
+                                }]),
+                            });
+                        }
+                    }
+                    block_errors.extend(items("mark_defs", defs));
+                }
+                Block::Embed(block) => {
+                    if let Err(error) = block.value.validate(ctx).await {
+                        block_errors.push(ErroredField {
+                            field: "value".into(),
+                            error,
+                        });
+                    }
+                }
+            }
+
+            if !block_errors.is_empty() {
+                errors.push(ErroredItem {
+                    index,
+                    error: ValidationError::Composite(block_errors),
+                });
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ValidationError::Items(errors))
+        }
+    }
+}
+
+/// A link annotation.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Link {
+    pub href: String,
+    /// Whether the link should open in a new tab.
+    #[serde(default)]
+    pub blank: bool,
+}
+
+impl Link {
+    fn is_safe(&self) -> bool {
+        // `//host` and `/\host` are protocol relative to browsers, so they can point anywhere
+        if let Some(path) = self.href.strip_prefix('/') {
+            return !path.starts_with(['/', '\\']);
+        }
+
+        ["http://", "https://", "mailto:", "tel:", "#"]
+            .iter()
+            .any(|prefix| self.href.starts_with(prefix))
+    }
+}
+
+impl RenderHtml for Link {
+    fn wrap_html(&self, inner: &str) -> String {
+        if !self.is_safe() {
+            return inner.to_owned();
+        }
+
+        let target = if self.blank {
+            " target=\"_blank\" rel=\"noopener noreferrer\""
+        } else {
+            ""
+        };
+        format!(
+            "<a href=\"{}\"{target}>{inner}</a>",
+            escape_html(&self.href)
+        )
+    }
+}
+
+impl Validate for Link {
+    async fn validate<DB: DatabaseConnection, D: Document>(
+        &self,
+        _ctx: ValidationContext<'_, DB, D>,
+    ) -> Result<(), ValidationError> {
+        self.is_safe().then_some(()).ok_or_else(|| {
+            ValidationError::Composite(vec![single(
+                "href",
+                "unsafe_link",
+                "links must be http(s), mailto, tel, or relative".into(),
+            )])
+        })
+    }
+}
+
+impl ToEditorField for Link {
+    fn to_editor_field(
+        default: Option<impl Into<Self>>,
+        name: &'static str,
+        title: &'static str,
+        placeholder: Option<&'static str>,
+        validator: Option<&'static str>,
+        component_key: Option<&'static str>,
+    ) -> EditorField
+    where
+        Self: std::marker::Sized,
+    {
+        EditorField::new(
+            name,
+            title,
+            placeholder,
+            validator,
+            EditorType::Struct {
+                component_key: component_key.map(Into::into).or(Some("link".into())),
+                name: Some("Link"),
+                default: default
+                    .map(|v| serde_json::to_value(v.into()).expect("this should never fail")),
+                fields: vec![
+                    String::to_editor_field(None::<String>, "href", "URL", None, None, None),
+                    bool::to_editor_field(
+                        None::<bool>,
+                        "blank",
+                        "Open in a new tab",
+                        None,
+                        None,
+                        None,
+                    ),
+                ],
+            },
+        )
+    }
+}
+
+impl<C: RichTextConfig> ToEditorField for RichText<C> {
+    fn to_editor_field(
+        default: Option<impl Into<Self>>,
+        name: &'static str,
+        title: &'static str,
+        placeholder: Option<&'static str>,
+        validator: Option<&'static str>,
+        component_key: Option<&'static str>,
+    ) -> EditorField
+    where
+        Self: std::marker::Sized,
+    {
+        let annotation = C::Annotation::to_editor_field(
+            None::<C::Annotation>,
+            "annotation",
+            "Annotation",
+            None,
+            None,
+            None,
+        );
+        let embed = C::Embed::to_editor_field(None::<C::Embed>, "embed", "Embed", None, None, None);
+
+        EditorField::new(
+            name,
+            title,
+            placeholder,
+            validator,
+            EditorType::RichText {
+                component_key: component_key.map(Into::into),
+                default: default
+                    .map(|v| serde_json::to_value(v.into()).expect("this should never fail")),
+                styles: C::STYLES,
+                decorators: C::DECORATORS,
+                lists: C::LISTS,
+                annotation: Box::new(annotation.field_type),
+                embed: Box::new(embed.field_type),
+            },
+        )
+    }
+}
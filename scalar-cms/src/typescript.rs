@@ -0,0 +1,420 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+    fs, io,
+    path::Path,
+};
+
+use crate::{
+    editor_field::ToEditorField,
+    editor_type::{EnumRepresentation, EnumVariant, VariantKind},
+    Document, EditorField, EditorType,
+};
+
+const HEADER: &str = "// This file was generated by scalar. Do not edit this file manually.\n";
+
+struct Declaration {
+    source: String,
+    dependencies: BTreeSet<String>,
+}
+
+/// Generates TypeScript types for the content of documents, from their fields.
+///
+/// Every document gets a type named after it's identifier in pascal case, along with an `Item`
+/// alias for it (e.g. `BlogPost` and `BlogPostItem`). Structs and enums deriving `EditorField`
+/// or using `doc_enum` get their own type, generic ones are inlined.
+///
+/// The files import `Item` from the bindings ts-rs exports, so they're meant to be written next to them.
+///
+/// Every type needs a unique name, so adding two different types with the same name (like a `link` document
+/// and a `Link` struct) panics.
+#[derive(Default)]
+pub struct TypeScript {
+    declarations: BTreeMap<String, Declaration>,
+}
+
+impl TypeScript {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// # Panics
+    ///
+    /// Panics if a different type with the same name was already added.
+    #[must_use]
+    pub fn with_doc<D: Document>(mut self) -> Self {
+        self.add_doc::<D>();
+        self
+    }
+
+    /// Adds a type that isn't necessarily used by a document. Types without a name aren't exported.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a different type with the same name was already added.
+    #[must_use]
+    pub fn with_type<T: ToEditorField>(mut self) -> Self {
+        self.add_type::<T>();
+        self
+    }
+
+    /// # Panics
+    ///
+    /// Panics if a different type with the same name was already added.
+    pub fn add_doc<D: Document>(&mut self) {
+        let name = type_name(D::IDENTIFIER);
+        let mut dependencies = BTreeSet::from(["Item".to_owned()]);
+        let content = self.object(D::fields(), &mut dependencies);
+
+        let source = format!(
+            "export type {name} = {content};\n\nexport type {name}Item = Omit<Item, \"content\"> & {{ content: {name} }};\n"
+        );
+        self.insert(
+            name,
+            Declaration {
+                source,
+                dependencies,
+            },
+        );
+    }
+
+    /// # Panics
+    ///
+    /// Panics if a different type with the same name was already added.
+    pub fn add_type<T: ToEditorField>(&mut self) {
+        let field = T::to_editor_field(None::<T>, "", "", None, None, None);
+        self.type_of(&field.field_type, &mut BTreeSet::new());
+    }
+
+    /// Every generated file, by file name.
+    pub fn files(&self) -> impl Iterator<Item = (String, String)> + '_ {
+        self.declarations.iter().map(|(name, declaration)| {
+            let imports =
+                declaration
+                    .dependencies
+                    .iter()
+                    .fold(String::new(), |mut imports, dependency| {
+                        // writing to a string can't fail
+                        let _ = writeln!(
+                            imports,
+                            "import type {{ {dependency} }} from \"./{dependency}\";"
+                        );
+                        imports
+                    });
+            let file = format!("{HEADER}{imports}\n{}", declaration.source);
+
+            (format!("{name}.ts"), file)
+        })
+    }
+
+    /// Writes every generated file to a directory, creating it if it doesn't exist.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the directory can't be created, or a file can't be written.
+    pub fn export_all_to(&self, dir: impl AsRef<Path>) -> io::Result<()> {
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir)?;
+        for (file_name, contents) in self.files() {
+            fs::write(dir.join(file_name), contents)?;
+        }
+
+        Ok(())
+    }
+
+    /// Adds a named declaration, unless it's already been added.
+    fn declare(
+        &mut self,
+        name: &str,
+        dependencies: &mut BTreeSet<String>,
+        body: impl FnOnce(&mut Self, &mut BTreeSet<String>) -> String,
+    ) -> String {
+        dependencies.insert(name.to_owned());
+        // the body is built again even if it's been added, to make sure it's the same type
+        let mut own_dependencies = BTreeSet::new();
+        let body = body(self, &mut own_dependencies);
+        own_dependencies.remove(name);
+        self.insert(
+            name.to_owned(),
+            Declaration {
+                source: format!("export type {name} = {body};\n"),
+                dependencies: own_dependencies,
+            },
+        );
+
+        name.to_owned()
+    }
+
+    fn insert(&mut self, name: String, declaration: Declaration) {
+        // it would overwrite the `Item` ts-rs exports
+        assert!(
+            name != "Item",
+            "`Item` is reserved, so no type or document can be named `Item` in TypeScript"
+        );
+        match self.declarations.get(&name) {
+            Some(existing) => assert!(
+                existing.source == declaration.source,
+                "two different types are named `{name}` in TypeScript, rename one of them"
+            ),
+            None => {
+                self.declarations.insert(name, declaration);
+            }
+        }
+    }
+
+    fn object(&mut self, fields: &[EditorField], dependencies: &mut BTreeSet<String>) -> String {
+        let properties = fields
+            .iter()
+            .map(|field| self.property(field, dependencies))
+            .collect::<Vec<_>>();
+
+        if properties.is_empty() {
+            "Record<string, never>".into()
+        } else {
+            format!("{{ {} }}", properties.join(", "))
+        }
+    }
+
+    fn property(&mut self, field: &EditorField, dependencies: &mut BTreeSet<String>) -> String {
+        let ty = self.type_of(&field.field_type, dependencies);
+        let property = if field.required {
+            format!("{}: {ty}", property_key(field.name))
+        } else {
+            format!("{}: {ty} | null", property_key(field.name))
+        };
+
+        format!("{}{property}", js_doc(field))
+    }
+
+    /// A variant's type, as it's tagged by the enum's representation.
+    fn variant(
+        &mut self,
+        variant: &EnumVariant,
+        representation: EnumRepresentation,
+        dependencies: &mut BTreeSet<String>,
+    ) -> String {
+        let name = string_literal(variant.variant_name);
+        let fields = variant.fields.as_deref().unwrap_or_default();
+        if let (EnumRepresentation::Internal { tag }, VariantKind::Struct) =
+            (representation, variant.kind)
+        {
+            let properties = std::iter::once(format!("{}: {name}", property_key(tag)))
+                .chain(
+                    fields
+                        .iter()
+                        .map(|field| self.property(field, dependencies)),
+                )
+                .collect::<Vec<_>>();
+            return format!("{{ {} }}", properties.join(", "));
+        }
+
+        let content = match (variant.kind, fields) {
+            (VariantKind::Unit, _) => None,
+            (VariantKind::Newtype, [field]) => Some(self.element(field, dependencies)),
+            (VariantKind::Struct, fields) => Some(self.object(fields, dependencies)),
+            (VariantKind::Tuple | VariantKind::Newtype, fields) => {
+                let elements = fields
+                    .iter()
+                    .map(|field| self.element(field, dependencies))
+                    .collect::<Vec<_>>();
+                Some(format!("[{}]", elements.join(", ")))
+            }
+        };
+
+        match (representation, content) {
+            (EnumRepresentation::External, None) => name,
+            (EnumRepresentation::External, Some(content)) => {
+                format!("{{ {}: {content} }}", property_key(variant.variant_name))
+            }
+            (
+                EnumRepresentation::Internal { tag } | EnumRepresentation::Adjacent { tag, .. },
+                None,
+            ) => format!("{{ {}: {name} }}", property_key(tag)),
+            (EnumRepresentation::Internal { tag }, Some(content)) => {
+                format!("{{ {}: {name} }} & ({content})", property_key(tag))
+            }
+            (EnumRepresentation::Adjacent { tag, content: key }, Some(content)) => format!(
+                "{{ {}: {name}, {}: {content} }}",
+                property_key(tag),
+                property_key(key)
+            ),
+            (EnumRepresentation::Untagged, None) => "null".into(),
+            (EnumRepresentation::Untagged, Some(content)) => content,
+        }
+    }
+
+    /// A field's type on it's own, like in a tuple.
+    fn element(&mut self, field: &EditorField, dependencies: &mut BTreeSet<String>) -> String {
+        let ty = self.type_of(&field.field_type, dependencies);
+        if field.required {
+            ty
+        } else {
+            format!("{ty} | null")
+        }
+    }
+
+    fn type_of(&mut self, ty: &EditorType, dependencies: &mut BTreeSet<String>) -> String {
+        match ty {
+            EditorType::Toggle { value, .. } => {
+                format!("{} | null", self.type_of(value, dependencies))
+            }
+            EditorType::Bool { .. } => "boolean".into(),
+            EditorType::Integer { .. } | EditorType::Float { .. } => "number".into(),
+            EditorType::Struct { name, fields, .. } => match name {
+                Some(name) => self.declare(name, dependencies, |this, dependencies| {
+                    this.object(fields, dependencies)
+                }),
+                None => self.object(fields, dependencies),
+            },
+            EditorType::Enum {
+                name,
+                representation,
+                variants,
+                ..
+            } => {
+                let union = |this: &mut Self, dependencies: &mut BTreeSet<String>| {
+                    variants
+                        .iter()
+                        .map(|variant| this.variant(variant, *representation, dependencies))
+                        .collect::<Vec<_>>()
+                        .join(" | ")
+                };
+                match name {
+                    Some(name) => self.declare(name, dependencies, union),
+                    None => union(self, dependencies),
+                }
+            }
+            EditorType::Array { of, .. } => format!("Array<{}>", self.type_of(of, dependencies)),
+            EditorType::SingleLine { .. }
+            | EditorType::MultiLine { .. }
+            | EditorType::Markdown { .. }
+            | EditorType::Decimal { .. }
+            | EditorType::Date { .. }
+            | EditorType::DateTime { .. } => "string".into(),
+            EditorType::Localized {
+                locales,
+                required,
+                of,
+                ..
+            } => {
+                let of = self.type_of(of, dependencies);
+                let properties = locales
+                    .iter()
+                    .map(|locale| {
+                        let optional = if required.contains(locale) { "" } else { "?" };
+                        format!("{}{optional}: {of}", property_key(locale))
+                    })
+                    .collect::<Vec<_>>();
+                format!("{{ {} }}", properties.join(", "))
+            }
+            EditorType::RichText {
+                styles,
+                lists,
+                annotation,
+                embed,
+                ..
+            } => {
+                let style = union_of(styles);
+                let list = if *lists {
+                    "\"bullet\" | \"number\" | null"
+                } else {
+                    "null"
+                };
+                let annotation = self.type_of(annotation, dependencies);
+                let mut blocks = vec![format!(
+                    "{{ _type: \"block\", _key: string, style: {style}, list: {list}, level: number | null, \
+                    children: Array<{{ text: string, marks: Array<string> }}>, \
+                    mark_defs: Array<{{ _key: string, value: {annotation} }}> }}"
+                )];
+                if !matches!(**embed, EditorType::Null { .. }) {
+                    blocks.push(format!(
+                        "{{ _type: \"embed\", _key: string, value: {} }}",
+                        self.type_of(embed, dependencies)
+                    ));
+                }
+
+                format!("Array<{}>", blocks.join(" | "))
+            }
+            EditorType::Reference { to, .. } => {
+                format!("{{ __sc_ref: string, __sc_doc: {} }}", string_literal(to))
+            }
+            // some types stand in for null with an empty array, so databases don't trim them
+            EditorType::Null { .. } => "[]".into(),
+        }
+    }
+}
+
+/// Turns a document identifier like `blog_post` into a type name like `BlogPost`.
+fn type_name(identifier: &str) -> String {
+    identifier
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .flat_map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_ascii_uppercase())
+                .into_iter()
+                .chain(chars)
+        })
+        .collect()
+}
+
+/// A doc comment with the field's description and deprecation, if it has either.
+fn js_doc(field: &EditorField) -> String {
+    let lines = field
+        .description
+        .into_iter()
+        .flat_map(str::lines)
+        .map(ToOwned::to_owned)
+        .chain(
+            field
+                .deprecated
+                .map(|reason| format!("@deprecated {reason}")),
+        )
+        .fold(String::new(), |mut lines, line| {
+            // a */ would end the comment early, and writing to a string can't fail
+            let line = format!(" * {}", line.replace("*/", "*\\/"));
+            let _ = writeln!(lines, "{}", line.trim_end());
+            lines
+        });
+
+    if lines.is_empty() {
+        lines
+    } else {
+        format!("\n/**\n{lines} */\n")
+    }
+}
+
+fn string_literal(value: &str) -> String {
+    serde_json::to_string(value).expect("strings should always serialize")
+}
+
+fn union_of(values: &[&str]) -> String {
+    if values.is_empty() {
+        "string".into()
+    } else {
+        values
+            .iter()
+            .map(|value| string_literal(value))
+            .collect::<Vec<_>>()
+            .join(" | ")
+    }
+}
+
+/// Quotes property names that aren't valid identifiers.
+fn property_key(name: &str) -> String {
+    let is_identifier = name
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '$')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$');
+
+    if is_identifier {
+        name.to_owned()
+    } else {
+        string_literal(name)
+    }
+}
@@ -1,26 +1,37 @@
 use std::{fmt::Display, sync::Arc};
 
 use serde::{Deserialize, Serialize};
+use ts_rs::TS;
 
 use crate::{db::ValidationContext, DatabaseConnection, Document};
 
-/// A wrapper type to indicate that the inner type is valid.
+pub mod rules;
+
+/// A wrapper type to indicate that the inner type is valid. It can still have warnings.
 #[derive(Debug, Serialize)]
 #[serde(transparent)]
-pub struct Valid<T: Document>(T);
+pub struct Valid<T: Document>(T, #[serde(skip)] Vec<PathError>);
 
 impl<T: Document + Sync> Valid<T> {
     /// Validates the input, then returns a Valid<T>.
     ///
     /// # Errors
     ///
-    /// This function will return an error if validation fails.
+    /// This function will return an error if validation fails with at least one error, warnings alone don't fail.
     pub async fn new<DB: DatabaseConnection + Sync>(
         val: T,
         ctx: ValidationContext<'_, DB, T>,
     ) -> Result<Self, ValidationError> {
-        val.validate(ctx).await?;
-        Ok(Self(val))
+        match val.validate(ctx).await {
+            Ok(()) => Ok(Self(val, Vec::new())),
+            Err(e) if e.has_errors() => Err(e),
+            Err(warnings) => Ok(Self(val, warnings.flatten())),
+        }
+    }
+
+    /// The warnings found while validating.
+    pub fn warnings(&self) -> &[PathError] {
+        &self.1
     }
 
     pub fn inner(self) -> T {
@@ -28,27 +39,80 @@ impl<T: Document + Sync> Valid<T> {
     }
 }
 
-macro_rules! wrapped_string {
-    ($ty:ident) => {
-        #[derive(Serialize, Debug)]
-        pub struct $ty(pub Arc<str>);
+/// A field name, as it appears in the serialized document.
+#[derive(Serialize, Debug, PartialEq, Eq)]
+pub struct Field(pub Arc<str>);
 
-        impl Display for $ty {
-            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-                f.write_str(&self.0)
-            }
+impl Display for Field {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl<T: Into<Arc<str>>> From<T> for Field {
+    fn from(val: T) -> Self {
+        Self(val.into())
+    }
+}
+
+/// How bad a validation failure is.
+#[derive(Serialize, TS, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+#[ts(export)]
+pub enum Severity {
+    /// The document can't be published.
+    #[default]
+    Error,
+    /// The editor should know about it, but the document can still be published.
+    Warning,
+}
+
+/// Why a value is invalid. The code is meant for machines (e.g. `too_long`), the message for people.
+///
+/// Reasons made from strings have the code `invalid`, and are errors.
+#[derive(Debug)]
+pub struct Reason {
+    pub code: Arc<str>,
+    pub message: Arc<str>,
+    pub severity: Severity,
+}
+
+impl Reason {
+    pub fn new(code: impl Into<Arc<str>>, message: impl Into<Arc<str>>) -> Self {
+        Self {
+            code: code.into(),
+            message: message.into(),
+            severity: Severity::Error,
         }
+    }
 
-        impl<T: Into<Arc<str>>> From<T> for $ty {
-            fn from(val: T) -> Self {
-                Self(val.into())
-            }
+    /// A reason that doesn't stop the document from being published.
+    pub fn warning(code: impl Into<Arc<str>>, message: impl Into<Arc<str>>) -> Self {
+        Self {
+            severity: Severity::Warning,
+            ..Self::new(code, message)
         }
-    };
+    }
+}
+
+impl Display for Reason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
 }
 
-wrapped_string!(Reason);
-wrapped_string!(Field);
+impl<T: Into<Arc<str>>> From<T> for Reason {
+    fn from(val: T) -> Self {
+        Self::new("invalid", val)
+    }
+}
+
+// only the message is serialized, so the nested format stays the same
+impl Serialize for Reason {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.message)
+    }
+}
 
 /// validatoin error
 #[derive(Debug, Serialize)]
@@ -58,6 +122,15 @@ pub enum ValidationError {
     Single(Reason),
     /// a struct/document of validated types is invalid for one or more reasons
     Composite(Vec<ErroredField>),
+    /// one or more items of a list are invalid
+    Items(Vec<ErroredItem>),
+    /// the fields of an enum variant are invalid
+    Variant {
+        variant: Field,
+        error: Box<ValidationError>,
+    },
+    /// the same value is invalid for more than one reason (e.g a failed rule and it's own validation)
+    Multiple(Vec<ValidationError>),
 }
 
 #[derive(Debug, Serialize)]
@@ -66,6 +139,186 @@ pub struct ErroredField {
     pub error: ValidationError,
 }
 
+impl ErroredField {
+    /// Adds errors to a list of field errors, combining them with the errors already there for
+    /// the same field, so each field shows up once.
+    pub fn merge(errors: &mut Vec<Self>, more: impl IntoIterator<Item = Self>) {
+        for Self { field, error } in more {
+            match errors.iter_mut().find(|e| e.field == field) {
+                Some(existing) => {
+                    let previous = std::mem::replace(
+                        &mut existing.error,
+                        ValidationError::Multiple(Vec::new()),
+                    );
+                    // two errors always combine into one
+                    if let Some(combined) = ValidationError::combine([previous, error]) {
+                        existing.error = combined;
+                    }
+                }
+                None => errors.push(Self { field, error }),
+            }
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ErroredItem {
+    pub index: usize,
+    pub error: ValidationError,
+}
+
+/// A validation error, addressed by where it happened in the document.
+#[derive(Serialize, TS, Debug, Clone)]
+#[ts(export)]
+pub struct PathError {
+    /// A JSON pointer to the invalid value, e.g. `/sections/3/title`.
+    pub path: String,
+    /// The path, including the enum variants along it (which aren't part of the pointer).
+    pub segments: Vec<PathSegment>,
+    #[ts(type = "string")]
+    pub code: Arc<str>,
+    #[ts(type = "string")]
+    pub message: Arc<str>,
+    pub severity: Severity,
+}
+
+#[derive(Serialize, TS, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "kind", content = "value", rename_all = "snake_case")]
+#[ts(export)]
+pub enum PathSegment {
+    Field(#[ts(type = "string")] Arc<str>),
+    Index(usize),
+    Variant(#[ts(type = "string")] Arc<str>),
+}
+
+impl ValidationError {
+    /// Whether anything in this is an error, rather than a warning.
+    #[must_use]
+    pub fn has_errors(&self) -> bool {
+        match self {
+            Self::Single(reason) => reason.severity == Severity::Error,
+            Self::Composite(fields) => fields.iter().any(|field| field.error.has_errors()),
+            Self::Items(items) => items.iter().any(|item| item.error.has_errors()),
+            Self::Variant { error, .. } => error.has_errors(),
+            Self::Multiple(errors) => errors.iter().any(Self::has_errors),
+        }
+    }
+
+    /// Turns every reason in this into a warning.
+    #[must_use]
+    pub fn into_warning(self) -> Self {
+        match self {
+            Self::Single(reason) => Self::Single(Reason {
+                severity: Severity::Warning,
+                ..reason
+            }),
+            Self::Composite(fields) => Self::Composite(
+                fields
+                    .into_iter()
+                    .map(|field| ErroredField {
+                        field: field.field,
+                        error: field.error.into_warning(),
+                    })
+                    .collect(),
+            ),
+            Self::Items(items) => Self::Items(
+                items
+                    .into_iter()
+                    .map(|item| ErroredItem {
+                        index: item.index,
+                        error: item.error.into_warning(),
+                    })
+                    .collect(),
+            ),
+            Self::Variant { variant, error } => Self::Variant {
+                variant,
+                error: Box::new(error.into_warning()),
+            },
+            Self::Multiple(errors) => {
+                Self::Multiple(errors.into_iter().map(Self::into_warning).collect())
+            }
+        }
+    }
+
+    /// Combines errors for the same value, or returns `None` if there aren't any.
+    pub fn combine(errors: impl IntoIterator<Item = Self>) -> Option<Self> {
+        let mut errors = errors
+            .into_iter()
+            .flat_map(|error| match error {
+                Self::Multiple(errors) => errors,
+                error => vec![error],
+            })
+            .collect::<Vec<_>>();
+
+        match errors.len() {
+            0 => None,
+            1 => errors.pop(),
+            _ => Some(Self::Multiple(errors)),
+        }
+    }
+
+    /// Flattens the error into a list of every invalid value, in the order they were found.
+    #[must_use]
+    pub fn flatten(&self) -> Vec<PathError> {
+        let mut errors = Vec::new();
+        self.flatten_into(&mut Vec::new(), &mut errors);
+        errors
+    }
+
+    fn flatten_into(&self, segments: &mut Vec<PathSegment>, errors: &mut Vec<PathError>) {
+        match self {
+            Self::Single(reason) => errors.push(PathError {
+                path: pointer(segments),
+                segments: segments.clone(),
+                code: reason.code.clone(),
+                message: reason.message.clone(),
+                severity: reason.severity,
+            }),
+            Self::Composite(fields) => {
+                for field in fields {
+                    segments.push(PathSegment::Field(field.field.0.clone()));
+                    field.error.flatten_into(segments, errors);
+                    segments.pop();
+                }
+            }
+            Self::Items(items) => {
+                for item in items {
+                    segments.push(PathSegment::Index(item.index));
+                    item.error.flatten_into(segments, errors);
+                    segments.pop();
+                }
+            }
+            Self::Variant { variant, error } => {
+                segments.push(PathSegment::Variant(variant.0.clone()));
+                error.flatten_into(segments, errors);
+                segments.pop();
+            }
+            Self::Multiple(multiple) => {
+                for error in multiple {
+                    error.flatten_into(segments, errors);
+                }
+            }
+        }
+    }
+}
+
+fn pointer(segments: &[PathSegment]) -> String {
+    segments.iter().fold(String::new(), |mut path, segment| {
+        match segment {
+            PathSegment::Field(field) => {
+                path.push('/');
+                path.push_str(&field.replace('~', "~0").replace('/', "~1"));
+            }
+            PathSegment::Index(index) => {
+                path.push('/');
+                path.push_str(&index.to_string());
+            }
+            PathSegment::Variant(_) => {}
+        }
+        path
+    })
+}
+
 #[diagnostic::on_unimplemented(
     note = "all document fields are validated by default",
     note = "if validation isn't necesarry, use #[validate(skip)]"
@@ -95,6 +348,98 @@ impl<T: Validate + Sync> Validate for Option<T> {
     }
 }
 
+impl<T: Validate + Sync> Validate for Vec<T> {
+    async fn validate<DB: DatabaseConnection + Sync, D: Document + Sync>(
+        &self,
+        ctx: ValidationContext<'_, DB, D>,
+    ) -> Result<(), ValidationError> {
+        let mut errors = Vec::new();
+        for (index, item) in self.iter().enumerate() {
+            if let Err(error) = item.validate(ctx).await {
+                errors.push(ErroredItem { index, error });
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ValidationError::Items(errors))
+        }
+    }
+}
+
+macro_rules! non_zero {
+    ($($ty:ty),+) => {
+        $(
+            // serde already refuses to deserialize a zero
+            impl Validate for $ty {
+                async fn validate<DB: DatabaseConnection, D: Document>(
+                    &self,
+                    _ctx: ValidationContext<'_, DB, D>,
+                ) -> Result<(), ValidationError> {
+                    Ok(())
+                }
+            }
+        )+
+    };
+}
+
+non_zero!(
+    std::num::NonZeroI8,
+    std::num::NonZeroI16,
+    std::num::NonZeroI32,
+    std::num::NonZeroI64,
+    std::num::NonZeroU8,
+    std::num::NonZeroU16,
+    std::num::NonZeroU32,
+    std::num::NonZeroU64
+);
+
+macro_rules! float {
+    ($($ty:ty),+) => {
+        $(
+            // json can't hold NaN or infinity, serde_json would quietly store them as null
+            impl Validate for $ty {
+                async fn validate<DB: DatabaseConnection, D: Document>(
+                    &self,
+                    _ctx: ValidationContext<'_, DB, D>,
+                ) -> Result<(), ValidationError> {
+                    if self.is_finite() {
+                        Ok(())
+                    } else {
+                        Err(ValidationError::Single(Reason::new(
+                            "not_finite",
+                            "must be a finite number",
+                        )))
+                    }
+                }
+            }
+        )+
+    };
+}
+
+float!(f32, f64);
+
+// unlike floats, every decimal serde can deserialize is a number, so rules like range are all it needs
+#[cfg(feature = "rust_decimal")]
+impl Validate for rust_decimal::Decimal {
+    async fn validate<DB: DatabaseConnection, D: Document>(
+        &self,
+        _ctx: ValidationContext<'_, DB, D>,
+    ) -> Result<(), ValidationError> {
+        Ok(())
+    }
+}
+
+impl Validate for () {
+    async fn validate<DB: DatabaseConnection, D: Document>(
+        &self,
+        _ctx: ValidationContext<'_, DB, D>,
+    ) -> Result<(), ValidationError> {
+        Ok(())
+    }
+}
+
 macro_rules! validator {
     ($ty:ty, $inner:ty, $expr:block, $v:ident) => {
         impl crate::editor_field::ToEditorField for $ty {
@@ -143,7 +488,10 @@ pub struct NonZeroI32(pub i32);
 
 validator! {NonZeroI32, i32, {
     match v.0 {
-        0 => Err(ValidationError::Single("value must not be zero".into())),
+        0 => Err(ValidationError::Single(Reason::new(
+            "zero",
+            "value must not be zero",
+        ))),
         _ => Ok(()),
     }
 }, v}
@@ -0,0 +1,347 @@
+//! Reusable validation rules, used by `#[validate(...)]` on document fields.
+//!
+//! Every rule skips `None`, use [`non_empty`] to require a value. Failures have a code matching the
+//! rule, like `too_long` for [`length`] or `pattern` for [`regex`].
+//!
+//! Rules inside `#[validate(warn(...))]` only warn, see [`with_warnings`].
+
+use std::{
+    num::{
+        NonZeroI16, NonZeroI32, NonZeroI64, NonZeroI8, NonZeroU16, NonZeroU32, NonZeroU64,
+        NonZeroU8,
+    },
+    sync::{Arc, LazyLock},
+};
+
+pub use regex::Regex;
+use serde::Serialize;
+use ts_rs::TS;
+
+use super::{Reason, ValidationError};
+use crate::types::{Markdown, MultiLine, Slug};
+
+/// A rule on a document field, as it's exposed to the editor in [`crate::EditorField::rules`].
+/// The editor can check these for quick feedback, but the server always checks them too, so it never
+/// has to trust the editor.
+#[derive(Serialize, TS, Debug, Clone, PartialEq)]
+#[serde(tag = "rule", rename_all = "snake_case")]
+#[ts(export)]
+pub enum Rule {
+    /// See [`non_empty`].
+    NonEmpty,
+    /// See [`length`], this counts characters.
+    Length {
+        min: Option<usize>,
+        max: Option<usize>,
+    },
+    /// See [`items`].
+    Items {
+        min: Option<usize>,
+        max: Option<usize>,
+    },
+    /// See [`range`], this is inclusive.
+    Range { min: Option<f64>, max: Option<f64> },
+    /// See [`regex`]. The pattern is used as is by the editor and in JSON Schema, which use JavaScript (ECMA-262)
+    /// regexes, so `#[validate(regex = "...")]` rejects syntax only Rust supports, like `(?i)` or `\A`.
+    /// Some syntax means something different though: `\d`, `\w` and `\s` match any unicode character in Rust,
+    /// but only ASCII in JavaScript, so spell out classes like `[0-9]` when it matters.
+    Regex { pattern: &'static str },
+    /// See [`email`].
+    Email { pattern: &'static str },
+    /// See [`url`].
+    Url { pattern: &'static str },
+}
+
+/// Text that can be checked by rules like [`length`] and [`email`].
+pub trait Text {
+    /// The text to check, or `None` if there's nothing to check.
+    fn text(&self) -> Option<&str>;
+}
+
+/// A number that can be checked by [`range`].
+pub trait Number {
+    /// The number to check, or `None` if there's nothing to check.
+    fn number(&self) -> Option<f64>;
+}
+
+/// A collection that can be checked by [`items`].
+pub trait Collection {
+    /// How many items there are, or `None` if there's nothing to check.
+    fn count(&self) -> Option<usize>;
+}
+
+/// Something that can be checked by [`non_empty`].
+pub trait Emptiable {
+    fn is_empty_value(&self) -> bool;
+}
+
+macro_rules! text {
+    ($($ty:ty),+) => {
+        $(
+            impl Text for $ty {
+                fn text(&self) -> Option<&str> {
+                    Some(self)
+                }
+            }
+
+            impl Emptiable for $ty {
+                fn is_empty_value(&self) -> bool {
+                    self.is_empty()
+                }
+            }
+        )+
+    };
+}
+
+text!(str, String, Arc<str>, MultiLine, Markdown, Slug);
+
+macro_rules! number {
+    ($($ty:ty),+) => {
+        $(
+            impl Number for $ty {
+                #[allow(clippy::cast_precision_loss, clippy::cast_lossless, clippy::unnecessary_cast)]
+                fn number(&self) -> Option<f64> {
+                    Some(*self as f64)
+                }
+            }
+        )+
+    };
+}
+
+number!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize, f32, f64);
+
+macro_rules! non_zero {
+    ($($ty:ty),+) => {
+        $(
+            impl Number for $ty {
+                #[allow(clippy::cast_precision_loss, clippy::cast_lossless)]
+                fn number(&self) -> Option<f64> {
+                    Some(self.get() as f64)
+                }
+            }
+        )+
+    };
+}
+
+non_zero!(
+    NonZeroI8, NonZeroI16, NonZeroI32, NonZeroI64, NonZeroU8, NonZeroU16, NonZeroU32, NonZeroU64
+);
+
+#[cfg(feature = "rust_decimal")]
+impl Number for rust_decimal::Decimal {
+    fn number(&self) -> Option<f64> {
+        rust_decimal::prelude::ToPrimitive::to_f64(self)
+    }
+}
+
+impl<T> Collection for Vec<T> {
+    fn count(&self) -> Option<usize> {
+        Some(self.len())
+    }
+}
+
+impl<T> Collection for [T] {
+    fn count(&self) -> Option<usize> {
+        Some(self.len())
+    }
+}
+
+impl<T> Emptiable for Vec<T> {
+    fn is_empty_value(&self) -> bool {
+        self.is_empty()
+    }
+}
+
+impl<T: Text> Text for Option<T> {
+    fn text(&self) -> Option<&str> {
+        self.as_ref().and_then(Text::text)
+    }
+}
+
+impl<T: Number> Number for Option<T> {
+    fn number(&self) -> Option<f64> {
+        self.as_ref().and_then(Number::number)
+    }
+}
+
+impl<T: Collection> Collection for Option<T> {
+    fn count(&self) -> Option<usize> {
+        self.as_ref().and_then(Collection::count)
+    }
+}
+
+impl<T: Emptiable> Emptiable for Option<T> {
+    fn is_empty_value(&self) -> bool {
+        self.as_ref().is_none_or(Emptiable::is_empty_value)
+    }
+}
+
+fn fail(code: &str, message: impl Into<Arc<str>>) -> Result<(), ValidationError> {
+    Err(ValidationError::Single(Reason::new(code, message)))
+}
+
+/// Runs a field's rules before it's own validation. Only the first failed rule is kept, since the
+/// rest tend to repeat it (empty text is also too short), but the field's own validation always runs.
+///
+/// # Errors
+///
+/// This function will return an error if a rule failed, or `result` is an error.
+pub fn with_rules(
+    rules: impl IntoIterator<Item = Result<(), ValidationError>>,
+    result: Result<(), ValidationError>,
+) -> Result<(), ValidationError> {
+    let rule = rules.into_iter().find_map(Result::err);
+    ValidationError::combine(rule.into_iter().chain(result.err())).map_or(Ok(()), Err)
+}
+
+/// Adds every failed warning to a field's result, as warnings.
+///
+/// # Errors
+///
+/// This function will return an error if `result` is an error, or any of the warnings failed.
+pub fn with_warnings(
+    result: Result<(), ValidationError>,
+    warnings: impl IntoIterator<Item = Result<(), ValidationError>>,
+) -> Result<(), ValidationError> {
+    let warnings = warnings
+        .into_iter()
+        .filter_map(Result::err)
+        .map(ValidationError::into_warning);
+    ValidationError::combine(result.err().into_iter().chain(warnings)).map_or(Ok(()), Err)
+}
+
+/// Checks how many characters long some text is.
+///
+/// # Errors
+///
+/// This function will return an error if the text is shorter than `min` or longer than `max`.
+pub fn length<T: Text + ?Sized>(
+    value: &T,
+    min: Option<usize>,
+    max: Option<usize>,
+) -> Result<(), ValidationError> {
+    let Some(length) = value.text().map(|text| text.chars().count()) else {
+        return Ok(());
+    };
+
+    match (min, max) {
+        (Some(min), _) if length < min => fail(
+            "too_short",
+            format!("must be at least {min} characters long"),
+        ),
+        (_, Some(max)) if length > max => {
+            fail("too_long", format!("must be at most {max} characters long"))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Checks that a number is within a range, inclusively.
+///
+/// # Errors
+///
+/// This function will return an error if the number is less than `min` or greater than `max`.
+pub fn range<T: Number + ?Sized>(
+    value: &T,
+    min: Option<f64>,
+    max: Option<f64>,
+) -> Result<(), ValidationError> {
+    let Some(number) = value.number() else {
+        return Ok(());
+    };
+
+    match (min, max) {
+        (Some(min), _) if number < min => fail("too_small", format!("must be at least {min}")),
+        (_, Some(max)) if number > max => fail("too_large", format!("must be at most {max}")),
+        _ => Ok(()),
+    }
+}
+
+/// Checks how many items a collection has.
+///
+/// # Errors
+///
+/// This function will return an error if there are less than `min` or more than `max` items.
+pub fn items<T: Collection + ?Sized>(
+    value: &T,
+    min: Option<usize>,
+    max: Option<usize>,
+) -> Result<(), ValidationError> {
+    let Some(count) = value.count() else {
+        return Ok(());
+    };
+
+    match (min, max) {
+        (Some(min), _) if count < min => {
+            fail("too_few_items", format!("must have at least {min} items"))
+        }
+        (_, Some(max)) if count > max => {
+            fail("too_many_items", format!("must have at most {max} items"))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Checks that text matches a regular expression.
+///
+/// # Errors
+///
+/// This function will return an error if the text doesn't match.
+pub fn regex<T: Text + ?Sized>(value: &T, regex: &Regex) -> Result<(), ValidationError> {
+    match value.text() {
+        Some(text) if !regex.is_match(text) => {
+            fail("pattern", format!("must match {}", regex.as_str()))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// The pattern [`email`] checks against.
+pub const EMAIL_PATTERN: &str = r"^[^\s@]+@[^\s@.]+(\.[^\s@.]+)+$";
+
+static EMAIL: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(EMAIL_PATTERN).expect("the email regex should be valid"));
+
+/// Checks that text looks like an email address. This is deliberately loose, the only way to
+/// really know if an address is valid is to send something to it.
+///
+/// # Errors
+///
+/// This function will return an error if the text isn't an email address.
+pub fn email<T: Text + ?Sized>(value: &T) -> Result<(), ValidationError> {
+    match value.text() {
+        Some(text) if !EMAIL.is_match(text) => fail("email", "must be an email address"),
+        _ => Ok(()),
+    }
+}
+
+/// The pattern [`url`] checks against.
+pub const URL_PATTERN: &str = r"^[a-zA-Z][a-zA-Z0-9+.-]*://[^\s/?#]+\S*$";
+
+static URL: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(URL_PATTERN).expect("the url regex should be valid"));
+
+/// Checks that text is an absolute url.
+///
+/// # Errors
+///
+/// This function will return an error if the text isn't an absolute url.
+pub fn url<T: Text + ?Sized>(value: &T) -> Result<(), ValidationError> {
+    match value.text() {
+        Some(text) if !URL.is_match(text) => fail("url", "must be a url"),
+        _ => Ok(()),
+    }
+}
+
+/// Checks that there's a value, and that it isn't empty.
+///
+/// # Errors
+///
+/// This function will return an error if the value is empty or `None`.
+pub fn non_empty<T: Emptiable + ?Sized>(value: &T) -> Result<(), ValidationError> {
+    if value.is_empty_value() {
+        fail("empty", "must not be empty")
+    } else {
+        Ok(())
+    }
+}
@@ -0,0 +1,18 @@
+use scalar_cms::editor_field::ToEditorField;
+use serde_json::json;
+
+#[test]
+fn integer_bounds_stay_exact() {
+    let field = u64::to_editor_field(None::<u64>, "count", "Count", None, None, None);
+
+    let editor = serde_json::to_value(&field.field_type).unwrap();
+    assert_eq!(editor["min"], "0");
+    assert_eq!(editor["max"], "18446744073709551615");
+
+    let schema = field.field_type.to_json_schema();
+    assert_eq!(schema["maximum"], json!(u64::MAX));
+
+    let field = i64::to_editor_field(None::<i64>, "offset", "Offset", None, None, None);
+    let editor = serde_json::to_value(&field.field_type).unwrap();
+    assert_eq!(editor["min"], "-9223372036854775808");
+}
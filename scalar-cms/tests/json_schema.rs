@@ -0,0 +1,52 @@
+use scalar_cms::{doc_enum, Document, EditorField};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+/// Anything that's a slug is a name too.
+#[doc_enum]
+#[serde(untagged)]
+#[derive(Debug)]
+enum Handle {
+    Slug(String),
+    Name(String),
+}
+
+#[doc_enum]
+#[derive(Debug)]
+enum Visibility {
+    Public,
+    Private,
+}
+
+#[derive(Document, Debug, Serialize, Deserialize)]
+#[document(identifier = "profile")]
+struct Profile {
+    #[validate(skip)]
+    handle: Handle,
+    #[validate(skip)]
+    visibility: Visibility,
+}
+
+#[test]
+fn untagged_enums_allow_overlapping_variants() {
+    let schema = Profile::schema().to_json_schema();
+    let handle = &schema["properties"]["handle"];
+
+    assert_eq!(
+        handle["anyOf"],
+        json!([
+            { "title": "Slug", "type": "string" },
+            { "title": "Name", "type": "string" },
+        ])
+    );
+    assert!(handle.get("oneOf").is_none());
+}
+
+#[test]
+fn tagged_enums_match_exactly_one_variant() {
+    let schema = Profile::schema().to_json_schema();
+    let visibility = &schema["properties"]["visibility"];
+
+    assert_eq!(visibility["oneOf"].as_array().map(Vec::len), Some(2));
+    assert!(visibility.get("anyOf").is_none());
+}
@@ -0,0 +1,77 @@
+use scalar_cms::{editor_field::restore_readonly, Document, EditorField};
+use scalar_expr::expression;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+#[derive(EditorField, Debug, Serialize, Deserialize)]
+struct Stats {
+    #[field(readonly)]
+    views: u32,
+    note: String,
+}
+
+#[derive(Document, Debug, Serialize, Deserialize)]
+#[document(identifier = "order")]
+struct Order {
+    #[validate(skip)]
+    #[field(readonly)]
+    number: u32,
+    #[validate(skip)]
+    shipped: bool,
+    #[validate(skip)]
+    #[field(readonly_if = expression!(field:"shipped" == true))]
+    address: String,
+    #[validate(skip)]
+    stats: Stats,
+}
+
+fn restore(data: serde_json::Value, stored: &serde_json::Value) -> serde_json::Value {
+    let mut data = data;
+    restore_readonly(Order::fields(), &mut data, stored);
+    data
+}
+
+#[test]
+fn undoes_changes_to_read_only_fields() {
+    let stored = json!({
+        "number": 1,
+        "shipped": false,
+        "address": "home",
+        "stats": { "views": 10, "note": "" },
+    });
+    let data = json!({
+        "number": 2,
+        "shipped": true,
+        "address": "work",
+        "stats": { "views": 0, "note": "hi" },
+    });
+
+    // the address isn't read only yet, shipping it and changing it at once is fine
+    assert_eq!(
+        restore(data, &stored),
+        json!({
+            "number": 1,
+            "shipped": true,
+            "address": "work",
+            "stats": { "views": 10, "note": "hi" },
+        })
+    );
+}
+
+#[test]
+fn evaluates_conditions_against_what_is_stored() {
+    let stored = json!({
+        "number": 1,
+        "shipped": true,
+        "address": "home",
+        "stats": { "views": 10, "note": "" },
+    });
+    let data = json!({
+        "number": 1,
+        "shipped": false,
+        "address": "work",
+        "stats": { "views": 10, "note": "" },
+    });
+
+    assert_eq!(restore(data, &stored)["address"], "home");
+}
@@ -0,0 +1,34 @@
+use scalar_cms::types::{Link, RenderHtml};
+
+fn link(href: &str) -> String {
+    Link {
+        href: href.to_owned(),
+        blank: false,
+    }
+    .wrap_html("text")
+}
+
+#[test]
+fn renders_safe_links() {
+    for href in [
+        "https://example.com",
+        "mailto:hi@example.com",
+        "/about",
+        "/",
+        "#top",
+    ] {
+        assert_eq!(link(href), format!("<a href=\"{href}\">text</a>"));
+    }
+}
+
+#[test]
+fn drops_links_that_can_leave_the_site_unexpectedly() {
+    for href in [
+        "//evil.example/",
+        "/\\evil.example/",
+        "javascript:alert(1)",
+        "data:text/html,hi",
+    ] {
+        assert_eq!(link(href), "text", "{href} should be dropped");
+    }
+}
@@ -0,0 +1,80 @@
+use scalar_cms::{
+    types::{Locales, Localized, Reference},
+    Document, EditorField,
+};
+use serde::{Deserialize, Serialize};
+
+struct English;
+
+impl Locales for English {
+    const LOCALES: &'static [&'static str] = &["en", "de"];
+    const REQUIRED: &'static [&'static str] = &["en"];
+    const FALLBACK: &'static [&'static str] = &["en"];
+}
+
+/// The same locales as [`English`], but German is what has to be there.
+struct German;
+
+impl Locales for German {
+    const LOCALES: &'static [&'static str] = &["en", "de"];
+    const REQUIRED: &'static [&'static str] = &["de"];
+    const FALLBACK: &'static [&'static str] = &["de"];
+}
+
+#[derive(Document, Debug, Serialize, Deserialize)]
+#[document(identifier = "author")]
+struct Author {
+    #[validate(skip)]
+    name: String,
+}
+
+#[derive(Document, Debug, Serialize, Deserialize)]
+#[document(identifier = "post")]
+struct Post {
+    #[validate(skip)]
+    views: u32,
+    #[validate(skip)]
+    score: f32,
+    #[validate(skip)]
+    author: Reference<Author>,
+    #[validate(skip)]
+    title: Localized<String, English>,
+}
+
+/// [`Post`], with different bounds and editor settings that are all stored the same way.
+#[derive(Document, Debug, Serialize, Deserialize)]
+#[document(identifier = "post")]
+struct WidePost {
+    #[validate(skip)]
+    views: i64,
+    #[validate(skip)]
+    score: f64,
+    #[validate(skip)]
+    author: Reference<Author, true>,
+    #[validate(skip)]
+    title: Localized<String, German>,
+}
+
+/// [`Post`], with views stored as a string.
+#[derive(Document, Debug, Serialize, Deserialize)]
+#[document(identifier = "post")]
+struct StringlyPost {
+    #[validate(skip)]
+    views: String,
+    #[validate(skip)]
+    score: f32,
+    #[validate(skip)]
+    author: Reference<Author>,
+    #[validate(skip)]
+    title: Localized<String, English>,
+}
+
+#[test]
+fn ignores_what_only_matters_to_the_editor() {
+    assert_eq!(Post::schema_hash(), WidePost::schema_hash());
+}
+
+#[test]
+fn changes_with_how_content_is_stored() {
+    assert_ne!(Post::schema_hash(), StringlyPost::schema_hash());
+}
@@ -0,0 +1,106 @@
+use std::collections::BTreeMap;
+
+use scalar_cms::{
+    doc_enum, editor_field::ToEditorField, types::Localized, typescript::TypeScript, Document,
+    EditorField,
+};
+use serde::{Deserialize, Serialize};
+
+struct Languages;
+
+impl scalar_cms::types::Locales for Languages {
+    const LOCALES: &'static [&'static str] = &["en", "de"];
+    const REQUIRED: &'static [&'static str] = &["en"];
+    const FALLBACK: &'static [&'static str] = &["en"];
+}
+
+#[derive(EditorField, Debug, Serialize, Deserialize)]
+struct Author {
+    name: String,
+    age: Option<i32>,
+}
+
+#[doc_enum]
+#[derive(Debug)]
+enum Block {
+    Quote { text: String },
+    Divider,
+}
+
+#[derive(Document, Debug, Serialize, Deserialize)]
+#[document(identifier = "blog_post")]
+struct BlogPost {
+    #[validate(skip)]
+    author: Author,
+    #[validate(skip)]
+    blocks: Vec<Block>,
+    #[validate(skip)]
+    title: Localized<String, Languages>,
+}
+
+// generic, so it's inlined instead of named
+#[doc_enum]
+#[derive(Debug)]
+enum Maybe<T: ToEditorField> {
+    Just { value: T },
+    Nothing,
+}
+
+#[derive(Document, Debug, Serialize, Deserialize)]
+#[document(identifier = "author")]
+struct AuthorPage {
+    #[validate(skip)]
+    bio: String,
+}
+
+fn files(typescript: &TypeScript) -> BTreeMap<String, String> {
+    typescript.files().collect()
+}
+
+#[test]
+fn generates_types_for_documents() {
+    let files = files(&TypeScript::new().with_doc::<BlogPost>());
+
+    assert_eq!(
+        files.keys().collect::<Vec<_>>(),
+        ["Author.ts", "Block.ts", "BlogPost.ts"]
+    );
+    assert_eq!(
+        files["BlogPost.ts"],
+        "// This file was generated by scalar. Do not edit this file manually.\n\
+        import type { Author } from \"./Author\";\n\
+        import type { Block } from \"./Block\";\n\
+        import type { Item } from \"./Item\";\n\
+        \n\
+        export type BlogPost = { author: Author, blocks: Array<Block>, title: { en: string, de?: string } };\n\
+        \n\
+        export type BlogPostItem = Omit<Item, \"content\"> & { content: BlogPost };\n"
+    );
+    assert_eq!(
+        files["Author.ts"],
+        "// This file was generated by scalar. Do not edit this file manually.\n\
+        \n\
+        export type Author = { name: string, age: number | null };\n"
+    );
+    assert_eq!(
+        files["Block.ts"],
+        "// This file was generated by scalar. Do not edit this file manually.\n\
+        \n\
+        export type Block = { type: \"Quote\", text: string } | { type: \"Divider\" };\n"
+    );
+}
+
+#[test]
+#[should_panic(expected = "two different types are named `Author`")]
+fn rejects_types_with_the_same_name() {
+    let _ = TypeScript::new()
+        .with_doc::<BlogPost>()
+        .with_doc::<AuthorPage>();
+}
+
+#[test]
+fn inlines_generic_types() {
+    let files = files(&TypeScript::new().with_type::<Vec<Maybe<Author>>>());
+
+    assert_eq!(files.keys().collect::<Vec<_>>(), ["Author.ts"]);
+}
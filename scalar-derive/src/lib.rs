@@ -1,5 +1,9 @@
+// darling's derives expand to `continue`s that clippy thinks are needless, and an `allow` on the
+// structs doesn't reach the impls they generate
+#![allow(clippy::needless_continue)]
+
 use convert_case::Casing;
-use darling::{util::Flag, FromDeriveInput, FromField, FromVariant};
+use darling::{util::Flag, FromDeriveInput, FromField, FromMeta, FromVariant};
 use proc_macro::TokenStream;
 use quote::quote;
 use syn::{parse_macro_input, Data, DeriveInput, Ident};
@@ -10,6 +14,56 @@ struct Document {
     identifier: Option<String>,
     title: Option<String>,
     singleton: Flag,
+    migrations: Option<syn::Path>,
+    groups: Option<Groups>,
+}
+
+/// `groups(...)` on a document, in the order they're shown, e.g. `groups(content, seo(title = "SEO", fieldset))`.
+#[derive(Clone)]
+struct Groups(Vec<(Ident, GroupInfo)>);
+
+impl FromMeta for Groups {
+    fn from_list(items: &[darling::ast::NestedMeta]) -> darling::Result<Self> {
+        items
+            .iter()
+            .map(|item| match item {
+                darling::ast::NestedMeta::Meta(meta) => {
+                    let id = meta
+                        .path()
+                        .get_ident()
+                        .ok_or_else(|| {
+                            darling::Error::custom("expected a group name").with_span(meta)
+                        })?
+                        .clone();
+                    let info = match meta {
+                        syn::Meta::Path(_) => GroupInfo::default(),
+                        meta => GroupInfo::from_meta(meta)?,
+                    };
+                    Ok((id, info))
+                }
+                darling::ast::NestedMeta::Lit(lit) => Err(darling::Error::unexpected_lit_type(lit)),
+            })
+            .collect::<darling::Result<_>>()
+            .map(Self)
+    }
+}
+
+#[derive(FromMeta, Clone, Default)]
+#[darling(default)]
+struct GroupInfo {
+    title: Option<String>,
+    /// Groups are tabs, unless they're fieldsets.
+    fieldset: Flag,
+    collapsible: Flag,
+    collapsed: Flag,
+}
+
+/// `#[validate(...)]` on the document itself.
+#[derive(FromDeriveInput)]
+#[darling(attributes(validate), supports(struct_named))]
+struct DocumentValidate {
+    /// Validates the whole document, for rules that span multiple fields.
+    document: Option<syn::Path>,
 }
 
 #[derive(FromDeriveInput)]
@@ -23,28 +77,60 @@ struct ToEditorField {
 }
 
 #[derive(FromDeriveInput)]
-#[darling(supports(enum_unit, enum_named))]
+#[darling(
+    supports(enum_unit, enum_named, enum_newtype, enum_tuple),
+    forward_attrs(serde)
+)]
 struct Enum {
+    attrs: Vec<syn::Attribute>,
     data: darling::ast::Data<EnumVariant, FieldInfo>,
 }
 
 #[derive(FromVariant)]
+#[darling(forward_attrs(serde))]
 struct EnumVariant {
     ident: Ident,
+    attrs: Vec<syn::Attribute>,
     fields: darling::ast::Fields<FieldInfo>,
 }
 
+/// The parts of `#[serde(...)]` on an enum that change how it's serialized.
+#[derive(Default)]
+struct SerdeEnum {
+    tag: Option<String>,
+    content: Option<String>,
+    untagged: bool,
+    rename_all: Option<syn::LitStr>,
+}
+
+/// The parts of `#[serde(...)]` on a variant that change how it's serialized.
+#[derive(Default)]
+struct SerdeVariant {
+    rename: Option<String>,
+    skip: bool,
+}
+
 #[derive(FromField, Clone)]
-#[darling(attributes(field))]
+#[darling(attributes(field), forward_attrs(doc))]
 struct FieldInfo {
     ident: Option<syn::Ident>,
     ty: syn::Type,
+    attrs: Vec<syn::Attribute>,
+    /// Overrides the doc comment.
+    description: Option<String>,
+    deprecated: Option<String>,
     title: Option<String>,
     placeholder: Option<String>,
     editor_component: Option<String>,
     default: Option<syn::Lit>,
     label: Flag,
     sublabel: Flag,
+    /// An expression that hides the field when it's true, e.g. `hidden_if = expression!(field:"kind" != "video")`.
+    hidden_if: Option<syn::Expr>,
+    readonly: Flag,
+    readonly_if: Option<syn::Expr>,
+    /// The document group this field is in, see `#[document(groups(...))]`.
+    group: Option<syn::LitStr>,
 }
 
 #[derive(FromField, Clone)]
@@ -52,20 +138,337 @@ struct FieldInfo {
 struct ValidateInfo {
     ident: Option<syn::Ident>,
     skip: Flag,
-    with: Option<Ident>,
+    with: Option<syn::Path>,
+    /// Only checks the rules, for types that don't implement `Validate` like `String`.
+    rules_only: Flag,
+    #[darling(flatten)]
+    rules: Rules,
+}
+
+/// Built-in rules, backed by `scalar_cms::validations::rules`.
+#[derive(FromMeta, Clone, Default)]
+#[darling(default)]
+struct Rules {
+    non_empty: Flag,
+    length: Option<Bounds<usize>>,
+    items: Option<Bounds<usize>>,
+    range: Option<Bounds<Bound>>,
+    regex: Option<syn::LitStr>,
+    email: Flag,
+    url: Flag,
+    /// Rules that only warn, e.g. `warn(length(max = 160))`.
+    warn: Option<Box<Rules>>,
+}
+
+#[derive(FromMeta, Clone)]
+struct Bounds<T> {
+    min: Option<T>,
+    max: Option<T>,
+}
+
+impl Bounds<usize> {
+    fn to_tokens(&self) -> (proc_macro2::TokenStream, proc_macro2::TokenStream) {
+        (
+            optional(self.min.map(|min| quote! { #min })),
+            optional(self.max.map(|max| quote! { #max })),
+        )
+    }
+}
+
+impl Bounds<Bound> {
+    fn to_tokens(&self) -> (proc_macro2::TokenStream, proc_macro2::TokenStream) {
+        (
+            optional(self.min.as_ref().map(Bound::to_tokens)),
+            optional(self.max.as_ref().map(Bound::to_tokens)),
+        )
+    }
+}
+
+/// A bound for `range`, which is always an `f64`. Integer and float literals are converted here, since
+/// something like `10_000_000_000` can't be written as a float, and other expressions are cast.
+#[derive(Clone)]
+enum Bound {
+    Literal(f64),
+    Expr(Box<syn::Expr>),
+}
+
+impl Bound {
+    fn to_tokens(&self) -> proc_macro2::TokenStream {
+        match self {
+            Self::Literal(value) => quote! { #value },
+            Self::Expr(expr) => quote! {
+                {
+                    #[allow(clippy::cast_precision_loss, clippy::cast_lossless, clippy::unnecessary_cast)]
+                    let bound = (#expr) as f64;
+                    bound
+                }
+            },
+        }
+    }
+}
+
+impl FromMeta for Bound {
+    fn from_expr(expr: &syn::Expr) -> darling::Result<Self> {
+        match expr {
+            syn::Expr::Lit(syn::ExprLit { lit, .. }) => Self::from_value(lit),
+            syn::Expr::Unary(syn::ExprUnary {
+                op: syn::UnOp::Neg(_),
+                expr: inner,
+                ..
+            }) => match &**inner {
+                syn::Expr::Lit(syn::ExprLit { lit, .. }) => {
+                    literal_bound(lit).map(|value| Self::Literal(-value))
+                }
+                _ => Ok(Self::Expr(Box::new(expr.clone()))),
+            },
+            syn::Expr::Group(group) => Self::from_expr(&group.expr),
+            expr => Ok(Self::Expr(Box::new(expr.clone()))),
+        }
+    }
+
+    fn from_value(lit: &syn::Lit) -> darling::Result<Self> {
+        literal_bound(lit).map(Self::Literal)
+    }
+}
+
+fn literal_bound(lit: &syn::Lit) -> darling::Result<f64> {
+    let value = match lit {
+        syn::Lit::Int(int) => int.base10_parse::<f64>()?,
+        syn::Lit::Float(float) => float.base10_parse::<f64>()?,
+        _ => return Err(darling::Error::unexpected_lit_type(lit)),
+    };
+    if value.is_finite() {
+        Ok(value)
+    } else {
+        Err(darling::Error::custom("the bound is too large for an f64").with_span(lit))
+    }
+}
+
+impl Rules {
+    fn is_empty(&self) -> bool {
+        !self.non_empty.is_present()
+            && self.length.is_none()
+            && self.items.is_none()
+            && self.range.is_none()
+            && self.regex.is_none()
+            && !self.email.is_present()
+            && !self.url.is_present()
+            && self.warn.is_none()
+    }
+
+    /// The checks for `warn(...)`.
+    fn warning_checks(
+        &self,
+        value: &proc_macro2::TokenStream,
+    ) -> syn::Result<Vec<proc_macro2::TokenStream>> {
+        self.warn
+            .as_ref()
+            .map_or(Ok(Vec::new()), |warn| warn.checks(value))
+    }
+
+    /// A check for every rule, in a consistent order. Each one evaluates to a `Result<(), ValidationError>`.
+    fn checks(
+        &self,
+        value: &proc_macro2::TokenStream,
+    ) -> syn::Result<Vec<proc_macro2::TokenStream>> {
+        let rules = quote! { ::scalar_cms::validations::rules };
+
+        let mut checks = Vec::new();
+        if self.non_empty.is_present() {
+            checks.push(quote! { #rules::non_empty(#value) });
+        }
+        if let Some(bounds) = &self.length {
+            let (min, max) = bounds.to_tokens();
+            checks.push(quote! { #rules::length(#value, #min, #max) });
+        }
+        if let Some(bounds) = &self.items {
+            let (min, max) = bounds.to_tokens();
+            checks.push(quote! { #rules::items(#value, #min, #max) });
+        }
+        if let Some(bounds) = &self.range {
+            let (min, max) = bounds.to_tokens();
+            checks.push(quote! { #rules::range(#value, #min, #max) });
+        }
+        if let Some(regex) = &self.regex {
+            // catch bad patterns at compile time, rather than the first time something's validated
+            if let Err(e) = regex::Regex::new(&regex.value()) {
+                return Err(syn::Error::new(regex.span(), e));
+            }
+            // the pattern ends up in JSON Schema and the editor, which use ECMA-262 regexes
+            if let Some(syntax) = ecma_incompatibility(&regex.value()) {
+                return Err(syn::Error::new(
+                    regex.span(),
+                    format!(
+                        "the pattern has to work in JavaScript too, which doesn't support {syntax}"
+                    ),
+                ));
+            }
+            checks.push(quote! {
+                {
+                    static REGEX: ::std::sync::LazyLock<#rules::Regex> =
+                        ::std::sync::LazyLock::new(|| #rules::Regex::new(#regex).expect("the regex was checked at compile time"));
+                    #rules::regex(#value, &REGEX)
+                }
+            });
+        }
+        if self.email.is_present() {
+            checks.push(quote! { #rules::email(#value) });
+        }
+        if self.url.is_present() {
+            checks.push(quote! { #rules::url(#value) });
+        }
+
+        Ok(checks)
+    }
+
+    /// Every rule as a `scalar_cms::validations::rules::Rule`, for the editor.
+    fn to_schema(&self) -> Vec<proc_macro2::TokenStream> {
+        let rule = quote! { ::scalar_cms::validations::rules::Rule };
+
+        let mut rules = Vec::new();
+        if self.non_empty.is_present() {
+            rules.push(quote! { #rule::NonEmpty });
+        }
+        if let Some(bounds) = &self.length {
+            let (min, max) = bounds.to_tokens();
+            rules.push(quote! { #rule::Length { min: #min, max: #max } });
+        }
+        if let Some(bounds) = &self.items {
+            let (min, max) = bounds.to_tokens();
+            rules.push(quote! { #rule::Items { min: #min, max: #max } });
+        }
+        if let Some(bounds) = &self.range {
+            let (min, max) = bounds.to_tokens();
+            rules.push(quote! { #rule::Range { min: #min, max: #max } });
+        }
+        if let Some(regex) = &self.regex {
+            rules.push(quote! { #rule::Regex { pattern: #regex } });
+        }
+        if self.email.is_present() {
+            rules.push(quote! { #rule::Email { pattern: ::scalar_cms::validations::rules::EMAIL_PATTERN } });
+        }
+        if self.url.is_present() {
+            rules.push(
+                quote! { #rule::Url { pattern: ::scalar_cms::validations::rules::URL_PATTERN } },
+            );
+        }
+
+        rules
+    }
+}
+
+/// Syntax the regex crate supports but JavaScript doesn't, if the pattern has any.
+/// Differences in meaning, like `\d` matching any unicode digit in Rust, can't be caught here.
+fn ecma_incompatibility(pattern: &str) -> Option<&'static str> {
+    use regex_syntax::ast::{
+        self, AssertionKind, Ast, ClassSetBinaryOp, ClassSetItem, ClassUnicodeKind, GroupKind,
+        HexLiteralKind, LiteralKind, SpecialLiteralKind,
+    };
+
+    struct Checker;
+
+    fn literal(literal: &ast::Literal) -> Result<(), &'static str> {
+        match literal.kind {
+            LiteralKind::HexBrace(_) => Err("`\\x{...}` escapes"),
+            LiteralKind::HexFixed(HexLiteralKind::UnicodeLong) => Err("`\\U` escapes"),
+            LiteralKind::Octal => Err("octal escapes"),
+            LiteralKind::Special(SpecialLiteralKind::Bell) => Err("`\\a`"),
+            LiteralKind::Special(SpecialLiteralKind::Space) => Err("escaped spaces"),
+            LiteralKind::Meta if matches!(literal.c, '&' | '~' | '#') => {
+                Err("escaping `&`, `~` or `#`")
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn unicode_class(kind: &ClassUnicodeKind) -> Result<(), &'static str> {
+        match kind {
+            ClassUnicodeKind::OneLetter(_) => Err("`\\pL`, use `\\p{L}`"),
+            _ => Ok(()),
+        }
+    }
+
+    impl ast::Visitor for Checker {
+        type Output = ();
+        type Err = &'static str;
+
+        fn finish(self) -> Result<(), &'static str> {
+            Ok(())
+        }
+
+        fn visit_pre(&mut self, ast: &Ast) -> Result<(), &'static str> {
+            match ast {
+                Ast::Flags(_) => Err("inline flags like `(?i)`"),
+                Ast::Group(group) => match &group.kind {
+                    GroupKind::NonCapturing(flags) if !flags.items.is_empty() => {
+                        Err("inline flags like `(?i:...)`")
+                    }
+                    GroupKind::CaptureName {
+                        starts_with_p: true,
+                        ..
+                    } => Err("`(?P<name>...)`, use `(?<name>...)`"),
+                    _ => Ok(()),
+                },
+                Ast::Assertion(assertion) => match assertion.kind {
+                    AssertionKind::StartLine
+                    | AssertionKind::EndLine
+                    | AssertionKind::WordBoundary
+                    | AssertionKind::NotWordBoundary => Ok(()),
+                    AssertionKind::StartText | AssertionKind::EndText => {
+                        Err("`\\A` and `\\z`, use `^` and `$`")
+                    }
+                    _ => Err("word boundaries other than `\\b` and `\\B`"),
+                },
+                Ast::ClassUnicode(class) => unicode_class(&class.kind),
+                Ast::Literal(lit) => literal(lit),
+                _ => Ok(()),
+            }
+        }
+
+        fn visit_class_set_item_pre(&mut self, item: &ClassSetItem) -> Result<(), &'static str> {
+            match item {
+                ClassSetItem::Ascii(_) => Err("ASCII classes like `[[:alpha:]]`"),
+                ClassSetItem::Unicode(class) => unicode_class(&class.kind),
+                ClassSetItem::Literal(lit) => literal(lit),
+                _ => Ok(()),
+            }
+        }
+
+        fn visit_class_set_binary_op_pre(
+            &mut self,
+            _op: &ClassSetBinaryOp,
+        ) -> Result<(), &'static str> {
+            Err("class set operations like `&&`")
+        }
+    }
+
+    // a pattern that doesn't parse was already reported by the regex crate
+    let ast = ast::parse::Parser::new().parse(pattern).ok()?;
+    ast::visit(&ast, Checker).err()
 }
 
 /// Sets up an enum for use in a Document. This macro does a couple of things:
 /// 1. It derives serde's Serialize and Deserialize traits. Make sure you have serde installed!
-/// 2. Sets up said serialization and deserialization to work the way the editor expects.
+/// 2. Tags the enum by `type`, unless it already has a `#[serde(tag = "...")]` or `#[serde(untagged)]`.
 /// 3. Derives `ToEditorField` for the schema
+///
+/// Externally tagged enums, serde's default, can derive `Serialize`, `Deserialize` and `Enum` themselves.
 #[proc_macro_attribute]
 pub fn doc_enum(_metadata: TokenStream, input: TokenStream) -> TokenStream {
-    let input: proc_macro2::TokenStream = input.into();
+    let parsed: DeriveInput = parse_macro_input!(input);
+    let representation = match serde_enum(&parsed.attrs) {
+        Ok(serde) => serde.tag.is_some() || serde.untagged,
+        Err(e) => return e.into_compile_error().into(),
+    };
+    let tag = if representation {
+        quote! {}
+    } else {
+        quote! { #[serde(tag = "type")] }
+    };
     let output = quote! {
         #[derive(::serde::Serialize, ::serde::Deserialize, ::scalar_cms::Enum)]
-        #[serde(tag = "type")]
-        #input
+        #tag
+        #parsed
     };
     output.into()
 }
@@ -136,6 +539,13 @@ pub fn struct_to_editor_field(input: TokenStream) -> TokenStream {
 
             let (impl_generics, ty_generics, where_clause) = struct_info.generics.split_for_impl();
             let ty = quote! { #ident #ty_generics };
+            // generic structs can have different fields depending on their parameters, so they can't share a name
+            let name = if struct_info.generics.params.is_empty() {
+                let name = ident.to_string();
+                quote! { Some(#name) }
+            } else {
+                quote! { None }
+            };
 
             quote! {
                 impl #impl_generics ::scalar_cms::editor_field::ToEditorField for #ty where #ty: ::serde::Serialize #where_clause {
@@ -150,18 +560,18 @@ pub fn struct_to_editor_field(input: TokenStream) -> TokenStream {
                     where
                         Self: std::marker::Sized,
                     {
-                        ::scalar_cms::EditorField {
+                        ::scalar_cms::EditorField::new(
                             name,
                             title,
                             placeholder,
-                            required: true,
                             validator,
-                            field_type: ::scalar_cms::EditorType::Struct {
+                            ::scalar_cms::EditorType::Struct {
                                 default: default.map(Into::into).as_ref().map(::scalar_cms::serde_json::to_value).map(|v| v.expect("a struct that should serialize to json")),
                                 component_key: component_key.map(Into::into).or(#component_key),
+                                name: #name,
                                 fields: vec![#(#fields),*]
                             }
-                        }
+                        )
                     }
                 }
             }
@@ -179,43 +589,95 @@ pub fn derive_enum(input: TokenStream) -> TokenStream {
         Err(e) => return TokenStream::from(e.write_errors()),
     };
     let ident = input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let ty = quote! { #ident #ty_generics };
+    let predicates = where_clause.map(|w| &w.predicates);
+    // generic enums can have different variants depending on their parameters, so they can't share a name
+    let name = if input.generics.params.is_empty() {
+        let name = ident.to_string();
+        quote! { Some(#name) }
+    } else {
+        quote! { None }
+    };
 
-    let variants: Vec<proc_macro2::TokenStream> = match enum_info.data {
-        darling::ast::Data::Enum(variants) => variants
-            .iter()
-            .map(|v| {
-                let ident = v.ident.to_string();
-                let fields: Vec<proc_macro2::TokenStream> = v
-                    .fields
-                    .iter()
-                    .map(|field| field_to_info_call(field.to_owned()))
-                    .collect();
+    let serde = match serde_enum(&enum_info.attrs) {
+        Ok(serde) => serde,
+        Err(e) => return e.into_compile_error().into(),
+    };
+    let representation = match (serde.untagged, serde.tag, serde.content) {
+        (true, _, _) => quote! { ::scalar_cms::editor_type::EnumRepresentation::Untagged },
+        (false, Some(tag), Some(content)) => {
+            quote! { ::scalar_cms::editor_type::EnumRepresentation::Adjacent { tag: #tag, content: #content } }
+        }
+        (false, Some(tag), None) => {
+            quote! { ::scalar_cms::editor_type::EnumRepresentation::Internal { tag: #tag } }
+        }
+        (false, None, _) => quote! { ::scalar_cms::editor_type::EnumRepresentation::External },
+    };
 
-                let fields_tokens = if fields.is_empty() {
-                    quote! { None }
-                } else {
-                    quote! { Some(vec![#(#fields),*]) }
-                };
+    let darling::ast::Data::Enum(variants) = enum_info.data else {
+        unreachable!("darling only accepts enums here")
+    };
+    let mut errors = darling::Error::accumulator();
+    let variants: Vec<proc_macro2::TokenStream> = variants
+        .into_iter()
+        .filter_map(|v| {
+            let attrs = errors.handle(serde_variant(&v.attrs).map_err(darling::Error::from))?;
+            if attrs.skip {
+                return None;
+            }
+            let variant_name = match attrs.rename {
+                Some(rename) => rename,
+                None => errors.handle(rename_variant(
+                    &cleanup_ident(&v.ident),
+                    serde.rename_all.as_ref(),
+                ))?,
+            };
 
-                quote! {
-                    ::scalar_cms::editor_type::EnumVariant {
-                        variant_name: #ident,
-                        fields: #fields_tokens
-                    }
+            let (kind, fields) = match v.fields.style {
+                darling::ast::Style::Unit => (quote! { Unit }, quote! { None }),
+                darling::ast::Style::Struct => {
+                    let fields = v.fields.into_iter().map(field_to_info_call);
+                    (quote! { Struct }, quote! { Some(vec![#(#fields),*]) })
+                }
+                darling::ast::Style::Tuple => {
+                    let kind = if v.fields.len() == 1 {
+                        quote! { Newtype }
+                    } else {
+                        quote! { Tuple }
+                    };
+                    let fields = v
+                        .fields
+                        .into_iter()
+                        .enumerate()
+                        .map(|(index, field)| field_info_call(field, &index.to_string()));
+                    (kind, quote! { Some(vec![#(#fields),*]) })
+                }
+            };
+
+            Some(quote! {
+                ::scalar_cms::editor_type::EnumVariant {
+                    variant_name: #variant_name,
+                    kind: ::scalar_cms::editor_type::VariantKind::#kind,
+                    fields: #fields
                 }
             })
-            .collect(),
-        darling::ast::Data::Struct(_) => unreachable!(),
-    };
+        })
+        .collect();
+    if let Err(e) = errors.finish() {
+        return e.write_errors().into();
+    }
 
     let output = quote! {
-        impl ::scalar_cms::editor_field::ToEditorField for #ident where Self: ::serde::Serialize {
-            fn to_editor_field(default: Option<impl Into<Self>>, name: &'static str, title: &'static str, placeholder: Option<&'static str>, validator: Option<&'static str>, component_key: Option<&'static str>) -> ::scalar_cms::EditorField where Self: std::marker::Sized {
-                ::scalar_cms::EditorField { name, title, placeholder, required: true, validator, field_type: ::scalar_cms::EditorType::Enum {
+        impl #impl_generics ::scalar_cms::editor_field::ToEditorField for #ty where #ty: ::serde::Serialize, #predicates {
+            fn to_editor_field(default: Option<impl Into<#ty>>, name: &'static str, title: &'static str, placeholder: Option<&'static str>, validator: Option<&'static str>, component_key: Option<&'static str>) -> ::scalar_cms::EditorField where Self: std::marker::Sized {
+                ::scalar_cms::EditorField::new(name, title, placeholder, validator, ::scalar_cms::EditorType::Enum {
                     default: default.map(Into::into).map(::scalar_cms::serde_json::to_value).map(|v| v.expect("a struct that should serialize to json")),
                     component_key: component_key.map(Into::into),
+                    name: #name,
+                    representation: #representation,
                     variants: vec![#(#variants),*]
-                } }
+                })
             }
         }
     };
@@ -224,6 +686,17 @@ pub fn derive_enum(input: TokenStream) -> TokenStream {
 
 /// Derives the document trait.
 ///
+/// `#[validate(document = path)]` on the struct validates rules that span multiple fields. It's called
+/// like `path(&doc, ctx).await` after the fields are validated, and returns a `Result<(), Vec<ErroredField>>`
+/// which is merged with the field errors.
+///
+/// Rules like `#[validate(non_empty, length(max = 100))]` run before the field's own `Validate` impl, or `with`
+/// if it's given. Types that don't implement `Validate`, like `String`, can use `rules_only` to only check the rules.
+/// `regex = "..."` patterns have to work in JavaScript too, since the editor and JSON Schema use them as is.
+///
+/// `#[document(groups(content, seo(title = "SEO", fieldset, collapsed)))]` declares groups of fields for the
+/// editor, and `#[field(group = "seo")]` puts a field in one. Groups are tabs unless they're fieldsets.
+///
 /// # Panics
 ///
 /// Panics if the input is somehow a tuple struct that isn't caught.
@@ -236,6 +709,12 @@ pub fn derive_document(input: TokenStream) -> TokenStream {
             return TokenStream::from(e.write_errors());
         }
     };
+    let document_validate = match DocumentValidate::from_derive_input(&input) {
+        Ok(v) => v,
+        Err(e) => {
+            return TokenStream::from(e.write_errors());
+        }
+    };
     let struct_fields = match input.data {
         Data::Struct(st) => st.fields,
         _ => unreachable!(),
@@ -252,6 +731,14 @@ pub fn derive_document(input: TokenStream) -> TokenStream {
 
     let singleton = document.singleton.is_present();
 
+    let migrations = document.migrations.map(|path| {
+        quote! {
+            fn migrations() -> &'static [::scalar_cms::migrations::Migration] {
+                &#path
+            }
+        }
+    });
+
     let struct_field_infos = match struct_fields
         .iter()
         .map(FieldInfo::from_field)
@@ -261,73 +748,19 @@ pub fn derive_document(input: TokenStream) -> TokenStream {
         Err(e) => return TokenStream::from(e.write_errors()),
     };
 
-    let document_label = match struct_field_infos
-        .iter()
-        .filter(|f| f.label.is_present())
-        .collect::<Vec<_>>()
-        .as_slice()
-    {
-        [] => None,
-        [one] => Some(cleanup_ident(
-            one.ident
-                .as_ref()
-                .expect("this shouldn't be a tuple struct!!"),
-        )),
-        [head, tail @ ..] => {
-            return tail
-                .iter()
-                .fold(
-                    syn::Error::new(
-                        head.label.span(),
-                        "only one field can be defined as the label",
-                    ),
-                    |mut error, field| {
-                        error.combine(syn::Error::new(
-                            field.label.span(),
-                            "only one field can be defined as the label",
-                        ));
-                        error
-                    },
-                )
-                .into_compile_error()
-                .into()
-        }
-    }
-    .map_or(quote! { None }, |lit| quote! {Some(#lit)});
+    let groups = match groups(document.groups, &struct_field_infos) {
+        Ok(groups) => groups,
+        Err(e) => return e.into_compile_error().into(),
+    };
 
-    let document_sub_label = match struct_field_infos
-        .iter()
-        .filter(|f| f.sublabel.is_present())
-        .collect::<Vec<_>>()
-        .as_slice()
-    {
-        [] => None,
-        [one] => Some(cleanup_ident(
-            one.ident
-                .as_ref()
-                .expect("this shouldn't be a tuple struct!!"),
-        )),
-        [head, tail @ ..] => {
-            return tail
-                .iter()
-                .fold(
-                    syn::Error::new(
-                        head.sublabel.span(),
-                        "only one field can be defined as the sub label",
-                    ),
-                    |mut error, field| {
-                        error.combine(syn::Error::new(
-                            field.sublabel.span(),
-                            "only one field can be defined as the sub label",
-                        ));
-                        error
-                    },
-                )
-                .into_compile_error()
-                .into()
-        }
-    }
-    .map_or(quote! { None }, |lit| quote! {Some(#lit)});
+    let document_label = match label(&struct_field_infos, |f| &f.label, "label") {
+        Ok(label) => label,
+        Err(e) => return e.into_compile_error().into(),
+    };
+    let document_sub_label = match label(&struct_field_infos, |f| &f.sublabel, "sub label") {
+        Ok(label) => label,
+        Err(e) => return e.into_compile_error().into(),
+    };
 
     let struct_validators = match struct_fields
         .iter()
@@ -338,31 +771,16 @@ pub fn derive_document(input: TokenStream) -> TokenStream {
         Err(e) => return TokenStream::from(e.write_errors()),
     };
 
-    let fields = struct_field_infos
-        .iter()
-        .map(|f| field_to_info_call(f.to_owned()))
-        .collect::<Vec<_>>();
-
-    let validators = struct_validators
-        .iter()
-        .filter(|&f| !f.skip.is_present())
-        .map(|f| {
-            let ident = f.ident.as_ref().expect("this shouldn't be a tuple struct!");
-            let ident_str = ident.to_string();
-
-            if let Some(fn_ident) = f.with.as_ref() {
-                quote! {
-                    (#ident_str.into(), #fn_ident(&self.#ident, ctx.for_field(#ident_str)).await)
-                }
-            } else {
-                quote! {
-                    (#ident_str.into(), ::scalar_cms::validations::Validate::validate(&self.#ident, ctx.for_field(#ident_str)).await)
-                }
-            }
-        })
-        .collect::<Vec<_>>();
-
-    let validators_count = validators.len();
+    let fields = field_calls(&struct_field_infos, &struct_validators);
+    let validate_impl = match validate_impl(
+        &ident,
+        &struct_field_infos,
+        &struct_validators,
+        document_validate.document.as_ref(),
+    ) {
+        Ok(v) => v,
+        Err(e) => return e.into_compile_error().into(),
+    };
 
     let output = quote! {
         #[automatically_derived]
@@ -382,35 +800,230 @@ pub fn derive_document(input: TokenStream) -> TokenStream {
 
                 &FIELDS
             }
+
+            #migrations
+
+            #groups
+        }
+
+        #validate_impl
+    };
+    output.into()
+}
+
+/// The `Validate` impl of a document, which validates every field and then the document as a whole.
+fn validate_impl(
+    ident: &Ident,
+    infos: &[FieldInfo],
+    field_validators: &[ValidateInfo],
+    document: Option<&syn::Path>,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let validators = validators(infos, field_validators)?;
+    let validators_count = validators.len();
+
+    // conditions are evaluated against the serialized document
+    let document_value = infos
+        .iter()
+        .zip(field_validators)
+        .any(|(info, v)| info.hidden_if.is_some() && !v.skip.is_present())
+        .then(|| {
+            quote! {
+                let document = ::scalar_cms::serde_json::to_value(self)
+                    .expect("documents should always serialize");
+            }
+        });
+
+    // runs even if fields are invalid, so every error shows up at once
+    let document_validator = document.map(|path| {
+        quote! {
+            if let Err(document_errors) = #path(self, ctx).await {
+                ::scalar_cms::validations::ErroredField::merge(&mut errors, document_errors);
+            }
         }
+    });
 
+    Ok(quote! {
         #[automatically_derived]
         impl ::scalar_cms::validations::Validate for #ident {
             async fn validate<DB: ::scalar_cms::db::DatabaseConnection + Sync, D: ::scalar_cms::Document + Sync>(&self, ctx: ::scalar_cms::db::ValidationContext<'_, DB, D>) -> Result<(), ::scalar_cms::validations::ValidationError> {
+                #document_value
                 let results: [(::scalar_cms::validations::Field, Result<(), ::scalar_cms::validations::ValidationError>); #validators_count] = [#(#validators),*];
 
-                let errors: Vec<::scalar_cms::validations::ErroredField> = results
+                #[allow(unused_mut)]
+                let mut errors: Vec<::scalar_cms::validations::ErroredField> = results
                     .into_iter()
                     .filter_map(|(f, r)| r.err().map(|e| ::scalar_cms::validations::ErroredField { field: f, error: e}))
                     .collect();
 
+                #document_validator
+
                 errors
                     .is_empty()
                     .then_some(())
                     .ok_or(::scalar_cms::validations::ValidationError::Composite(errors))
             }
         }
+    })
+}
+
+/// The field marked with `flag`, if there is one.
+fn label(
+    fields: &[FieldInfo],
+    flag: impl Fn(&FieldInfo) -> &Flag,
+    what: &str,
+) -> syn::Result<proc_macro2::TokenStream> {
+    match fields
+        .iter()
+        .filter(|f| flag(f).is_present())
+        .collect::<Vec<_>>()
+        .as_slice()
+    {
+        [] => Ok(quote! { None }),
+        [one] => {
+            let name = cleanup_ident(
+                one.ident
+                    .as_ref()
+                    .expect("this shouldn't be a tuple struct!!"),
+            );
+            Ok(quote! { Some(#name) })
+        }
+        [head, tail @ ..] => {
+            let message = format!("only one field can be defined as the {what}");
+            Err(tail.iter().fold(
+                syn::Error::new(flag(head).span(), &message),
+                |mut error, field| {
+                    error.combine(syn::Error::new(flag(field).span(), &message));
+                    error
+                },
+            ))
+        }
+    }
+}
+
+/// The editor fields of a document, along with the rules and warnings they're validated with.
+fn field_calls(infos: &[FieldInfo], validators: &[ValidateInfo]) -> Vec<proc_macro2::TokenStream> {
+    infos
+        .iter()
+        .zip(validators)
+        .map(|(f, v)| {
+            let call = field_to_info_call(f.to_owned());
+            let rules = v.rules.to_schema();
+            let warnings = v
+                .rules
+                .warn
+                .as_ref()
+                .map(|warn| warn.to_schema())
+                .unwrap_or_default();
+            if rules.is_empty() && warnings.is_empty() {
+                call
+            } else {
+                quote! {
+                    {
+                        let mut field = #call;
+                        field.rules = vec![#(#rules),*];
+                        field.warnings = vec![#(#warnings),*];
+                        field
+                    }
+                }
+            }
+        })
+        .collect()
+}
+
+/// A `(field, result)` pair validating each field that isn't skipped.
+fn validators(
+    infos: &[FieldInfo],
+    validators: &[ValidateInfo],
+) -> syn::Result<Vec<proc_macro2::TokenStream>> {
+    if let Some(field) = validators.iter().find(|f| {
+        f.skip.is_present()
+            && (f.with.is_some() || f.rules_only.is_present() || !f.rules.is_empty())
+    }) {
+        return Err(syn::Error::new(
+            field.skip.span(),
+            "skipped fields can't have validation rules",
+        ));
+    }
+
+    validators
+        .iter()
+        .zip(infos)
+        .filter(|(f, _)| !f.skip.is_present())
+        .map(|(f, info)| field_validator(f, info))
+        .collect()
+}
+
+/// Validates one field with it's rules and warnings, unless it's hidden.
+fn field_validator(f: &ValidateInfo, info: &FieldInfo) -> syn::Result<proc_macro2::TokenStream> {
+    let ident = f.ident.as_ref().expect("this shouldn't be a tuple struct!");
+    let ident_str = ident.to_string();
+    if f.rules
+        .warn
+        .as_ref()
+        .is_some_and(|warn| warn.warn.is_some())
+    {
+        return Err(syn::Error::new(ident.span(), "warn(...) can't be nested"));
+    }
+    let checks = f.rules.checks(&quote! { &self.#ident })?;
+    let warnings = f.rules.warning_checks(&quote! { &self.#ident })?;
+
+    if f.rules_only.is_present() && (f.with.is_some() || f.rules.is_empty()) {
+        return Err(syn::Error::new(
+            f.rules_only.span(),
+            "rules_only needs at least one rule, and can't be used with `with`",
+        ));
+    }
+
+    // rules run before the field's own validation, which `with` replaces
+    let validator = if let Some(fn_path) = f.with.as_ref() {
+        quote! { #fn_path(&self.#ident, ctx.for_field(#ident_str)).await }
+    } else if f.rules_only.is_present() {
+        quote! { Ok(()) }
+    } else {
+        quote! { ::scalar_cms::validations::Validate::validate(&self.#ident, ctx.for_field(#ident_str)).await }
     };
-    output.into()
+
+    let validator = if checks.is_empty() {
+        validator
+    } else {
+        quote! { ::scalar_cms::validations::rules::with_rules([#(#checks),*], #validator) }
+    };
+
+    // warnings never stop anything else from being checked
+    let result = if warnings.is_empty() {
+        validator
+    } else {
+        quote! { ::scalar_cms::validations::rules::with_warnings(#validator, [#(#warnings),*]) }
+    };
+
+    // hidden fields aren't validated, the editor doesn't show them so they can't be fixed
+    Ok(if let Some(hidden_if) = &info.hidden_if {
+        let name = cleanup_ident(ident);
+        quote! {
+            (#ident_str.into(), if ::scalar_cms::expr::Expression::evaluate(&#hidden_if, &document, document.get(#name)) {
+                Ok(())
+            } else {
+                #result
+            })
+        }
+    } else {
+        quote! { (#ident_str.into(), #result) }
+    })
 }
 
 fn field_to_info_call(field: FieldInfo) -> proc_macro2::TokenStream {
-    let ty = field.ty;
-
     let ident = field
         .ident
-        .map(|i| cleanup_ident(&i))
+        .as_ref()
+        .map(cleanup_ident)
         .expect("this shouldn't be a tuple struct!!!!");
+    field_info_call(field, &ident)
+}
+
+/// Like [`field_to_info_call`], for fields that don't have a name of their own, like in tuple variants.
+fn field_info_call(field: FieldInfo, ident: &str) -> proc_macro2::TokenStream {
+    let ty = field.ty;
+
     let title = field
         .title
         .unwrap_or(ident.to_case(convert_case::Case::Title));
@@ -425,23 +1038,275 @@ fn field_to_info_call(field: FieldInfo) -> proc_macro2::TokenStream {
         quote! { None }
     };
 
-    // let validator = match field.validate.is_present() {
-    //     true => quote! { Some(stringify!(#ty)) },
-    //     false => quote! { None },
-    // };
-
     let default = match field.default {
         Some(lit) => quote! { Some(#lit) },
         None => {
             quote! { None::<#ty> }
         }
     };
-    quote! {
+    let call = quote! {
         <#ty as ::scalar_cms::editor_field::ToEditorField>::to_editor_field(#default, #ident, #title, #placeholder, None, #component_key)
+    };
+
+    let description = field.description.or_else(|| doc_comment(&field.attrs));
+    if description.is_none()
+        && field.deprecated.is_none()
+        && field.hidden_if.is_none()
+        && !field.readonly.is_present()
+        && field.readonly_if.is_none()
+    {
+        return call;
+    }
+    let description = optional(description.map(|description| quote! { #description }));
+    let deprecated = optional(field.deprecated.map(|reason| quote! { #reason }));
+    let hidden_if = optional(field.hidden_if.map(|expr| quote! { #expr }));
+    let readonly = field.readonly.is_present();
+    let readonly_if = optional(field.readonly_if.map(|expr| quote! { #expr }));
+    quote! {
+        {
+            let mut field = #call;
+            field.description = #description;
+            field.deprecated = #deprecated;
+            field.hidden_if = #hidden_if;
+            field.readonly = #readonly;
+            field.readonly_if = #readonly_if;
+            field
+        }
     }
 }
 
+/// Joins the lines of a doc comment, like rustdoc does before rendering it.
+fn doc_comment(attrs: &[syn::Attribute]) -> Option<String> {
+    let lines = attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("doc"))
+        .filter_map(|attr| match &attr.meta {
+            syn::Meta::NameValue(syn::MetaNameValue {
+                value:
+                    syn::Expr::Lit(syn::ExprLit {
+                        lit: syn::Lit::Str(line),
+                        ..
+                    }),
+                ..
+            }) => Some(line.value()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    let doc = lines
+        .iter()
+        .map(|line| line.strip_prefix(' ').unwrap_or(line))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let doc = doc.trim();
+
+    (!doc.is_empty()).then(|| doc.to_owned())
+}
+
+/// The `groups` function for a document, if it has any groups.
+fn groups(
+    groups: Option<Groups>,
+    fields: &[FieldInfo],
+) -> syn::Result<Option<proc_macro2::TokenStream>> {
+    let groups = groups.map(|groups| groups.0).unwrap_or_default();
+
+    for (i, (id, _)) in groups.iter().enumerate() {
+        if groups[..i].iter().any(|(other, _)| other == id) {
+            return Err(syn::Error::new(id.span(), "duplicate group"));
+        }
+    }
+    for (_, info) in &groups {
+        if !info.fieldset.is_present() && info.collapsible.is_present() {
+            return Err(syn::Error::new(
+                info.collapsible.span(),
+                "only fieldsets can be collapsible",
+            ));
+        }
+        if !info.fieldset.is_present() && info.collapsed.is_present() {
+            return Err(syn::Error::new(
+                info.collapsed.span(),
+                "only fieldsets can be collapsed",
+            ));
+        }
+    }
+    for group in fields.iter().filter_map(|field| field.group.as_ref()) {
+        if !groups.iter().any(|(id, _)| *id == group.value()) {
+            return Err(syn::Error::new(
+                group.span(),
+                "unknown group, add it to #[document(groups(...))]",
+            ));
+        }
+    }
+
+    if groups.is_empty() {
+        return Ok(None);
+    }
+
+    let groups = groups.iter().map(|(id, info)| {
+        let id = id.to_string();
+        let title = info
+            .title
+            .clone()
+            .unwrap_or_else(|| id.to_case(convert_case::Case::Title));
+        let kind = if info.fieldset.is_present() {
+            quote! { ::scalar_cms::GroupKind::Fieldset }
+        } else {
+            quote! { ::scalar_cms::GroupKind::Tab }
+        };
+        // collapsed fieldsets have to be collapsible, otherwise they couldn't be opened
+        let collapsible = info.collapsible.is_present() || info.collapsed.is_present();
+        let collapsed = info.collapsed.is_present();
+        let fields = fields
+            .iter()
+            .filter(|field| {
+                field
+                    .group
+                    .as_ref()
+                    .is_some_and(|group| group.value() == id)
+            })
+            .map(|field| {
+                cleanup_ident(
+                    field
+                        .ident
+                        .as_ref()
+                        .expect("this shouldn't be a tuple struct!"),
+                )
+            });
+
+        quote! {
+            ::scalar_cms::FieldGroup {
+                id: #id,
+                title: #title,
+                kind: #kind,
+                collapsible: #collapsible,
+                collapsed: #collapsed,
+                fields: &[#(#fields),*],
+            }
+        }
+    });
+
+    Ok(Some(quote! {
+        fn groups() -> &'static [::scalar_cms::FieldGroup] {
+            &[#(#groups),*]
+        }
+    }))
+}
+
+fn optional(value: Option<proc_macro2::TokenStream>) -> proc_macro2::TokenStream {
+    value.map_or(quote! { None }, |value| quote! { Some(#value) })
+}
+
 /// cleans up idents which may start with r#, same as serde
 fn cleanup_ident(ident: &Ident) -> String {
     ident.to_string().trim_start_matches("r#").to_string()
 }
+
+/// Reads `#[serde(...)]` on an enum, skipping anything that doesn't change it's representation.
+fn serde_enum(attrs: &[syn::Attribute]) -> syn::Result<SerdeEnum> {
+    let mut serde = SerdeEnum::default();
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("serde")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("tag") {
+                serde.tag = Some(meta.value()?.parse::<syn::LitStr>()?.value());
+            } else if meta.path.is_ident("content") {
+                serde.content = Some(meta.value()?.parse::<syn::LitStr>()?.value());
+            } else if meta.path.is_ident("untagged") {
+                serde.untagged = true;
+            } else if meta.path.is_ident("rename_all") {
+                serde.rename_all = serialized_name(&meta)?;
+            } else {
+                skip_meta(&meta)?;
+            }
+            Ok(())
+        })?;
+    }
+
+    Ok(serde)
+}
+
+/// Reads `#[serde(...)]` on a variant, skipping anything that doesn't change how it's serialized.
+fn serde_variant(attrs: &[syn::Attribute]) -> syn::Result<SerdeVariant> {
+    let mut serde = SerdeVariant::default();
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("serde")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename") {
+                serde.rename = serialized_name(&meta)?.map(|name| name.value());
+            } else if meta.path.is_ident("skip") || meta.path.is_ident("skip_deserializing") {
+                // the editor can't write variants that can't be deserialized
+                serde.skip = true;
+            } else {
+                skip_meta(&meta)?;
+            }
+            Ok(())
+        })?;
+    }
+
+    Ok(serde)
+}
+
+/// Reads `name = "..."`, or the serialized name in `name(serialize = "...", deserialize = "...")`.
+fn serialized_name(meta: &syn::meta::ParseNestedMeta) -> syn::Result<Option<syn::LitStr>> {
+    if meta.input.peek(syn::Token![=]) {
+        return Ok(Some(meta.value()?.parse()?));
+    }
+
+    let mut name = None;
+    meta.parse_nested_meta(|meta| {
+        if meta.path.is_ident("serialize") {
+            name = Some(meta.value()?.parse()?);
+        } else {
+            skip_meta(&meta)?;
+        }
+        Ok(())
+    })?;
+    Ok(name)
+}
+
+/// Skips the value of a serde attribute we don't care about, like `= "..."` or `(...)`.
+fn skip_meta(meta: &syn::meta::ParseNestedMeta) -> syn::Result<()> {
+    if meta.input.peek(syn::Token![=]) {
+        meta.value()?.parse::<syn::Expr>()?;
+    } else if meta.input.peek(syn::token::Paren) {
+        meta.input.parse::<proc_macro2::TokenTree>()?;
+    }
+    Ok(())
+}
+
+/// Renames a variant like serde's `rename_all` does.
+fn rename_variant(variant: &str, rule: Option<&syn::LitStr>) -> darling::Result<String> {
+    let Some(rule) = rule else {
+        return Ok(variant.to_owned());
+    };
+    let snake = variant
+        .char_indices()
+        .fold(String::new(), |mut snake, (i, c)| {
+            if i > 0 && c.is_uppercase() {
+                snake.push('_');
+            }
+            snake.push(c.to_ascii_lowercase());
+            snake
+        });
+
+    Ok(match rule.value().as_str() {
+        "lowercase" => variant.to_ascii_lowercase(),
+        "UPPERCASE" => variant.to_ascii_uppercase(),
+        "PascalCase" => variant.to_owned(),
+        "camelCase" => {
+            let mut chars = variant.chars();
+            chars
+                .next()
+                .map(|first| first.to_ascii_lowercase())
+                .into_iter()
+                .chain(chars)
+                .collect()
+        }
+        "snake_case" => snake,
+        "SCREAMING_SNAKE_CASE" => snake.to_ascii_uppercase(),
+        "kebab-case" => snake.replace('_', "-"),
+        "SCREAMING-KEBAB-CASE" => snake.to_ascii_uppercase().replace('_', "-"),
+        other => {
+            return Err(
+                darling::Error::custom(format!("unknown rename rule `{other}`")).with_span(rule),
+            )
+        }
+    })
+}
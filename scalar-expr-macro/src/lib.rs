@@ -1,15 +1,15 @@
 use proc_macro::TokenStream;
 use unsynn::{
-    AndAnd, DelimitedVec, Either, Equal, Ident, LeftAssocExpr, Literal, NonAssocExpr, NotEqual,
-    OrOr, ParenthesisGroupContaining, Parse, ToTokenIter, TrailingDelimiter::Forbidden, quote,
-    unsynn,
+    AndAnd, DelimitedVec, Either, Equal, Ge, Gt, Ident, Le, LeftAssocExpr, Literal, Lt,
+    NonAssocExpr, NotEqual, OrOr, ParenthesisGroupContaining, Parse, ToTokenIter,
+    TrailingDelimiter::Forbidden, quote, unsynn,
 };
 use unsynn::{Colon, Cons, Dollar, LiteralString, ToTokens};
 
 unsynn! {
     keyword Field = "field";
     keyword Current = "current";
-    struct ComparisonOp(Either<Equal, NotEqual>);
+    struct ComparisonOp(Either<Either<Equal, NotEqual>, Either<Ge, Le, Gt, Lt>>);
     type ComparisonExpr = DelimitedVec<Either<Cons<Dollar, Current>, Cons<Field, Colon, LiteralString>, Literal, Ident>, ComparisonOp, Forbidden, 2, 2>;
 
     struct CompositeOp(Either<AndAnd, OrOr>);
@@ -41,20 +41,24 @@ pub fn expression(token_stream: TokenStream) -> TokenStream {
 }
 
 fn component_to_token_stream(component: &ComparisonExpr) -> unsynn::TokenStream {
-    let operator = match component[0]
+    let operator = match &component[0]
         .delimiter
         .as_ref()
         .expect("expected an operator")
         .0
     {
-        Either::First(_) => quote! {Equals},
-        Either::Second(_) => quote! {NotEquals},
+        Either::First(Either::First(_)) => quote! {Equals},
+        Either::First(Either::Second(_)) => quote! {NotEquals},
+        Either::Second(Either::First(_)) => quote! {GreaterThanOrEquals},
+        Either::Second(Either::Second(_)) => quote! {LessThanOrEquals},
+        Either::Second(Either::Third(_)) => quote! {GreaterThan},
+        Either::Second(Either::Fourth(_)) => quote! {LessThan},
         _ => unreachable!(),
     };
     let lhs = match &component[0].value {
         Either::First(_) => quote! {::scalar_expr::Value::CurrentField},
         Either::Second(Cons { third, .. }) => {
-            quote! {::scalar_expr::Value::Ident(#third)}
+            quote! {::scalar_expr::Value::Ident(::std::borrow::Cow::Borrowed(#third))}
         }
         Either::Third(val) => {
             quote! {::scalar_expr::Value::Value(::scalar_expr::to_value(#val).unwrap())}
@@ -66,7 +70,7 @@ fn component_to_token_stream(component: &ComparisonExpr) -> unsynn::TokenStream
     let rhs = match &component[1].value {
         Either::First(_) => quote! {::scalar_expr::Value::CurrentField},
         Either::Second(Cons { third, .. }) => {
-            quote! {::scalar_expr::Value::Ident(#third)}
+            quote! {::scalar_expr::Value::Ident(::std::borrow::Cow::Borrowed(#third))}
         }
         Either::Third(val) => {
             quote! {::scalar_expr::Value::Value(::scalar_expr::to_value(#val).unwrap())}
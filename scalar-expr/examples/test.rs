@@ -2,8 +2,9 @@ use scalar_expr::expression;
 
 fn main() {
     let wow = 3;
+    let min = 1;
     println!(
         "{:#?}",
-        expression!(field:"x" == wow && field:"y" == 2 || 2 == 2)
+        expression!(field:"x" == wow && field:"y" >= min || 2 == 2)
     );
 }
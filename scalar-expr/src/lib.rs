@@ -1,12 +1,21 @@
-#[derive(Debug)]
+use std::{borrow::Cow, cmp::Ordering};
+
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+#[derive(Debug, Serialize, Deserialize, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export)]
 pub enum Value {
     CurrentField,
-    Ident(&'static str),
-    Value(serde_json::Value),
+    Ident(Cow<'static, str>),
+    Value(#[ts(type = "any")] serde_json::Value),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize, TS)]
+#[serde(tag = "op", rename_all = "snake_case")]
 #[non_exhaustive]
+#[ts(export)]
 pub enum Expression {
     Equals {
         lhs: Value,
@@ -16,6 +25,22 @@ pub enum Expression {
         lhs: Value,
         rhs: Value,
     },
+    GreaterThan {
+        lhs: Value,
+        rhs: Value,
+    },
+    GreaterThanOrEquals {
+        lhs: Value,
+        rhs: Value,
+    },
+    LessThan {
+        lhs: Value,
+        rhs: Value,
+    },
+    LessThanOrEquals {
+        lhs: Value,
+        rhs: Value,
+    },
     And {
         lhs: Box<Expression>,
         rhs: Box<Expression>,
@@ -26,5 +51,102 @@ pub enum Expression {
     },
 }
 
+impl Expression {
+    /// Returns every value used in this expression, depth first.
+    #[must_use]
+    pub fn values(&self) -> Vec<&Value> {
+        match self {
+            Self::Equals { lhs, rhs }
+            | Self::NotEquals { lhs, rhs }
+            | Self::GreaterThan { lhs, rhs }
+            | Self::GreaterThanOrEquals { lhs, rhs }
+            | Self::LessThan { lhs, rhs }
+            | Self::LessThanOrEquals { lhs, rhs } => vec![lhs, rhs],
+            Self::And { lhs, rhs } | Self::Or { lhs, rhs } => {
+                let mut values = lhs.values();
+                values.extend(rhs.values());
+                values
+            }
+        }
+    }
+
+    /// Evaluates this expression against a serialized document. Fields are looked up by name, with `.`
+    /// separating nested fields, and `$current` is `current`. Anything missing is `null`.
+    ///
+    /// Numbers are compared by value, and strings can be ordered. Ordering anything else is always false.
+    #[must_use]
+    pub fn evaluate(
+        &self,
+        document: &serde_json::Value,
+        current: Option<&serde_json::Value>,
+    ) -> bool {
+        let order = |lhs: &Value, rhs: &Value| {
+            compare(
+                lhs.resolve(document, current),
+                rhs.resolve(document, current),
+            )
+        };
+
+        match self {
+            Self::Equals { lhs, rhs } => equals(
+                lhs.resolve(document, current),
+                rhs.resolve(document, current),
+            ),
+            Self::NotEquals { lhs, rhs } => !equals(
+                lhs.resolve(document, current),
+                rhs.resolve(document, current),
+            ),
+            Self::GreaterThan { lhs, rhs } => order(lhs, rhs) == Some(Ordering::Greater),
+            Self::GreaterThanOrEquals { lhs, rhs } => order(lhs, rhs).is_some_and(Ordering::is_ge),
+            Self::LessThan { lhs, rhs } => order(lhs, rhs) == Some(Ordering::Less),
+            Self::LessThanOrEquals { lhs, rhs } => order(lhs, rhs).is_some_and(Ordering::is_le),
+            Self::And { lhs, rhs } => {
+                lhs.evaluate(document, current) && rhs.evaluate(document, current)
+            }
+            Self::Or { lhs, rhs } => {
+                lhs.evaluate(document, current) || rhs.evaluate(document, current)
+            }
+        }
+    }
+}
+
+static NULL: serde_json::Value = serde_json::Value::Null;
+
+impl Value {
+    fn resolve<'a>(
+        &'a self,
+        document: &'a serde_json::Value,
+        current: Option<&'a serde_json::Value>,
+    ) -> &'a serde_json::Value {
+        match self {
+            Self::CurrentField => current.unwrap_or(&NULL),
+            Self::Ident(ident) => ident
+                .split('.')
+                .try_fold(document, |value, field| value.get(field))
+                .unwrap_or(&NULL),
+            Self::Value(value) => value,
+        }
+    }
+}
+
+fn equals(lhs: &serde_json::Value, rhs: &serde_json::Value) -> bool {
+    // 1 and 1.0 are different json values, but they should be equal here
+    if lhs.is_number() && rhs.is_number() {
+        compare(lhs, rhs) == Some(Ordering::Equal)
+    } else {
+        lhs == rhs
+    }
+}
+
+fn compare(lhs: &serde_json::Value, rhs: &serde_json::Value) -> Option<Ordering> {
+    match (lhs, rhs) {
+        (serde_json::Value::Number(lhs), serde_json::Value::Number(rhs)) => {
+            lhs.as_f64()?.partial_cmp(&rhs.as_f64()?)
+        }
+        (serde_json::Value::String(lhs), serde_json::Value::String(rhs)) => Some(lhs.cmp(rhs)),
+        _ => None,
+    }
+}
+
 pub use scalar_expr_macro::expression;
 pub use serde_json::to_value;
@@ -31,16 +31,15 @@ impl ToEditorField for Null {
     where
         Self: std::marker::Sized,
     {
-        EditorField {
+        EditorField::new(
             name,
             title,
             placeholder,
-            required: true,
             validator,
-            field_type: scalar_cms::EditorType::Null {
+            scalar_cms::EditorType::Null {
                 component_key: component_key.map(Into::into),
             },
-        }
+        )
     }
 }
 
@@ -0,0 +1,15 @@
+-- Add migration script here
+CREATE TABLE "sc__revisions" (
+	"revision_id"	TEXT NOT NULL,
+	"id"	TEXT NOT NULL,
+	"doc"	TEXT NOT NULL,
+	"created_at"	TEXT NOT NULL,
+	"created_by"	TEXT NOT NULL,
+	"inner"	TEXT NOT NULL,
+	PRIMARY KEY("revision_id")
+);
+
+CREATE INDEX "doc_revisions" ON "sc__revisions" (
+	"doc",
+	"id"
+);
@@ -0,0 +1,8 @@
+-- Add migration script here
+ALTER TABLE "sc__users" ADD COLUMN "role" TEXT NOT NULL DEFAULT 'editor';
+ALTER TABLE "sc__users" ADD COLUMN "doc_roles" TEXT NOT NULL DEFAULT '{}';
+
+-- sqlite stores booleans as 0 and 1, so admins are the ones with 1
+UPDATE "sc__users" SET "role" = 'admin' WHERE "admin" = 1;
+
+ALTER TABLE "sc__users" DROP COLUMN "admin";
@@ -0,0 +1,8 @@
+-- Add migration script here
+CREATE TABLE "sc__migrations" (
+	"doc"	TEXT NOT NULL,
+	"version"	INTEGER NOT NULL,
+	"name"	TEXT NOT NULL,
+	"ran_at"	TEXT NOT NULL,
+	PRIMARY KEY("doc", "version")
+);
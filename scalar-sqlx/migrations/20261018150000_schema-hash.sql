@@ -0,0 +1,2 @@
+-- Add migration script here
+ALTER TABLE "sc__meta" ADD COLUMN "schema_hash" TEXT;
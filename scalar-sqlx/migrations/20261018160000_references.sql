@@ -0,0 +1,24 @@
+-- Add migration script here
+CREATE TABLE "sc__references" (
+    "from_doc" TEXT NOT NULL,
+    "from_id" TEXT NOT NULL,
+    "to_doc" TEXT NOT NULL,
+    "to_id" TEXT NOT NULL,
+    "published" BOOL NOT NULL,
+    PRIMARY KEY ("from_doc", "from_id", "published", "to_doc", "to_id")
+);
+CREATE INDEX "sc__references_to" ON "sc__references" ("to_doc", "to_id");
+
+-- find the references that are already stored, once
+INSERT OR IGNORE INTO "sc__references"
+SELECT "sc__drafts"."doc", "sc__drafts"."id", json_extract(tree.value, '$.__sc_doc'), json_extract(tree.value, '$.__sc_ref'), FALSE
+FROM "sc__drafts", json_tree("sc__drafts"."inner") AS tree
+WHERE tree.type = 'object'
+    AND json_type(tree.value, '$.__sc_doc') = 'text'
+    AND json_type(tree.value, '$.__sc_ref') = 'text';
+INSERT OR IGNORE INTO "sc__references"
+SELECT "sc__published"."doc", "sc__published"."id", json_extract(tree.value, '$.__sc_doc'), json_extract(tree.value, '$.__sc_ref'), TRUE
+FROM "sc__published", json_tree("sc__published"."inner") AS tree
+WHERE tree.type = 'object'
+    AND json_type(tree.value, '$.__sc_doc') = 'text'
+    AND json_type(tree.value, '$.__sc_ref') = 'text';
@@ -7,11 +7,14 @@ use rusty_paseto::{
     prelude::*,
 };
 use scalar_cms::{
-    DatabaseConnection, DateTime, Document, Item, Utc,
+    DatabaseConnection, DateTime, Document, IncomingReference, Item, Revision, Utc,
     db::{Authenticated, AuthenticationError, Credentials, DatabaseFactory, User},
     expr::Expression,
+    migrations::MigrationError,
+    query::{ListQuery, Page},
     validations::Valid,
 };
+use serde::{Serialize, de::DeserializeOwned};
 use sqlx::{Database, Pool};
 use thiserror::Error;
 
@@ -32,6 +35,38 @@ pub(crate) trait DatabaseInner {
         data: serde_json::Value,
     ) -> impl Future<Output = Result<Item<serde_json::Value>, sqlx::Error>> + Send;
 
+    fn publish<D: Document + Serialize + Send>(
+        &self,
+        id: &str,
+        publish_at: Option<DateTime<Utc>>,
+        data: D,
+        user: &User,
+    ) -> impl Future<Output = Result<Item<D>, Error>> + Send;
+
+    fn delete_draft<D: Document>(
+        &self,
+        id: &str,
+    ) -> impl Future<Output = Result<Item<serde_json::Value>, sqlx::Error>> + Send;
+
+    /// Unpublishes a document, returning what was published if anything was.
+    fn unpublish<D: Document>(
+        &self,
+        id: &str,
+    ) -> impl Future<Output = Result<Option<serde_json::Value>, sqlx::Error>> + Send;
+
+    fn delete<D: Document>(
+        &self,
+        id: &str,
+    ) -> impl Future<Output = Result<Option<Item<serde_json::Value>>, sqlx::Error>> + Send;
+
+    /// Counts the published documents other than `excl_id`, that match the expression if there is one.
+    fn count_published<D: Document>(
+        &self,
+        excl_id: &str,
+        field_name: &str,
+        expression: Option<Expression>,
+    ) -> impl Future<Output = Result<i64, sqlx::Error>> + Send;
+
     fn get_all<D: Document>(
         &self,
     ) -> impl Future<Output = Result<Vec<Item<serde_json::Value>>, sqlx::Error>> + Send;
@@ -40,6 +75,42 @@ pub(crate) trait DatabaseInner {
         &self,
         id: &str,
     ) -> impl Future<Output = Result<Option<Item<serde_json::Value>>, sqlx::Error>> + Send;
+
+    fn get_published<D: Document>(
+        &self,
+        id: &str,
+    ) -> impl Future<Output = Result<Option<Item<serde_json::Value>>, sqlx::Error>> + Send;
+
+    fn get_all_published<D: Document>(
+        &self,
+    ) -> impl Future<Output = Result<Vec<Item<serde_json::Value>>, sqlx::Error>> + Send;
+
+    fn list<D: Document>(
+        &self,
+        query: ListQuery,
+    ) -> impl Future<Output = Result<Page<Item<serde_json::Value>>, sqlx::Error>> + Send;
+
+    fn get_stale<D: Document>(
+        &self,
+    ) -> impl Future<Output = Result<Vec<Item<serde_json::Value>>, sqlx::Error>> + Send;
+
+    fn get_incoming_references<D: Document>(
+        &self,
+        id: &str,
+    ) -> impl Future<Output = Result<Vec<IncomingReference>, sqlx::Error>> + Send;
+
+    fn get_revisions<D: Document>(
+        &self,
+        id: &str,
+    ) -> impl Future<Output = Result<Vec<Revision<serde_json::Value>>, sqlx::Error>> + Send;
+
+    fn get_revision<D: Document>(
+        &self,
+        id: &str,
+        revision_id: &str,
+    ) -> impl Future<Output = Result<Option<Revision<serde_json::Value>>, sqlx::Error>> + Send;
+
+    fn run_migrations<D: Document>(&self) -> impl Future<Output = Result<Vec<u32>, Error>> + Send;
 }
 
 #[derive(Debug)]
@@ -114,6 +185,10 @@ pub enum Error {
     Database(#[from] sqlx::Error),
     #[error("password error: {0}")]
     Password(#[from] password_hash::Error),
+    #[error("couldn't convert to or from json: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("couldn't run migrations: {0}")]
+    Migration(#[from] MigrationError),
 }
 
 impl<DB: Database> DatabaseConnection for Connection<DB>
@@ -184,7 +259,7 @@ where
                 .picture()
                 .and_then(|l| l.get(None).map(|u| u.as_str()))
                 .unwrap_or_default(),
-            true,
+            scalar_cms::db::Role::Admin,
         );
 
         let token = PasetoBuilder::<_, Local>::default()
@@ -209,80 +284,34 @@ where
         conn: &Authenticated<Self>,
         id: &str,
     ) -> Result<Item<serde_json::Value>, Self::Error> {
-        todo!();
-        // #[derive(Serialize)]
-        // struct Bindings<'a> {
-        //     doc: Cow<'a, str>,
-        //     id: Cow<'a, str>,
-        // }
-
-        // //TODO: VERY BAD!!!!
-        // let pre_delete = self.get_by_id::<D>(id).await?.unwrap();
-
-        // let _ = self
-        //     .query("LET $draft_id = type::thing(string::concat($doc, '_draft'), $id)")
-        //     .query("LET $meta_id = type::thing(string::concat($doc, '_meta'), $id)")
-        //     .query("DELETE $draft_id")
-        //     .query("DELETE $meta_id WHERE published IS NONE")
-        //     .bind(Bindings {
-        //         doc: D::identifier().into(),
-        //         id: id.to_owned().into(),
-        //     })
-        //     .await?;
-
-        // Ok(pre_delete)
+        Ok(conn.inner().inner.delete_draft::<D>(id).await?)
     }
 
-    async fn publish<D: Document + Send + 'static>(
+    #[tracing::instrument(level = "debug", err, skip(data))]
+    async fn publish<D: Document + Send + Sync + Serialize + DeserializeOwned + 'static>(
         conn: &Authenticated<Self>,
         id: &str,
         publish_at: Option<DateTime<Utc>>,
         data: Valid<D>,
     ) -> Result<Item<D>, Self::Error> {
-        todo!()
-        // #[derive(Serialize)]
-        // struct Bindings<'a, D> {
-        //     doc: Cow<'a, str>,
-        //     id: Cow<'a, str>,
-        //     publish_at: Option<DateTime<Utc>>,
-        //     inner: D,
-        // }
-
-        // let mut result = self
-        //     .query("LET $published_id = type::thing($doc, $id)")
-        //     .query("LET $draft_id = type::thing(string::concat($doc, '_draft'), $id)")
-        //     .query("LET $meta_id = type::thing(string::concat($doc, '_meta'), $id)")
-        //     .query("UPSERT $published_id SET inner = $inner, published_at = $published_at")
-        //     .query("UPSERT $meta_id SET published = $published_id, modified_at = time::now(), draft = NONE")
-        //     .query("DELETE $draft_id")
-        //     .query(
-        //         "SELECT
-        //         id,
-        //         created_at,
-        //         modified_at,
-        //         IF draft IS NOT NONE THEN draft.inner ELSE published.inner END AS inner,
-        //         published.published_at AS published_at
-        //     FROM $meta_id
-        //     FETCH draft, published",
-        //     ).bind(Bindings::<D> {
-        //         doc: D::identifier().into(),
-        //         id: id.to_owned().into(),
-        //         publish_at,
-        //         inner: data.inner()
-        //     }).await?;
-
-        // let thingy: Option<SurrealItem<D>> = result.take(6).expect("this should always succeed");
-
-        // Ok(thingy
-        //     .expect("this option should always return something")
-        //     .into())
+        conn.inner()
+            .inner
+            .publish(id, publish_at, data.inner(), &conn.me())
+            .await
     }
 
-    async fn unpublish<D: Document + Send + 'static>(
+    #[tracing::instrument(level = "debug", err)]
+    async fn unpublish<D: Document + Send + DeserializeOwned + 'static>(
         conn: &Authenticated<Self>,
         id: &str,
     ) -> Result<Option<D>, Self::Error> {
-        todo!()
+        Ok(conn
+            .inner()
+            .inner
+            .unpublish::<D>(id)
+            .await?
+            .map(serde_json::from_value)
+            .transpose()?)
     }
 
     #[tracing::instrument(level = "debug", err)]
@@ -306,7 +335,7 @@ where
         conn: &Authenticated<Self>,
         id: &str,
     ) -> Result<Option<Item<serde_json::Value>>, Self::Error> {
-        todo!()
+        Ok(conn.inner().inner.delete::<D>(id).await?)
     }
 
     #[tracing::instrument(level = "debug", err)]
@@ -324,25 +353,140 @@ where
         Ok(self.inner.get_by_id::<D>(id).await?)
     }
 
+    #[tracing::instrument(level = "debug", err)]
+    async fn get_published<D: Document + DeserializeOwned + Send>(
+        &self,
+        id: &str,
+    ) -> Result<Option<Item<D>>, Self::Error> {
+        self.inner
+            .get_published::<D>(id)
+            .await?
+            .map(deserialize_item)
+            .transpose()
+    }
+
+    #[tracing::instrument(level = "debug", err)]
+    async fn get_all_published<D: Document + DeserializeOwned + Send>(
+        &self,
+    ) -> Result<Vec<Item<D>>, Self::Error> {
+        self.inner
+            .get_all_published::<D>()
+            .await?
+            .into_iter()
+            .map(deserialize_item)
+            .collect()
+    }
+
+    #[tracing::instrument(level = "debug", err)]
+    async fn get_incoming_references<D: Document + Send>(
+        &self,
+        id: &str,
+    ) -> Result<Vec<IncomingReference>, Self::Error> {
+        Ok(self.inner.get_incoming_references::<D>(id).await?)
+    }
+
+    #[tracing::instrument(level = "debug", err)]
+    async fn list<D: Document + Send>(
+        &self,
+        query: ListQuery,
+    ) -> Result<Page<Item<serde_json::Value>>, Self::Error> {
+        Ok(self.inner.list::<D>(query).await?)
+    }
+
+    #[tracing::instrument(level = "debug", err)]
+    async fn get_revisions<D: Document + Send>(
+        &self,
+        id: &str,
+    ) -> Result<Vec<Revision<serde_json::Value>>, Self::Error> {
+        Ok(self.inner.get_revisions::<D>(id).await?)
+    }
+
+    #[tracing::instrument(level = "debug", err)]
+    async fn get_revision<D: Document + Send>(
+        &self,
+        id: &str,
+        revision_id: &str,
+    ) -> Result<Option<Revision<serde_json::Value>>, Self::Error> {
+        Ok(self.inner.get_revision::<D>(id, revision_id).await?)
+    }
+
+    #[tracing::instrument(level = "debug", err)]
+    async fn restore_revision<D: Document + Send>(
+        conn: &Authenticated<Self>,
+        id: &str,
+        revision_id: &str,
+    ) -> Result<Option<Item<serde_json::Value>>, Self::Error> {
+        let Some(revision) = conn.inner().get_revision::<D>(id, revision_id).await? else {
+            return Ok(None);
+        };
+
+        Self::draft::<D>(conn, id, revision.inner).await.map(Some)
+    }
+
+    #[tracing::instrument(level = "debug", err)]
+    async fn get_stale<D: Document + Send>(
+        &self,
+    ) -> Result<Vec<Item<serde_json::Value>>, Self::Error> {
+        Ok(self.inner.get_stale::<D>().await?)
+    }
+
+    #[tracing::instrument(level = "info", err)]
+    async fn run_migrations<D: Document + Send>(&self) -> Result<Vec<u32>, Self::Error> {
+        self.inner.run_migrations::<D>().await
+    }
+
     async fn vctx_all<D: Document>(
         &self,
         excl_id: &str,
+        field_name: &str,
         expression: Expression,
     ) -> Result<bool, Self::Error> {
-        todo!()
+        let matching = self
+            .inner
+            .count_published::<D>(excl_id, field_name, Some(expression))
+            .await?;
+        let total = self
+            .inner
+            .count_published::<D>(excl_id, field_name, None)
+            .await?;
+
+        Ok(matching == total)
     }
     async fn vctx_none<D: Document>(
         &self,
         excl_id: &str,
+        field_name: &str,
         expression: Expression,
     ) -> Result<bool, Self::Error> {
-        todo!()
+        let matching = self
+            .inner
+            .count_published::<D>(excl_id, field_name, Some(expression))
+            .await?;
+
+        Ok(matching == 0)
     }
     async fn vctx_any<D: Document>(
         &self,
         excl_id: &str,
+        field_name: &str,
         expression: Expression,
     ) -> Result<bool, Self::Error> {
-        todo!()
+        let matching = self
+            .inner
+            .count_published::<D>(excl_id, field_name, Some(expression))
+            .await?;
+
+        Ok(matching > 0)
     }
 }
+
+fn deserialize_item<D: DeserializeOwned>(item: Item<serde_json::Value>) -> Result<Item<D>, Error> {
+    Ok(Item {
+        id: item.id,
+        created_at: item.created_at,
+        modified_at: item.modified_at,
+        published_at: item.published_at,
+        schema_hash: item.schema_hash,
+        inner: serde_json::from_value(item.inner)?,
+    })
+}
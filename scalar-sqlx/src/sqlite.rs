@@ -1,7 +1,18 @@
-use scalar_cms::{DateTime, Document, Item, Utc, db::User};
-use sqlx::{SqlitePool, query, query_as};
+use scalar_cms::{
+    DateTime, Document, IncomingReference, Item, Revision, Utc,
+    db::User,
+    expr::{Expression, Value},
+    migrations, nanoid,
+    query::{Cursor, ListQuery, Page, SortDirection, SortField, SortKey},
+    types::find_references,
+};
+use serde::Serialize;
+use sqlx::{
+    QueryBuilder, Row, Sqlite, SqliteConnection, SqlitePool, query, query_as, query_scalar,
+    types::Json,
+};
 
-use crate::DatabaseInner;
+use crate::{DatabaseInner, Error};
 pub type Pool = SqlitePool;
 
 /// Migrates the database.
@@ -27,18 +38,20 @@ impl DatabaseInner for SqlitePool {
 
     async fn get_user(&self, email: &str) -> Result<scalar_cms::db::User, sqlx::Error> {
         let data = query!(
-            "SELECT email, name, admin from sc__users WHERE email = $1",
+            "SELECT email, name, role, doc_roles from sc__users WHERE email = $1",
             email
         )
         .fetch_one(self)
         .await?;
 
-        Ok(User::new(
-            data.email,
-            data.name,
-            String::default(),
-            data.admin,
-        ))
+        let role = data
+            .role
+            .parse()
+            .map_err(|e| sqlx::Error::Decode(Box::new(e)))?;
+        let doc_roles =
+            serde_json::from_str(&data.doc_roles).map_err(|e| sqlx::Error::Decode(Box::new(e)))?;
+
+        Ok(User::new(data.email, data.name, String::default(), role).with_doc_roles(doc_roles))
     }
 
     async fn draft<D: Document>(
@@ -46,22 +59,24 @@ impl DatabaseInner for SqlitePool {
         id: &str,
         data: serde_json::Value,
     ) -> Result<Item<serde_json::Value>, sqlx::Error> {
-        let mut transcation = self.begin().await?;
+        let mut transaction = self.begin().await?;
         let now = Utc::now();
+        let schema_hash = D::schema_hash();
 
         let published_at = query!(
-            r#"INSERT INTO sc__meta(doc, id, created_at, modified_at)
-            VALUES($1, $2, $3, $3)
+            r#"INSERT INTO sc__meta(doc, id, created_at, modified_at, schema_hash)
+            VALUES($1, $2, $3, $3, $4)
             ON CONFLICT(id)
             DO
                UPDATE
-               SET modified_at = $3
+               SET modified_at = $3, schema_hash = $4
             RETURNING published_at as 'published_at: DateTime<Utc>'"#,
             D::IDENTIFIER,
             id,
-            now
+            now,
+            schema_hash
         )
-        .fetch_one(&mut *transcation)
+        .fetch_one(&mut *transaction)
         .await?
         .published_at;
 
@@ -76,20 +91,270 @@ impl DatabaseInner for SqlitePool {
             id,
             data
         )
-        .execute(&mut *transcation)
+        .execute(&mut *transaction)
         .await?;
+        set_references::<D>(&mut transaction, id, false, &data).await?;
 
-        transcation.commit().await?;
+        transaction.commit().await?;
 
         Ok(Item {
             id: id.into(),
             created_at: now,
             modified_at: now,
             published_at,
+            schema_hash: Some(schema_hash),
+            inner: data,
+        })
+    }
+
+    async fn publish<D: Document + Serialize + Send>(
+        &self,
+        id: &str,
+        publish_at: Option<DateTime<Utc>>,
+        data: D,
+        user: &User,
+    ) -> Result<Item<D>, Error> {
+        let mut transaction = self.begin().await?;
+        let now = Utc::now();
+        let published_at = publish_at.unwrap_or(now);
+        let inner = serde_json::to_value(&data)?;
+        let created_by = serde_json::to_value(user)?;
+        let schema_hash = D::schema_hash();
+
+        let created_at = query!(
+            r#"INSERT INTO sc__meta(doc, id, created_at, modified_at, published_at, schema_hash)
+            VALUES($1, $2, $3, $3, $4, $5)
+            ON CONFLICT(id)
+            DO
+               UPDATE
+               SET modified_at = $3, published_at = $4, schema_hash = $5
+            RETURNING created_at as 'created_at: DateTime<Utc>'"#,
+            D::IDENTIFIER,
+            id,
+            now,
+            published_at,
+            schema_hash
+        )
+        .fetch_one(&mut *transaction)
+        .await?
+        .created_at;
+
+        query!(
+            r#"INSERT INTO sc__published(doc, id, inner)
+            VALUES($1, $2, $3)
+            ON CONFLICT(id)
+            DO
+               UPDATE
+               SET inner = $3"#,
+            D::IDENTIFIER,
+            id,
+            inner
+        )
+        .execute(&mut *transaction)
+        .await?;
+
+        query!(
+            "DELETE FROM sc__drafts WHERE doc = $1 AND id = $2",
+            D::IDENTIFIER,
+            id
+        )
+        .execute(&mut *transaction)
+        .await?;
+        query!(
+            "DELETE FROM sc__references WHERE from_doc = $1 AND from_id = $2 AND published = FALSE",
+            D::IDENTIFIER,
+            id
+        )
+        .execute(&mut *transaction)
+        .await?;
+        set_references::<D>(&mut transaction, id, true, &inner).await?;
+
+        let revision_id = nanoid!();
+        query!(
+            r#"INSERT INTO sc__revisions(revision_id, id, doc, created_at, created_by, inner)
+            VALUES($1, $2, $3, $4, $5, $6)"#,
+            revision_id,
+            id,
+            D::IDENTIFIER,
+            now,
+            created_by,
+            inner
+        )
+        .execute(&mut *transaction)
+        .await?;
+
+        transaction.commit().await?;
+
+        Ok(Item {
+            id: id.into(),
+            created_at,
+            modified_at: now,
+            published_at: Some(published_at),
+            schema_hash: Some(schema_hash),
             inner: data,
         })
     }
 
+    async fn delete_draft<D: Document>(
+        &self,
+        id: &str,
+    ) -> Result<Item<serde_json::Value>, sqlx::Error> {
+        let mut transaction = self.begin().await?;
+        let pre_delete = item_by_id::<D, _>(&mut *transaction, id)
+            .await?
+            .ok_or(sqlx::Error::RowNotFound)?;
+
+        query!(
+            "DELETE FROM sc__drafts WHERE doc = $1 AND id = $2",
+            D::IDENTIFIER,
+            id
+        )
+        .execute(&mut *transaction)
+        .await?;
+        query!(
+            "DELETE FROM sc__references WHERE from_doc = $1 AND from_id = $2 AND published = FALSE",
+            D::IDENTIFIER,
+            id
+        )
+        .execute(&mut *transaction)
+        .await?;
+        // without a draft or a published version, there's nothing left
+        query!(
+            "DELETE FROM sc__meta WHERE doc = $1 AND id = $2 AND published_at IS NULL",
+            D::IDENTIFIER,
+            id
+        )
+        .execute(&mut *transaction)
+        .await?;
+
+        transaction.commit().await?;
+
+        Ok(pre_delete)
+    }
+
+    async fn unpublish<D: Document>(
+        &self,
+        id: &str,
+    ) -> Result<Option<serde_json::Value>, sqlx::Error> {
+        let mut transaction = self.begin().await?;
+        let now = Utc::now();
+
+        let Some(published) = query_scalar!(
+            r#"DELETE FROM sc__published WHERE doc = $1 AND id = $2
+            RETURNING inner as 'inner!: serde_json::Value'"#,
+            D::IDENTIFIER,
+            id
+        )
+        .fetch_optional(&mut *transaction)
+        .await?
+        else {
+            return Ok(None);
+        };
+
+        // the published version becomes the draft, like it was never published
+        query!(
+            r#"INSERT INTO sc__drafts(doc, id, inner)
+            VALUES($1, $2, $3)
+            ON CONFLICT(id)
+            DO
+               UPDATE
+               SET inner = $3"#,
+            D::IDENTIFIER,
+            id,
+            published
+        )
+        .execute(&mut *transaction)
+        .await?;
+        query!(
+            "UPDATE sc__meta SET published_at = NULL, modified_at = $1 WHERE doc = $2 AND id = $3",
+            now,
+            D::IDENTIFIER,
+            id
+        )
+        .execute(&mut *transaction)
+        .await?;
+        query!(
+            "DELETE FROM sc__references WHERE from_doc = $1 AND from_id = $2 AND published = FALSE",
+            D::IDENTIFIER,
+            id
+        )
+        .execute(&mut *transaction)
+        .await?;
+        query!(
+            "UPDATE sc__references SET published = FALSE WHERE from_doc = $1 AND from_id = $2",
+            D::IDENTIFIER,
+            id
+        )
+        .execute(&mut *transaction)
+        .await?;
+
+        transaction.commit().await?;
+
+        Ok(Some(published))
+    }
+
+    async fn delete<D: Document>(
+        &self,
+        id: &str,
+    ) -> Result<Option<Item<serde_json::Value>>, sqlx::Error> {
+        let mut transaction = self.begin().await?;
+        let Some(pre_delete) = item_by_id::<D, _>(&mut *transaction, id).await? else {
+            return Ok(None);
+        };
+
+        query!(
+            "DELETE FROM sc__drafts WHERE doc = $1 AND id = $2",
+            D::IDENTIFIER,
+            id
+        )
+        .execute(&mut *transaction)
+        .await?;
+        query!(
+            "DELETE FROM sc__published WHERE doc = $1 AND id = $2",
+            D::IDENTIFIER,
+            id
+        )
+        .execute(&mut *transaction)
+        .await?;
+        query!(
+            "DELETE FROM sc__meta WHERE doc = $1 AND id = $2",
+            D::IDENTIFIER,
+            id
+        )
+        .execute(&mut *transaction)
+        .await?;
+        query!(
+            "DELETE FROM sc__references WHERE from_doc = $1 AND from_id = $2",
+            D::IDENTIFIER,
+            id
+        )
+        .execute(&mut *transaction)
+        .await?;
+
+        transaction.commit().await?;
+
+        Ok(Some(pre_delete))
+    }
+
+    async fn count_published<D: Document>(
+        &self,
+        excl_id: &str,
+        field_name: &str,
+        expression: Option<Expression>,
+    ) -> Result<i64, sqlx::Error> {
+        let mut builder =
+            QueryBuilder::<Sqlite>::new("SELECT COUNT(*) FROM sc__published WHERE doc = ");
+        builder.push_bind(D::IDENTIFIER);
+        builder.push(" AND id != ");
+        builder.push_bind(excl_id.to_owned());
+        if let Some(expression) = expression {
+            builder.push(" AND ");
+            push_expression(&mut builder, field_name, expression);
+        }
+
+        builder.build_query_scalar().fetch_one(self).await
+    }
+
     async fn get_all<D: Document>(&self) -> Result<Vec<Item<serde_json::Value>>, sqlx::Error> {
         type InnerItem = Item<serde_json::Value>;
         query_as!(
@@ -99,6 +364,7 @@ impl DatabaseInner for SqlitePool {
                 sc__meta.created_at as 'created_at!: DateTime<Utc>',
                 sc__meta.modified_at as 'modified_at!: DateTime<Utc>',
                 sc__meta.published_at as 'published_at: DateTime<Utc>',
+                sc__meta.schema_hash,
                 (
                     CASE WHEN sc__drafts.inner IS NULL
                         THEN sc__published.inner
@@ -119,8 +385,16 @@ impl DatabaseInner for SqlitePool {
     async fn get_by_id<D: Document>(
         &self,
         id: &str,
+    ) -> Result<Option<Item<serde_json::Value>>, sqlx::Error> {
+        item_by_id::<D, _>(self, id).await
+    }
+
+    async fn get_published<D: Document>(
+        &self,
+        id: &str,
     ) -> Result<Option<Item<serde_json::Value>>, sqlx::Error> {
         type InnerItem = Item<serde_json::Value>;
+        let now = Utc::now();
         query_as!(
             InnerItem,
             r#"SELECT
@@ -128,6 +402,56 @@ impl DatabaseInner for SqlitePool {
                 sc__meta.created_at as 'created_at!: DateTime<Utc>',
                 sc__meta.modified_at as 'modified_at!: DateTime<Utc>',
                 sc__meta.published_at as 'published_at: DateTime<Utc>',
+                sc__meta.schema_hash,
+                sc__published.inner as 'inner!: serde_json::Value'
+                FROM sc__meta
+                INNER JOIN sc__published ON sc__meta.id = sc__published.id
+                WHERE sc__meta.doc = $1 AND sc__meta.id = $2 AND sc__meta.published_at <= $3
+            "#,
+            D::IDENTIFIER,
+            id,
+            now
+        )
+        .fetch_optional(self)
+        .await
+    }
+
+    async fn get_all_published<D: Document>(
+        &self,
+    ) -> Result<Vec<Item<serde_json::Value>>, sqlx::Error> {
+        type InnerItem = Item<serde_json::Value>;
+        let now = Utc::now();
+        query_as!(
+            InnerItem,
+            r#"SELECT
+                sc__meta.id as 'id!',
+                sc__meta.created_at as 'created_at!: DateTime<Utc>',
+                sc__meta.modified_at as 'modified_at!: DateTime<Utc>',
+                sc__meta.published_at as 'published_at: DateTime<Utc>',
+                sc__meta.schema_hash,
+                sc__published.inner as 'inner!: serde_json::Value'
+                FROM sc__meta
+                INNER JOIN sc__published ON sc__meta.id = sc__published.id
+                WHERE sc__meta.doc = $1 AND sc__meta.published_at <= $2
+            "#,
+            D::IDENTIFIER,
+            now
+        )
+        .fetch_all(self)
+        .await
+    }
+
+    async fn get_stale<D: Document>(&self) -> Result<Vec<Item<serde_json::Value>>, sqlx::Error> {
+        type InnerItem = Item<serde_json::Value>;
+        let schema_hash = D::schema_hash();
+        query_as!(
+            InnerItem,
+            r#"SELECT
+                sc__meta.id as 'id!',
+                sc__meta.created_at as 'created_at!: DateTime<Utc>',
+                sc__meta.modified_at as 'modified_at!: DateTime<Utc>',
+                sc__meta.published_at as 'published_at: DateTime<Utc>',
+                sc__meta.schema_hash,
                 (
                     CASE WHEN sc__drafts.inner IS NULL
                         THEN sc__published.inner
@@ -137,12 +461,490 @@ impl DatabaseInner for SqlitePool {
                 FROM sc__meta
                 FULL OUTER JOIN sc__drafts ON sc__meta.id = sc__drafts.id
                 FULL OUTER JOIN sc__published ON sc__meta.id = sc__published.id
-                WHERE sc__meta.doc = $1 AND sc__meta.id = $2
+                WHERE sc__meta.doc = $1 AND sc__meta.schema_hash IS NOT $2
+            "#,
+            D::IDENTIFIER,
+            schema_hash
+        )
+        .fetch_all(self)
+        .await
+    }
+
+    async fn get_incoming_references<D: Document>(
+        &self,
+        id: &str,
+    ) -> Result<Vec<IncomingReference>, sqlx::Error> {
+        let rows = query!(
+            r#"SELECT
+                from_doc as 'doc!',
+                from_id as 'id!',
+                published as 'published!: bool'
+                FROM sc__references
+                WHERE to_doc = $1 AND to_id = $2 AND NOT (from_doc = $1 AND from_id = $2)
+            "#,
+            D::IDENTIFIER,
+            id
+        )
+        .fetch_all(self)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| IncomingReference {
+                doc: row.doc,
+                id: row.id,
+                published: row.published,
+            })
+            .collect())
+    }
+
+    async fn get_revisions<D: Document>(
+        &self,
+        id: &str,
+    ) -> Result<Vec<Revision<serde_json::Value>>, sqlx::Error> {
+        let rows = query!(
+            r#"SELECT
+                revision_id as 'revision_id!',
+                id as 'id!',
+                created_at as 'created_at!: DateTime<Utc>',
+                created_by as 'created_by!: Json<User>',
+                inner as 'inner!: serde_json::Value'
+                FROM sc__revisions
+                WHERE doc = $1 AND id = $2
+                ORDER BY created_at DESC
             "#,
             D::IDENTIFIER,
             id
         )
+        .fetch_all(self)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| Revision {
+                revision_id: row.revision_id,
+                id: row.id,
+                created_at: row.created_at,
+                created_by: row.created_by.0,
+                inner: row.inner,
+            })
+            .collect())
+    }
+
+    async fn get_revision<D: Document>(
+        &self,
+        id: &str,
+        revision_id: &str,
+    ) -> Result<Option<Revision<serde_json::Value>>, sqlx::Error> {
+        let row = query!(
+            r#"SELECT
+                revision_id as 'revision_id!',
+                id as 'id!',
+                created_at as 'created_at!: DateTime<Utc>',
+                created_by as 'created_by!: Json<User>',
+                inner as 'inner!: serde_json::Value'
+                FROM sc__revisions
+                WHERE doc = $1 AND id = $2 AND revision_id = $3
+            "#,
+            D::IDENTIFIER,
+            id,
+            revision_id
+        )
         .fetch_optional(self)
-        .await
+        .await?;
+
+        Ok(row.map(|row| Revision {
+            revision_id: row.revision_id,
+            id: row.id,
+            created_at: row.created_at,
+            created_by: row.created_by.0,
+            inner: row.inner,
+        }))
+    }
+
+    async fn run_migrations<D: Document>(&self) -> Result<Vec<u32>, Error> {
+        let mut transaction = self.begin().await?;
+
+        let ran = query_scalar!(
+            "SELECT version FROM sc__migrations WHERE doc = $1",
+            D::IDENTIFIER
+        )
+        .fetch_all(&mut *transaction)
+        .await?
+        .into_iter()
+        .filter_map(|version| u32::try_from(version).ok())
+        .collect::<Vec<_>>();
+
+        let pending = migrations::pending::<D>(&ran)?;
+        if pending.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let drafts = query!(
+            r#"SELECT id as 'id!', inner as 'inner!: serde_json::Value' FROM sc__drafts WHERE doc = $1"#,
+            D::IDENTIFIER
+        )
+        .fetch_all(&mut *transaction)
+        .await?;
+        for mut row in drafts {
+            migrations::apply(&pending, &mut row.inner);
+            query!(
+                "UPDATE sc__drafts SET inner = $1 WHERE id = $2",
+                row.inner,
+                row.id
+            )
+            .execute(&mut *transaction)
+            .await?;
+            set_references::<D>(&mut transaction, &row.id, false, &row.inner).await?;
+        }
+
+        let published = query!(
+            r#"SELECT id as 'id!', inner as 'inner!: serde_json::Value' FROM sc__published WHERE doc = $1"#,
+            D::IDENTIFIER
+        )
+        .fetch_all(&mut *transaction)
+        .await?;
+        for mut row in published {
+            migrations::apply(&pending, &mut row.inner);
+            query!(
+                "UPDATE sc__published SET inner = $1 WHERE id = $2",
+                row.inner,
+                row.id
+            )
+            .execute(&mut *transaction)
+            .await?;
+            set_references::<D>(&mut transaction, &row.id, true, &row.inner).await?;
+        }
+
+        let revisions = query!(
+            r#"SELECT revision_id as 'revision_id!', inner as 'inner!: serde_json::Value' FROM sc__revisions WHERE doc = $1"#,
+            D::IDENTIFIER
+        )
+        .fetch_all(&mut *transaction)
+        .await?;
+        for mut row in revisions {
+            migrations::apply(&pending, &mut row.inner);
+            query!(
+                "UPDATE sc__revisions SET inner = $1 WHERE revision_id = $2",
+                row.inner,
+                row.revision_id
+            )
+            .execute(&mut *transaction)
+            .await?;
+        }
+
+        // the content is in the current shape now
+        let schema_hash = D::schema_hash();
+        query!(
+            "UPDATE sc__meta SET schema_hash = $1 WHERE doc = $2",
+            schema_hash,
+            D::IDENTIFIER
+        )
+        .execute(&mut *transaction)
+        .await?;
+
+        let now = Utc::now();
+        for migration in &pending {
+            query!(
+                "INSERT INTO sc__migrations(doc, version, name, ran_at) VALUES($1, $2, $3, $4)",
+                D::IDENTIFIER,
+                migration.version,
+                migration.name,
+                now
+            )
+            .execute(&mut *transaction)
+            .await?;
+        }
+
+        transaction.commit().await?;
+
+        Ok(pending.iter().map(|migration| migration.version).collect())
+    }
+
+    async fn list<D: Document>(
+        &self,
+        query: ListQuery,
+    ) -> Result<Page<Item<serde_json::Value>>, sqlx::Error> {
+        let keys = query.sort_keys();
+        let limit = query.page_size();
+        let mut builder = QueryBuilder::<Sqlite>::new(
+            "SELECT * FROM (
+                SELECT
+                    sc__meta.id as id,
+                    sc__meta.created_at as created_at,
+                    sc__meta.modified_at as modified_at,
+                    sc__meta.published_at as published_at,
+                    sc__meta.schema_hash as schema_hash,
+                    COALESCE(sc__drafts.inner, sc__published.inner) as inner
+                FROM sc__meta
+                LEFT JOIN sc__drafts ON sc__meta.id = sc__drafts.id
+                LEFT JOIN sc__published ON sc__meta.id = sc__published.id
+                WHERE sc__meta.doc = ",
+        );
+        builder.push_bind(D::IDENTIFIER);
+        builder.push(") WHERE 1 = 1");
+
+        if let Some(filter) = query.filter {
+            builder.push(" AND ");
+            push_expression(&mut builder, "", filter);
+        }
+        if let Some(cursor) = query.cursor {
+            builder.push(" AND ");
+            push_cursor(&mut builder, &keys, cursor);
+        }
+
+        builder.push(" ORDER BY ");
+        for (i, key) in keys.iter().enumerate() {
+            if i > 0 {
+                builder.push(", ");
+            }
+            push_sort_field(&mut builder, &key.field);
+            builder.push(match key.direction {
+                SortDirection::Ascending => " ASC NULLS FIRST",
+                SortDirection::Descending => " DESC NULLS LAST",
+            });
+        }
+
+        // grab one extra item to know if there's another page
+        builder.push(" LIMIT ");
+        builder.push_bind(i64::try_from(limit.saturating_add(1)).unwrap_or(i64::MAX));
+
+        let mut items = builder
+            .build()
+            .fetch_all(self)
+            .await?
+            .into_iter()
+            .map(|row| {
+                Ok(Item {
+                    id: row.try_get("id")?,
+                    created_at: row.try_get("created_at")?,
+                    modified_at: row.try_get("modified_at")?,
+                    published_at: row.try_get("published_at")?,
+                    schema_hash: row.try_get("schema_hash")?,
+                    inner: row.try_get("inner")?,
+                })
+            })
+            .collect::<Result<Vec<_>, sqlx::Error>>()?;
+
+        let next_cursor = if items.len() > limit {
+            items.truncate(limit);
+            items.last().map(|item| Cursor::after(item, &keys).encode())
+        } else {
+            None
+        };
+
+        Ok(Page { items, next_cursor })
+    }
+}
+
+/// Replaces the references going out of a document's draft or published version,
+/// so incoming references can be looked up without going through every document.
+async fn set_references<D: Document>(
+    conn: &mut SqliteConnection,
+    id: &str,
+    published: bool,
+    inner: &serde_json::Value,
+) -> Result<(), sqlx::Error> {
+    query!(
+        "DELETE FROM sc__references WHERE from_doc = $1 AND from_id = $2 AND published = $3",
+        D::IDENTIFIER,
+        id,
+        published
+    )
+    .execute(&mut *conn)
+    .await?;
+    for reference in find_references(inner) {
+        query!(
+            r#"INSERT OR IGNORE INTO sc__references(from_doc, from_id, to_doc, to_id, published)
+            VALUES($1, $2, $3, $4, $5)"#,
+            D::IDENTIFIER,
+            id,
+            reference.doc,
+            reference.id,
+            published
+        )
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Gets an item by id, so it can be read inside a transaction too.
+async fn item_by_id<'e, D: Document, E: sqlx::Executor<'e, Database = Sqlite>>(
+    executor: E,
+    id: &str,
+) -> Result<Option<Item<serde_json::Value>>, sqlx::Error> {
+    type InnerItem = Item<serde_json::Value>;
+    query_as!(
+        InnerItem,
+        r#"SELECT
+            sc__meta.id as 'id!',
+            sc__meta.created_at as 'created_at!: DateTime<Utc>',
+            sc__meta.modified_at as 'modified_at!: DateTime<Utc>',
+            sc__meta.published_at as 'published_at: DateTime<Utc>',
+            sc__meta.schema_hash,
+            (
+                CASE WHEN sc__drafts.inner IS NULL
+                    THEN sc__published.inner
+                    ELSE sc__drafts.inner
+                END
+            ) as 'inner!: serde_json::Value'
+            FROM sc__meta
+            FULL OUTER JOIN sc__drafts ON sc__meta.id = sc__drafts.id
+            FULL OUTER JOIN sc__published ON sc__meta.id = sc__published.id
+            WHERE sc__meta.doc = $1 AND sc__meta.id = $2
+        "#,
+        D::IDENTIFIER,
+        id
+    )
+    .fetch_optional(executor)
+    .await
+}
+
+/// Pushes an expression, where `$current` is the field called `field_name`.
+fn push_expression(
+    builder: &mut QueryBuilder<'_, Sqlite>,
+    field_name: &str,
+    expression: Expression,
+) {
+    let (lhs, operator, rhs) = match expression {
+        Expression::Equals { lhs, rhs } => (lhs, " = ", rhs),
+        Expression::NotEquals { lhs, rhs } => (lhs, " != ", rhs),
+        Expression::GreaterThan { lhs, rhs } => (lhs, " > ", rhs),
+        Expression::GreaterThanOrEquals { lhs, rhs } => (lhs, " >= ", rhs),
+        Expression::LessThan { lhs, rhs } => (lhs, " < ", rhs),
+        Expression::LessThanOrEquals { lhs, rhs } => (lhs, " <= ", rhs),
+        Expression::And { lhs, rhs } => {
+            builder.push("(");
+            push_expression(builder, field_name, *lhs);
+            builder.push(" AND ");
+            push_expression(builder, field_name, *rhs);
+            builder.push(")");
+            return;
+        }
+        Expression::Or { lhs, rhs } => {
+            builder.push("(");
+            push_expression(builder, field_name, *lhs);
+            builder.push(" OR ");
+            push_expression(builder, field_name, *rhs);
+            builder.push(")");
+            return;
+        }
+        // ListQuery::check rejects these, and validation expressions only use the ones above
+        _ => unreachable!("unsupported expression"),
+    };
+
+    push_value(builder, field_name, lhs);
+    builder.push(operator);
+    push_value(builder, field_name, rhs);
+}
+
+fn push_value(builder: &mut QueryBuilder<'_, Sqlite>, field_name: &str, value: Value) {
+    match value {
+        Value::CurrentField => push_field(builder, field_name),
+        Value::Ident(ident) => push_field(builder, &ident),
+        Value::Value(value) => push_json(builder, value),
+    }
+}
+
+fn push_field(builder: &mut QueryBuilder<'_, Sqlite>, field: &str) {
+    builder.push("json_extract(inner, ");
+    builder.push_bind(format!("$.\"{}\"", field.replace('"', "\\\"")));
+    builder.push(")");
+}
+
+fn push_sort_field(builder: &mut QueryBuilder<'_, Sqlite>, field: &SortField) {
+    match field {
+        SortField::Id => builder.push("id"),
+        SortField::CreatedAt => builder.push("created_at"),
+        SortField::ModifiedAt => builder.push("modified_at"),
+        SortField::PublishedAt => builder.push("published_at"),
+        SortField::Field(field) => {
+            push_field(builder, field);
+            builder
+        }
+    };
+}
+
+/// Binds a json value the same way `json_extract` would return it.
+fn push_json(builder: &mut QueryBuilder<'_, Sqlite>, value: serde_json::Value) {
+    match value {
+        serde_json::Value::Null => builder.push("NULL"),
+        serde_json::Value::Bool(value) => builder.push_bind(value),
+        serde_json::Value::Number(number) => match number.as_i64() {
+            Some(number) => builder.push_bind(number),
+            None => builder.push_bind(number.as_f64()),
+        },
+        serde_json::Value::String(value) => builder.push_bind(value),
+        value @ (serde_json::Value::Array(_) | serde_json::Value::Object(_)) => {
+            builder.push_bind(value.to_string())
+        }
+    };
+}
+
+/// Pushes a condition that only matches items after the cursor. Nulls are compared explicitly, since
+/// comparing anything to `NULL` is never true.
+fn push_cursor(builder: &mut QueryBuilder<'_, Sqlite>, keys: &[SortKey], cursor: Cursor) {
+    let bounds = keys.iter().zip(cursor.0).collect::<Vec<_>>();
+
+    builder.push("(");
+    for i in 0..bounds.len() {
+        if i > 0 {
+            builder.push(" OR ");
+        }
+        builder.push("(");
+        for (key, value) in &bounds[..i] {
+            push_sort_field(builder, &key.field);
+            if value.is_null() {
+                builder.push(" IS NULL");
+            } else {
+                builder.push(" = ");
+                push_bound(builder, &key.field, value.clone());
+            }
+            builder.push(" AND ");
+        }
+        let (key, value) = &bounds[i];
+        match (key.direction, value.is_null()) {
+            // nulls come first, so everything else is after them
+            (SortDirection::Ascending, true) => {
+                push_sort_field(builder, &key.field);
+                builder.push(" IS NOT NULL");
+            }
+            // nulls come last, so nothing is after them
+            (SortDirection::Descending, true) => {
+                builder.push("0");
+            }
+            (SortDirection::Ascending, false) => {
+                push_sort_field(builder, &key.field);
+                builder.push(" > ");
+                push_bound(builder, &key.field, value.clone());
+            }
+            (SortDirection::Descending, false) => {
+                builder.push("(");
+                push_sort_field(builder, &key.field);
+                builder.push(" < ");
+                push_bound(builder, &key.field, value.clone());
+                builder.push(" OR ");
+                push_sort_field(builder, &key.field);
+                builder.push(" IS NULL)");
+            }
+        }
+        builder.push(")");
+    }
+    builder.push(")");
+}
+
+fn push_bound(builder: &mut QueryBuilder<'_, Sqlite>, field: &SortField, value: serde_json::Value) {
+    match field {
+        // dates have to be bound as dates so they get encoded like the stored ones
+        SortField::CreatedAt | SortField::ModifiedAt | SortField::PublishedAt => {
+            builder.push_bind(
+                serde_json::from_value::<Option<DateTime<Utc>>>(value)
+                    .ok()
+                    .flatten(),
+            );
+        }
+        SortField::Id | SortField::Field(_) => push_json(builder, value),
     }
 }
@@ -0,0 +1,393 @@
+#![cfg(feature = "sqlite")]
+
+use argon2::{
+    Argon2, PasswordHasher,
+    password_hash::{SaltString, rand_core::OsRng},
+};
+use scalar_cms::{
+    DatabaseConnection, Document, EditorField, IncomingReference,
+    db::{Authenticated, DatabaseFactory, ValidationContext},
+    migrations::{Migration, MigrationError},
+    query::{Cursor, ListQuery, SortKey},
+    types::Reference,
+    validations::Valid,
+};
+use scalar_sqlx::{
+    Connection, ConnectionFactory,
+    sqlite::{self, Pool},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::{Sqlite, sqlite::SqlitePoolOptions};
+
+const PASSWORD: &str = "hunter2";
+
+const POST_MIGRATIONS: &[Migration] = &[Migration::new(1, "rename headline to title", |v| {
+    if let Some(headline) = v.as_object_mut().and_then(|o| o.remove("headline")) {
+        v["title"] = headline;
+    }
+})];
+
+#[derive(Document, Debug, Serialize, Deserialize)]
+#[document(identifier = "author")]
+struct Author {
+    #[validate(skip)]
+    name: String,
+}
+
+#[derive(Document, Debug, Serialize, Deserialize)]
+#[document(identifier = "post", migrations = POST_MIGRATIONS)]
+struct Post {
+    #[validate(skip)]
+    title: String,
+    #[validate(skip)]
+    rank: Option<i32>,
+    #[validate(skip)]
+    author: Option<Reference<Author>>,
+}
+
+/// [`Post`], after a migration was wrongly added before one that already ran.
+#[derive(Document, Debug, Serialize, Deserialize)]
+#[document(identifier = "post", migrations = LATE_MIGRATIONS)]
+struct LatePost {
+    #[validate(skip)]
+    title: String,
+}
+
+const LATE_MIGRATIONS: &[Migration] = &[Migration::new(0, "too late", |_| {}), POST_MIGRATIONS[0]];
+
+#[derive(Document, Debug, Serialize, Deserialize)]
+#[document(identifier = "shuffled", migrations = SHUFFLED_MIGRATIONS)]
+struct Shuffled {
+    #[validate(skip)]
+    title: String,
+}
+
+const SHUFFLED_MIGRATIONS: &[Migration] = &[
+    Migration::new(2, "second", |_| {}),
+    Migration::new(1, "first", |_| {}),
+];
+
+/// A fresh in-memory database, signed in as an admin.
+async fn connect() -> (Pool, Authenticated<Connection<Sqlite>>) {
+    // every connection to :memory: is a different database, so stick to one
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+    sqlite::migrate(&pool).await.unwrap();
+
+    let password_hash = Argon2::default()
+        .hash_password(PASSWORD.as_bytes(), &SaltString::generate(&mut OsRng))
+        .unwrap()
+        .to_string();
+    sqlx::query(
+        "INSERT INTO sc__users(email, name, password_hash, role) VALUES('admin@example.com', 'Admin', $1, 'admin')",
+    )
+    .bind(password_hash)
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let conn = ConnectionFactory::try_new_random(pool.clone())
+        .unwrap()
+        .init()
+        .await
+        .unwrap();
+    let credentials =
+        serde_json::from_value(json!({ "email": "admin@example.com", "password": PASSWORD }))
+            .unwrap();
+    let token = conn.signin(credentials).await.unwrap();
+
+    (
+        pool,
+        Authenticated::authenticate(conn, &token).await.unwrap(),
+    )
+}
+
+async fn publish(conn: &Authenticated<Connection<Sqlite>>, id: &str, post: Post) {
+    let valid = Valid::new(post, ValidationContext::new(conn.inner(), id))
+        .await
+        .unwrap();
+    Connection::publish(conn, id, None, valid).await.unwrap();
+}
+
+/// Lists every id, following cursors a page at a time.
+async fn list_ids(conn: &Connection<Sqlite>, sort: &str) -> Vec<String> {
+    let sort = SortKey::parse_list::<Post>(sort).unwrap();
+    let mut ids = Vec::new();
+    let mut cursor = None;
+    loop {
+        let query = ListQuery {
+            sort: sort.clone(),
+            cursor,
+            limit: Some(2),
+            ..ListQuery::default()
+        };
+        query.check::<Post>().unwrap();
+        let page = conn.list::<Post>(query).await.unwrap();
+        assert!(page.items.len() <= 2);
+        ids.extend(page.items.into_iter().map(|item| item.id));
+
+        match page.next_cursor {
+            Some(next) => cursor = Some(Cursor::decode(&next).unwrap()),
+            None => break ids,
+        }
+    }
+}
+
+#[tokio::test]
+async fn pages_through_null_sort_values() {
+    let (_, conn) = connect().await;
+    for (id, rank) in [
+        ("a", Some(2)),
+        ("b", None),
+        ("c", Some(1)),
+        ("d", None),
+        ("e", Some(3)),
+    ] {
+        Connection::draft::<Post>(
+            &conn,
+            id,
+            json!({ "title": id, "rank": rank, "author": null }),
+        )
+        .await
+        .unwrap();
+    }
+
+    // nulls come first when ascending and last when descending, with the id breaking ties
+    assert_eq!(
+        list_ids(conn.inner(), "rank").await,
+        ["b", "d", "c", "a", "e"]
+    );
+    assert_eq!(
+        list_ids(conn.inner(), "-rank").await,
+        ["e", "a", "c", "b", "d"]
+    );
+    assert_eq!(
+        list_ids(conn.inner(), "-__sc_id").await,
+        ["e", "d", "c", "b", "a"]
+    );
+}
+
+#[tokio::test]
+async fn publishing_keeps_revisions_and_unpublishing_restores_the_draft() {
+    let (_, conn) = connect().await;
+    for title in ["first", "second"] {
+        let post = Post {
+            title: title.into(),
+            rank: None,
+            author: None,
+        };
+        publish(&conn, "post", post).await;
+    }
+
+    let revisions = conn.inner().get_revisions::<Post>("post").await.unwrap();
+    assert_eq!(
+        revisions
+            .iter()
+            .map(|revision| &revision.inner["title"])
+            .collect::<Vec<_>>(),
+        ["second", "first"]
+    );
+    assert_eq!(revisions[0].created_by.name(), "Admin");
+
+    let unpublished = Connection::unpublish::<Post>(&conn, "post").await.unwrap();
+    assert_eq!(unpublished.unwrap().title, "second");
+    assert!(
+        conn.inner()
+            .get_published::<Post>("post")
+            .await
+            .unwrap()
+            .is_none()
+    );
+    let draft = conn
+        .inner()
+        .get_by_id::<Post>("post")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(draft.inner["title"], "second");
+    assert_eq!(draft.published_at, None);
+
+    Connection::delete::<Post>(&conn, "post").await.unwrap();
+    assert!(
+        conn.inner()
+            .get_by_id::<Post>("post")
+            .await
+            .unwrap()
+            .is_none()
+    );
+}
+
+#[tokio::test]
+async fn finds_references_that_would_block_deleting() {
+    let (_, conn) = connect().await;
+    Connection::draft::<Author>(&conn, "jane", json!({ "name": "Jane" }))
+        .await
+        .unwrap();
+    let post = Post {
+        title: "hello".into(),
+        rank: None,
+        author: Some(Reference::new("jane")),
+    };
+    publish(&conn, "post", post).await;
+    Connection::draft::<Post>(
+        &conn,
+        "post",
+        json!({ "title": "hello", "rank": null, "author": { "__sc_doc": "author", "__sc_ref": "jane" } }),
+    )
+    .await
+    .unwrap();
+
+    let mut references = conn
+        .inner()
+        .get_incoming_references::<Author>("jane")
+        .await
+        .unwrap();
+    references.sort_by_key(|reference| reference.published);
+    assert_eq!(
+        references,
+        [
+            IncomingReference {
+                doc: "post".into(),
+                id: "post".into(),
+                published: false,
+            },
+            IncomingReference {
+                doc: "post".into(),
+                id: "post".into(),
+                published: true,
+            },
+        ]
+    );
+
+    // the published version replaces the draft, so its references are only in a draft now
+    Connection::unpublish::<Post>(&conn, "post").await.unwrap();
+    assert_eq!(
+        conn.inner()
+            .get_incoming_references::<Author>("jane")
+            .await
+            .unwrap(),
+        [IncomingReference {
+            doc: "post".into(),
+            id: "post".into(),
+            published: false,
+        }]
+    );
+
+    Connection::delete::<Post>(&conn, "post").await.unwrap();
+    assert!(
+        conn.inner()
+            .get_incoming_references::<Author>("jane")
+            .await
+            .unwrap()
+            .is_empty()
+    );
+}
+
+#[tokio::test]
+async fn runs_document_migrations_once() {
+    let (pool, conn) = connect().await;
+    Connection::draft::<Post>(
+        &conn,
+        "post",
+        json!({ "headline": "hello", "rank": null, "author": null }),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(conn.inner().run_migrations::<Post>().await.unwrap(), [1]);
+    let draft = conn
+        .inner()
+        .get_by_id::<Post>("post")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        draft.inner,
+        json!({ "title": "hello", "rank": null, "author": null })
+    );
+
+    assert!(
+        conn.inner()
+            .run_migrations::<Post>()
+            .await
+            .unwrap()
+            .is_empty()
+    );
+    let ran: Vec<(i64, String)> = sqlx::query_as("SELECT version, name FROM sc__migrations")
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+    assert_eq!(ran, [(1, "rename headline to title".to_owned())]);
+}
+
+#[tokio::test]
+async fn keeps_admins_when_moving_to_roles() {
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+    let mut migrator = sqlx::migrate!();
+    let migrations = migrator.migrations.clone();
+
+    // only the users table, from before there were roles
+    migrator.migrations = migrations[..1].to_vec().into();
+    migrator.run(&pool).await.unwrap();
+    sqlx::query(
+        "INSERT INTO sc__users(email, name, password_hash, admin) VALUES('admin@example.com', 'Admin', '', TRUE), ('editor@example.com', 'Editor', '', FALSE)",
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    migrator.migrations = migrations;
+    migrator.run(&pool).await.unwrap();
+    let roles: Vec<(String, String)> =
+        sqlx::query_as("SELECT email, role FROM sc__users ORDER BY email")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+    assert_eq!(
+        roles,
+        [
+            ("admin@example.com".to_owned(), "admin".to_owned()),
+            ("editor@example.com".to_owned(), "editor".to_owned()),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn refuses_migrations_that_could_run_differently() {
+    let (pool, conn) = connect().await;
+
+    let error = conn.inner().run_migrations::<Shuffled>().await.unwrap_err();
+    assert!(matches!(
+        error,
+        scalar_sqlx::Error::Migration(MigrationError::OutOfOrder {
+            before: 2,
+            after: 1,
+            ..
+        })
+    ));
+
+    conn.inner().run_migrations::<Post>().await.unwrap();
+    let error = conn.inner().run_migrations::<LatePost>().await.unwrap_err();
+    assert!(matches!(
+        error,
+        scalar_sqlx::Error::Migration(MigrationError::BeforeRan {
+            version: 0,
+            latest: 1,
+            ..
+        })
+    ));
+
+    let ran: Vec<(String, i64)> = sqlx::query_as("SELECT doc, version FROM sc__migrations")
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+    assert_eq!(ran, [("post".to_owned(), 1)]);
+}
@@ -8,8 +8,11 @@ use openidconnect::{
 use scalar_cms::{
     db::{Authenticated, AuthenticationError, Credentials, DatabaseFactory, User},
     expr::Expression,
+    migrations,
+    query::{Cursor, ListQuery, Page, SortDirection, SortField, SortKey},
+    types::find_references,
     validations::Valid,
-    DateTime, Document, Item, Utc,
+    DateTime, Document, IncomingReference, Item, Revision, Utc,
 };
 use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize};
 use serde_json::Value;
@@ -28,6 +31,8 @@ pub struct MetaTable {
     pub id: Thing,
     pub created_at: DateTime<Utc>,
     pub modified_at: DateTime<Utc>,
+    #[serde(default)]
+    pub schema_hash: Option<String>,
     pub draft: Option<Thing>,
     pub published: Option<Thing>,
 }
@@ -86,6 +91,8 @@ pub struct SurrealItem<D> {
     pub created_at: DateTime<Utc>,
     pub modified_at: DateTime<Utc>,
     pub published_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub schema_hash: Option<String>,
     pub inner: D,
 }
 
@@ -96,6 +103,7 @@ impl<D> From<SurrealItem<D>> for Item<D> {
             created_at: item.created_at,
             modified_at: item.modified_at,
             published_at: item.published_at,
+            schema_hash: item.schema_hash,
             inner: item.inner,
         }
     }
@@ -108,11 +116,98 @@ impl<D: Debug> From<Item<D>> for SurrealItem<D> {
             created_at: value.created_at,
             modified_at: value.modified_at,
             published_at: value.published_at,
+            schema_hash: value.schema_hash,
             inner: value.inner,
         }
     }
 }
 
+#[derive(Deserialize, Debug)]
+pub struct SurrealRevision<D> {
+    #[serde(deserialize_with = "thing_to_string")]
+    pub id: String,
+    pub doc_id: String,
+    pub created_at: DateTime<Utc>,
+    pub created_by: User,
+    pub inner: D,
+}
+
+impl<D> From<SurrealRevision<D>> for Revision<D> {
+    fn from(revision: SurrealRevision<D>) -> Self {
+        Self {
+            revision_id: revision.id,
+            id: revision.doc_id,
+            created_at: revision.created_at,
+            created_by: revision.created_by,
+            inner: revision.inner,
+        }
+    }
+}
+
+/// A reference going out of a document, stored in `sc__reference` so incoming references can be looked up.
+#[derive(Serialize, Debug)]
+struct OutgoingReference {
+    to_doc: String,
+    to_id: String,
+}
+
+impl OutgoingReference {
+    fn find_all(value: &Value) -> Vec<Self> {
+        find_references(value)
+            .into_iter()
+            .map(|reference| Self {
+                to_doc: reference.doc.to_owned(),
+                to_id: reference.id.to_owned(),
+            })
+            .collect()
+    }
+}
+
+/// A stored document, as it's read for migrations.
+#[derive(Deserialize)]
+struct Row {
+    id: Value,
+    inner: Value,
+}
+
+/// A migrated [`Row`]. It's only written if it hasn't changed since it was read, anything saved in the
+/// meantime was saved by the current code and is already in the current shape.
+#[derive(Serialize)]
+struct MigratedRow {
+    id: Value,
+    original: Value,
+    inner: Value,
+    references: Vec<OutgoingReference>,
+}
+
+impl From<Row> for MigratedRow {
+    fn from(row: Row) -> Self {
+        Self {
+            id: row.id,
+            original: row.inner.clone(),
+            inner: row.inner,
+            references: Vec::new(),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct SurrealReference {
+    from_doc: String,
+    from_id: String,
+    published: bool,
+}
+
+impl From<SurrealReference> for IncomingReference {
+    fn from(reference: SurrealReference) -> Self {
+        Self {
+            doc: reference.from_doc,
+            id: reference.from_id,
+            published: reference.published,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct SurrealStore<C: Connection> {
     namespace: String,
@@ -276,6 +371,8 @@ impl<C: Connection + Debug> scalar_cms::DatabaseConnection for SurrealConnection
             doc: Cow<'a, str>,
             id: Cow<'a, str>,
             inner: serde_json::Value,
+            references: Vec<OutgoingReference>,
+            schema_hash: String,
         }
 
         let mut result = conn
@@ -284,22 +381,27 @@ impl<C: Connection + Debug> scalar_cms::DatabaseConnection for SurrealConnection
             .query("LET $draft_id = type::thing(string::concat($doc, '_draft'), $id)")
             .query("LET $meta_id = type::thing(string::concat($doc, '_meta'), $id)")
             .query("UPSERT $draft_id SET inner = $inner")
-            .query("UPSERT $meta_id SET draft = $draft_id, modified_at = time::now()")
+            .query("UPSERT $meta_id SET draft = $draft_id, modified_at = time::now(), schema_hash = $schema_hash")
             .query(
                 "SELECT
                 id,
                 created_at,
                 modified_at,
+                schema_hash,
                 IF draft IS NOT NONE THEN draft.inner ELSE published.inner END AS inner,
                 published.published_at AS published_at
             FROM $meta_id
             FETCH draft, published",
             )
+            .query("DELETE sc__reference WHERE from_doc = $doc AND from_id = $id AND published = false")
+            .query("FOR $reference IN $references { CREATE sc__reference SET from_doc = $doc, from_id = $id, to_doc = $reference.to_doc, to_id = $reference.to_id, published = false }")
             .query("COMMIT")
             .bind(Bindings {
                 doc: D::IDENTIFIER.into(),
                 id: id.to_owned().into(),
+                references: OutgoingReference::find_all(&data),
                 inner: data,
+                schema_hash: D::schema_hash(),
             })
             .await?;
 
@@ -332,6 +434,7 @@ impl<C: Connection + Debug> scalar_cms::DatabaseConnection for SurrealConnection
             .query("LET $meta_id = type::thing(string::concat($doc, '_meta'), $id)")
             .query("DELETE $draft_id")
             .query("DELETE $meta_id WHERE published IS NONE")
+            .query("DELETE sc__reference WHERE from_doc = $doc AND from_id = $id AND published = false")
             .query("COMMIT")
             .bind(Bindings {
                 doc: D::IDENTIFIER.into(),
@@ -355,9 +458,13 @@ impl<C: Connection + Debug> scalar_cms::DatabaseConnection for SurrealConnection
             id: Cow<'a, str>,
             publish_at: Option<DateTime<Utc>>,
             inner: serde_json::Value,
+            references: Vec<OutgoingReference>,
+            user: User,
+            schema_hash: String,
         }
 
         let data = data.inner();
+        let inner = serde_json::to_value(&data).expect("whuh");
 
         let mut result = conn.inner()
             .query("BEGIN")
@@ -365,29 +472,36 @@ impl<C: Connection + Debug> scalar_cms::DatabaseConnection for SurrealConnection
             .query("LET $draft_id = type::thing(string::concat($doc, '_draft'), $id)")
             .query("LET $meta_id = type::thing(string::concat($doc, '_meta'), $id)")
             .query("UPSERT $published_id SET inner = $inner, published_at = IF $publish_at IS NOT NONE { <datetime>$publish_at } ELSE { NONE }")
-            .query("UPSERT $meta_id SET published = $published_id, modified_at = time::now(), draft = NONE")
+            .query("UPSERT $meta_id SET published = $published_id, modified_at = time::now(), draft = NONE, schema_hash = $schema_hash")
+            .query("CREATE type::table(string::concat($doc, '_revision')) SET doc_id = $id, created_by = $user, inner = $inner")
             .query("DELETE $draft_id")
             .query(
                 "SELECT
                 id,
                 created_at,
                 modified_at,
+                schema_hash,
                 IF draft IS NOT NONE THEN draft.inner ELSE published.inner END AS inner,
                 published.published_at AS published_at
             FROM $meta_id
             FETCH draft, published",
             )
+            .query("DELETE sc__reference WHERE from_doc = $doc AND from_id = $id")
+            .query("FOR $reference IN $references { CREATE sc__reference SET from_doc = $doc, from_id = $id, to_doc = $reference.to_doc, to_id = $reference.to_id, published = true }")
             .query("COMMIT")
             .bind(Bindings {
                 doc: D::IDENTIFIER.into(),
                 id: id.to_owned().into(),
                 publish_at,
-                inner: serde_json::to_value(&data).expect("whuh")
+                references: OutgoingReference::find_all(&inner),
+                inner,
+                user: conn.me(),
+                schema_hash: D::schema_hash(),
             }).await?;
 
         println!("{:?}", surrealdb::value::to_value(data));
 
-        let thingy: Option<SurrealItem<D>> = result.take(6).expect("this should always succeed");
+        let thingy: Option<SurrealItem<D>> = result.take(7).expect("this should always succeed");
 
         Ok(thingy
             .expect("this option should always return something")
@@ -413,6 +527,9 @@ impl<C: Connection + Debug> scalar_cms::DatabaseConnection for SurrealConnection
             .query("UPSERT $draft_id SET inner = (SELECT VALUE inner FROM ONLY $published_id)")
             .query("UPDATE $meta_id SET draft = $draft_id, published = NONE, modified_at = time::now()")
             .query("DELETE $published_id RETURN BEFORE")
+            // the published version is now the draft, so it's references are too
+            .query("DELETE sc__reference WHERE from_doc = $doc AND from_id = $id AND published = false")
+            .query("UPDATE sc__reference SET published = false WHERE from_doc = $doc AND from_id = $id")
             .query("COMMIT")
             .bind(Bindings {
                 doc: D::IDENTIFIER.into(),
@@ -452,6 +569,7 @@ impl<C: Connection + Debug> scalar_cms::DatabaseConnection for SurrealConnection
             .query("DELETE $meta_id RETURN BEFORE")
             .query("DELETE $published_id RETURN BEFORE")
             .query("DELETE $draft_id RETURN BEFORE")
+            .query("DELETE sc__reference WHERE from_doc = $doc AND from_id = $id")
             .query("COMMIT")
             .bind(("doc", D::IDENTIFIER))
             .bind(("id", id.to_owned()))
@@ -466,12 +584,14 @@ impl<C: Connection + Debug> scalar_cms::DatabaseConnection for SurrealConnection
                  id,
                  created_at,
                  modified_at,
+                 schema_hash,
                  ..
              }| Item {
                 id: id.id.to_raw(),
                 created_at,
                 modified_at,
                 published_at: published.as_ref().map(|p| p.published_at),
+                schema_hash,
                 inner: draft.map_or(published.map_or(Value::Null, |p| p.inner), |d| d.inner),
             },
         ))
@@ -487,6 +607,7 @@ impl<C: Connection + Debug> scalar_cms::DatabaseConnection for SurrealConnection
                 id,
                 created_at,
                 modified_at,
+                schema_hash,
                 IF draft IS NOT NONE THEN draft.inner ELSE published.inner END AS inner,
                 published.published_at AS published_at
             FROM type::table(string::concat($doc, '_meta'))
@@ -517,6 +638,7 @@ impl<C: Connection + Debug> scalar_cms::DatabaseConnection for SurrealConnection
                 id,
                 created_at,
                 modified_at,
+                schema_hash,
                 IF draft IS NOT NONE THEN draft.inner ELSE published.inner END AS inner,
                 published.published_at AS published_at
             FROM $meta_id
@@ -531,6 +653,346 @@ impl<C: Connection + Debug> scalar_cms::DatabaseConnection for SurrealConnection
             .map(Into::into))
     }
 
+    #[tracing::instrument(level = "debug", err)]
+    async fn get_published<D: Document + DeserializeOwned + Send>(
+        &self,
+        id: &str,
+    ) -> Result<Option<Item<D>>, Self::Error> {
+        #[derive(Serialize)]
+        struct Bindings<'a> {
+            doc: Cow<'a, str>,
+            id: Cow<'a, str>,
+        }
+
+        Ok(self
+            .query("LET $meta_id = type::thing(string::concat($doc, '_meta'), $id)")
+            .query(
+                "SELECT
+                id,
+                created_at,
+                modified_at,
+                schema_hash,
+                published.inner AS inner,
+                published.published_at AS published_at
+            FROM $meta_id
+            WHERE published IS NOT NONE AND published.published_at <= time::now()
+            FETCH published",
+            )
+            .bind(Bindings {
+                doc: D::IDENTIFIER.into(),
+                id: id.to_owned().into(),
+            })
+            .await?
+            .take::<Option<SurrealItem<D>>>(1)?
+            .map(Into::into))
+    }
+
+    #[tracing::instrument(level = "debug", err)]
+    async fn get_all_published<D: Document + DeserializeOwned + Send>(
+        &self,
+    ) -> Result<Vec<Item<D>>, Self::Error> {
+        let result = self
+            .query(
+                "SELECT
+                id,
+                created_at,
+                modified_at,
+                schema_hash,
+                published.inner AS inner,
+                published.published_at AS published_at
+            FROM type::table(string::concat($doc, '_meta'))
+            WHERE published IS NOT NONE AND published.published_at <= time::now()
+            FETCH published",
+            )
+            .bind(("doc", D::IDENTIFIER))
+            .await?
+            .take::<Vec<SurrealItem<D>>>(0)?;
+
+        Ok(result.into_iter().map(Into::into).collect())
+    }
+
+    #[tracing::instrument(level = "debug", err)]
+    async fn get_stale<D: Document + Send>(
+        &self,
+    ) -> Result<Vec<Item<serde_json::Value>>, Self::Error> {
+        let result = self
+            .query(
+                "SELECT
+                id,
+                created_at,
+                modified_at,
+                schema_hash,
+                IF draft IS NOT NONE THEN draft.inner ELSE published.inner END AS inner,
+                published.published_at AS published_at
+            FROM type::table(string::concat($doc, '_meta'))
+            WHERE schema_hash != $schema_hash
+            FETCH draft, published",
+            )
+            .bind(("doc", D::IDENTIFIER))
+            .bind(("schema_hash", D::schema_hash()))
+            .await?
+            .take::<Vec<SurrealItem<serde_json::Value>>>(0)?;
+
+        Ok(result.into_iter().map(Into::into).collect())
+    }
+
+    #[tracing::instrument(level = "debug", err)]
+    async fn get_incoming_references<D: Document + Send>(
+        &self,
+        id: &str,
+    ) -> Result<Vec<IncomingReference>, Self::Error> {
+        let result = self
+            .query(
+                "SELECT from_doc, from_id, published
+                FROM sc__reference
+                WHERE to_doc = $doc AND to_id = $id AND !(from_doc = $doc AND from_id = $id)
+                GROUP BY from_doc, from_id, published",
+            )
+            .bind(("doc", D::IDENTIFIER))
+            .bind(("id", id.to_owned()))
+            .await?
+            .take::<Vec<SurrealReference>>(0)?;
+
+        Ok(result.into_iter().map(Into::into).collect())
+    }
+
+    #[tracing::instrument(level = "debug", err)]
+    async fn list<D: Document + Send>(
+        &self,
+        query: ListQuery,
+    ) -> Result<Page<Item<serde_json::Value>>, Self::Error> {
+        let keys = query.sort_keys();
+        let limit = query.page_size();
+        let mut bindings = Vec::new();
+        let mut conditions = Vec::new();
+
+        if let Some(filter) = query.filter {
+            conditions.push(compile_expression_into(&mut bindings, "", filter));
+        }
+        if let Some(cursor) = query.cursor {
+            conditions.push(compile_cursor(&mut bindings, &keys, cursor));
+        }
+
+        let where_clause = if conditions.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", conditions.join(" AND "))
+        };
+        let order = keys
+            .iter()
+            .map(|key| {
+                let direction = match key.direction {
+                    SortDirection::Ascending => "ASC",
+                    SortDirection::Descending => "DESC",
+                };
+                format!("{} {direction}", sort_field(&key.field))
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let mut items: Vec<Item<serde_json::Value>> = bindings
+            .into_iter()
+            .fold(
+                self.query(format!(
+                    "SELECT * FROM (
+                        SELECT
+                            id,
+                            created_at,
+                            modified_at,
+                            schema_hash,
+                            IF draft IS NOT NONE THEN draft.inner ELSE published.inner END AS inner,
+                            published.published_at AS published_at
+                        FROM type::table(string::concat($doc, '_meta'))
+                        FETCH draft, published
+                    )
+                    {where_clause}
+                    ORDER BY {order}
+                    LIMIT $limit"
+                ))
+                .bind(("doc", D::IDENTIFIER))
+                // grab one extra item to know if there's another page
+                .bind(("limit", limit.saturating_add(1))),
+                surrealdb::method::Query::bind,
+            )
+            .await?
+            .take::<Vec<SurrealItem<serde_json::Value>>>(0)?
+            .into_iter()
+            .map(Into::into)
+            .collect();
+
+        let next_cursor = if items.len() > limit {
+            items.truncate(limit);
+            items.last().map(|item| Cursor::after(item, &keys).encode())
+        } else {
+            None
+        };
+
+        Ok(Page { items, next_cursor })
+    }
+
+    #[tracing::instrument(level = "debug", err)]
+    async fn get_revisions<D: Document + Send>(
+        &self,
+        id: &str,
+    ) -> Result<Vec<Revision<serde_json::Value>>, Self::Error> {
+        #[derive(Serialize)]
+        struct Bindings<'a> {
+            doc: Cow<'a, str>,
+            id: Cow<'a, str>,
+        }
+
+        let result = self
+            .query(
+                "SELECT * FROM type::table(string::concat($doc, '_revision'))
+                WHERE doc_id = $id
+                ORDER BY created_at DESC",
+            )
+            .bind(Bindings {
+                doc: D::IDENTIFIER.into(),
+                id: id.to_owned().into(),
+            })
+            .await?
+            .take::<Vec<SurrealRevision<serde_json::Value>>>(0)?;
+
+        Ok(result.into_iter().map(Into::into).collect())
+    }
+
+    #[tracing::instrument(level = "debug", err)]
+    async fn get_revision<D: Document + Send>(
+        &self,
+        id: &str,
+        revision_id: &str,
+    ) -> Result<Option<Revision<serde_json::Value>>, Self::Error> {
+        #[derive(Serialize)]
+        struct Bindings<'a> {
+            doc: Cow<'a, str>,
+            id: Cow<'a, str>,
+            revision_id: Cow<'a, str>,
+        }
+
+        Ok(self
+            .query(
+                "SELECT * FROM type::thing(string::concat($doc, '_revision'), $revision_id)
+                WHERE doc_id = $id",
+            )
+            .bind(Bindings {
+                doc: D::IDENTIFIER.into(),
+                id: id.to_owned().into(),
+                revision_id: revision_id.to_owned().into(),
+            })
+            .await?
+            .take::<Option<SurrealRevision<serde_json::Value>>>(0)?
+            .map(Into::into))
+    }
+
+    #[tracing::instrument(level = "debug", err, skip(conn))]
+    async fn restore_revision<D: Document + Send>(
+        conn: &Authenticated<Self>,
+        id: &str,
+        revision_id: &str,
+    ) -> Result<Option<Item<serde_json::Value>>, Self::Error> {
+        let Some(revision) = conn.inner().get_revision::<D>(id, revision_id).await? else {
+            return Ok(None);
+        };
+
+        Self::draft::<D>(conn, id, revision.inner).await.map(Some)
+    }
+
+    #[tracing::instrument(level = "info", err, fields(doc = D::IDENTIFIER))]
+    async fn run_migrations<D: Document + Send>(&self) -> Result<Vec<u32>, Self::Error> {
+        #[derive(Serialize)]
+        struct MigrationRecord {
+            version: u32,
+            name: &'static str,
+        }
+
+        #[derive(Serialize)]
+        struct Bindings<'a> {
+            doc: Cow<'a, str>,
+            published: Vec<MigratedRow>,
+            drafts: Vec<MigratedRow>,
+            revisions: Vec<MigratedRow>,
+            migrations: Vec<MigrationRecord>,
+            schema_hash: String,
+        }
+
+        let ran = self
+            .query("SELECT VALUE version FROM sc__migration WHERE doc = $doc")
+            .bind(("doc", D::IDENTIFIER))
+            .await?
+            .take::<Vec<u32>>(0)?;
+
+        let pending =
+            migrations::pending::<D>(&ran).map_err(|e| Error::Db(Db::Thrown(e.to_string())))?;
+        if pending.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut result = self
+            .query("SELECT record::id(id) AS id, inner FROM type::table($doc)")
+            .query("SELECT record::id(id) AS id, inner FROM type::table(string::concat($doc, '_draft'))")
+            .query("SELECT record::id(id) AS id, inner FROM type::table(string::concat($doc, '_revision'))")
+            .bind(("doc", D::IDENTIFIER))
+            .await?;
+
+        let mut tables = [
+            result.take::<Vec<Row>>(0)?,
+            result.take::<Vec<Row>>(1)?,
+            result.take::<Vec<Row>>(2)?,
+        ]
+        .map(|rows| rows.into_iter().map(MigratedRow::from).collect::<Vec<_>>());
+        for row in tables.iter_mut().flatten() {
+            migrations::apply(&pending, &mut row.inner);
+            row.references = OutgoingReference::find_all(&row.inner);
+        }
+        let [published, drafts, revisions] = tables;
+
+        // rows that changed since they were read would be marked as migrated without being migrated,
+        // so that cancels the whole thing, and running the migrations again picks them up
+        self.query("BEGIN")
+            .query(
+                "FOR $row IN $published {
+                    IF !(UPDATE type::thing($doc, $row.id) SET inner = $row.inner WHERE inner = $row.original) {
+                        THROW 'a published document changed while migrating, so nothing was migrated';
+                    };
+                    UPDATE type::thing(string::concat($doc, '_meta'), $row.id) SET schema_hash = $schema_hash;
+                    DELETE sc__reference WHERE from_doc = $doc AND from_id = $row.id AND published = true;
+                    FOR $reference IN $row.references { CREATE sc__reference SET from_doc = $doc, from_id = $row.id, to_doc = $reference.to_doc, to_id = $reference.to_id, published = true };
+                }",
+            )
+            .query(
+                "FOR $row IN $drafts {
+                    IF !(UPDATE type::thing(string::concat($doc, '_draft'), $row.id) SET inner = $row.inner WHERE inner = $row.original) {
+                        THROW 'a draft changed while migrating, so nothing was migrated';
+                    };
+                    UPDATE type::thing(string::concat($doc, '_meta'), $row.id) SET schema_hash = $schema_hash;
+                    DELETE sc__reference WHERE from_doc = $doc AND from_id = $row.id AND published = false;
+                    FOR $reference IN $row.references { CREATE sc__reference SET from_doc = $doc, from_id = $row.id, to_doc = $reference.to_doc, to_id = $reference.to_id, published = false };
+                }",
+            )
+            .query("FOR $row IN $revisions { UPDATE type::thing(string::concat($doc, '_revision'), $row.id) SET inner = $row.inner WHERE inner = $row.original }")
+            .query("FOR $migration IN $migrations { CREATE type::thing('sc__migration', [$doc, $migration.version]) SET doc = $doc, version = $migration.version, name = $migration.name }")
+            .query("COMMIT")
+            .bind(Bindings {
+                doc: D::IDENTIFIER.into(),
+                published,
+                drafts,
+                revisions,
+                migrations: pending
+                    .iter()
+                    .map(|migration| MigrationRecord {
+                        version: migration.version,
+                        name: migration.name,
+                    })
+                    .collect(),
+                schema_hash: D::schema_hash(),
+            })
+            .await?
+            .check()?;
+
+        Ok(pending.iter().map(|migration| migration.version).collect())
+    }
+
     async fn vctx_all<D: Document>(
         &self,
         excl_id: &str,
@@ -615,34 +1077,40 @@ fn compile_expression(
     expression: Expression,
 ) -> (Vec<(String, serde_json::Value)>, String) {
     let mut bindings = Vec::new();
-    let where_clause = match expression {
-        Expression::Equals { lhs, rhs } => format!(
-            "{} = {}",
-            resolve_value(&mut bindings, field_name, lhs),
-            resolve_value(&mut bindings, field_name, rhs)
-        ),
-        Expression::NotEquals { lhs, rhs } => format!(
-            "{} != {}",
-            resolve_value(&mut bindings, field_name, lhs),
-            resolve_value(&mut bindings, field_name, rhs)
-        ),
+    let where_clause = compile_expression_into(&mut bindings, field_name, expression);
+    (bindings, where_clause)
+}
+
+fn compile_expression_into(
+    bindings: &mut Vec<(String, serde_json::Value)>,
+    field_name: &str,
+    expression: Expression,
+) -> String {
+    let (lhs, operator, rhs) = match expression {
+        Expression::Equals { lhs, rhs } => (lhs, "=", rhs),
+        Expression::NotEquals { lhs, rhs } => (lhs, "!=", rhs),
+        Expression::GreaterThan { lhs, rhs } => (lhs, ">", rhs),
+        Expression::GreaterThanOrEquals { lhs, rhs } => (lhs, ">=", rhs),
+        Expression::LessThan { lhs, rhs } => (lhs, "<", rhs),
+        Expression::LessThanOrEquals { lhs, rhs } => (lhs, "<=", rhs),
         Expression::And { lhs, rhs } => {
-            let (left_bindings, left_inner) = compile_expression(field_name, *lhs);
-            bindings.extend(left_bindings);
-            let (right_bindings, right_inner) = compile_expression(field_name, *rhs);
-            bindings.extend(right_bindings);
-            format!("({left_inner} AND {right_inner})")
+            let left_inner = compile_expression_into(bindings, field_name, *lhs);
+            let right_inner = compile_expression_into(bindings, field_name, *rhs);
+            return format!("({left_inner} AND {right_inner})");
         }
         Expression::Or { lhs, rhs } => {
-            let (left_bindings, left_inner) = compile_expression(field_name, *lhs);
-            bindings.extend(left_bindings);
-            let (right_bindings, right_inner) = compile_expression(field_name, *rhs);
-            bindings.extend(right_bindings);
-            format!("({left_inner} OR {right_inner})")
+            let left_inner = compile_expression_into(bindings, field_name, *lhs);
+            let right_inner = compile_expression_into(bindings, field_name, *rhs);
+            return format!("({left_inner} OR {right_inner})");
         }
-        _ => panic!("missed expr"),
+        // ListQuery::check rejects these, and validation expressions only use the ones above
+        _ => unreachable!("unsupported expression"),
     };
-    (bindings, where_clause)
+    format!(
+        "{} {operator} {}",
+        resolve_value(bindings, field_name, lhs),
+        resolve_value(bindings, field_name, rhs)
+    )
 }
 
 fn resolve_value(
@@ -651,17 +1119,90 @@ fn resolve_value(
     value: scalar_cms::expr::Value,
 ) -> String {
     match value {
-        scalar_cms::expr::Value::CurrentField => format!("inner.{field_name}"),
+        scalar_cms::expr::Value::CurrentField => format!("inner.{}", escape_ident(field_name)),
         // all fields are on the inner object, so we gotta adapt
-        scalar_cms::expr::Value::Ident(ident) => format!("inner.{ident}"),
-        scalar_cms::expr::Value::Value(value) => {
-            let binding_name = format!("b{}", bindings.len());
-            bindings.push((binding_name.clone(), value));
-            format!("${binding_name}")
-        }
+        scalar_cms::expr::Value::Ident(ident) => format!("inner.{}", escape_ident(&ident)),
+        scalar_cms::expr::Value::Value(value) => bind_value(bindings, value),
     }
 }
 
+fn bind_value(bindings: &mut Vec<(String, serde_json::Value)>, value: serde_json::Value) -> String {
+    let binding_name = format!("b{}", bindings.len());
+    bindings.push((binding_name.clone(), value));
+    format!("${binding_name}")
+}
+
+fn escape_ident(ident: &str) -> String {
+    format!("`{}`", ident.replace('`', "\\`"))
+}
+
+fn sort_field(field: &SortField) -> String {
+    match field {
+        SortField::Id => "id".into(),
+        SortField::CreatedAt => "created_at".into(),
+        SortField::ModifiedAt => "modified_at".into(),
+        SortField::PublishedAt => "published_at".into(),
+        SortField::Field(field) => format!("inner.{}", escape_ident(field)),
+    }
+}
+
+/// Compiles a cursor into a condition that only matches items after it. Surreal sorts `NONE` and `NULL`
+/// before everything else, and comparing them with `>` or `<` doesn't mean much, so they're handled explicitly.
+fn compile_cursor(
+    bindings: &mut Vec<(String, serde_json::Value)>,
+    keys: &[SortKey],
+    cursor: Cursor,
+) -> String {
+    let bounds = keys
+        .iter()
+        .zip(cursor.0)
+        .map(|(key, value)| {
+            let bound = (!value.is_null()).then(|| {
+                let binding = bind_value(bindings, value);
+                match key.field {
+                    SortField::Id => {
+                        format!("type::thing(string::concat($doc, '_meta'), {binding})")
+                    }
+                    SortField::CreatedAt | SortField::ModifiedAt | SortField::PublishedAt => {
+                        format!("<datetime>{binding}")
+                    }
+                    SortField::Field(_) => binding,
+                }
+            });
+            (sort_field(&key.field), key.direction, bound)
+        })
+        .collect::<Vec<_>>();
+
+    let alternatives = (0..bounds.len())
+        .map(|i| {
+            let (field, direction, bound) = &bounds[i];
+            let is_null = format!("({field} = NONE OR {field} = NULL)");
+            let after = match (direction, bound) {
+                // nulls come first, so everything else is after them
+                (SortDirection::Ascending, None) => format!("!{is_null}"),
+                // nulls come last, so nothing is after them
+                (SortDirection::Descending, None) => "false".into(),
+                (SortDirection::Ascending, Some(bound)) => format!("{field} > {bound}"),
+                (SortDirection::Descending, Some(bound)) => {
+                    format!("({field} < {bound} OR {is_null})")
+                }
+            };
+            bounds[..i]
+                .iter()
+                .map(|(field, _, bound)| match bound {
+                    Some(bound) => format!("{field} = {bound}"),
+                    None => format!("({field} = NONE OR {field} = NULL)"),
+                })
+                .chain([after])
+                .collect::<Vec<_>>()
+                .join(" AND ")
+        })
+        .map(|alternative| format!("({alternative})"))
+        .collect::<Vec<_>>();
+
+    format!("({})", alternatives.join(" OR "))
+}
+
 impl<C: Connection + Debug> SurrealConnection<C> {
     /// Initializes data for a given doc. Most of the time,
     /// this should be a completely safe operation.
@@ -675,6 +1216,7 @@ impl<C: Connection + Debug> SurrealConnection<C> {
         let published_table = D::IDENTIFIER;
         let draft_table = format!("{published_table}_draft");
         let meta_table = format!("{published_table}_meta");
+        let revision_table = format!("{published_table}_revision");
         self
             // published documents
             .query(format!("DEFINE TABLE OVERWRITE {published_table} SCHEMAFULL PERMISSIONS FOR select WHERE true FOR create, update, delete WHERE $auth.id IS NOT NONE"))
@@ -685,17 +1227,64 @@ impl<C: Connection + Debug> SurrealConnection<C> {
             .query(format!("DEFINE TABLE OVERWRITE {draft_table} SCHEMAFULL PERMISSIONS FOR select, create, update, delete WHERE $auth.id IS NOT NONE"))
             .query(format!("DEFINE FIELD IF NOT EXISTS inner ON {draft_table} FLEXIBLE TYPE object"))
             // meta table
-            .query(format!("DEFINE TABLE OVERWRITE {meta_table} SCHEMAFULL PERMISSIONS FOR select, create, update, delete WHERE $auth.id IS NOT NONE"))
+            .query(format!("DEFINE TABLE OVERWRITE {meta_table} SCHEMAFULL PERMISSIONS FOR select WHERE $auth.id IS NOT NONE OR published IS NOT NONE FOR create, update, delete WHERE $auth.id IS NOT NONE"))
             .query(format!("DEFINE FIELD IF NOT EXISTS created_at ON {meta_table} TYPE datetime DEFAULT time::now()"))
             .query(format!("DEFINE FIELD IF NOT EXISTS modified_at ON {meta_table} TYPE datetime"))
             .query(format!("DEFINE FIELD IF NOT EXISTS draft ON {meta_table} TYPE option<record<{draft_table}>>"))
             .query(format!("DEFINE FIELD IF NOT EXISTS published ON {meta_table} TYPE option<record<{published_table}>>"))
+            .query(format!("DEFINE FIELD IF NOT EXISTS schema_hash ON {meta_table} TYPE option<string>"))
+            // revisions, these are append only
+            .query(format!("DEFINE TABLE OVERWRITE {revision_table} SCHEMAFULL PERMISSIONS FOR select, create WHERE $auth.id IS NOT NONE FOR update, delete NONE"))
+            .query(format!("DEFINE FIELD IF NOT EXISTS doc_id ON {revision_table} TYPE string"))
+            .query(format!("DEFINE FIELD IF NOT EXISTS created_at ON {revision_table} TYPE datetime DEFAULT time::now()"))
+            .query(format!("DEFINE FIELD IF NOT EXISTS created_by ON {revision_table} FLEXIBLE TYPE object"))
+            .query(format!("DEFINE FIELD IF NOT EXISTS inner ON {revision_table} FLEXIBLE TYPE object"))
+            .query(format!("DEFINE INDEX IF NOT EXISTS doc_id ON {revision_table} FIELDS doc_id"))
             .query(format!("DEFINE FUNCTION OVERWRITE fn::{published_table}_public() {{ RETURN (array::map(SELECT inner FROM {published_table} WHERE published_at < time::now(), |$v| $v.inner)) }}"))
             .await
             .unwrap_or_else(|e| panic!("setting up tables for {published_table} failed: {e}"));
         tracing::info!("done");
     }
 
+    /// Initializes the table used to look up references between documents.
+    ///
+    /// # Panics
+    ///
+    /// Panics if initialization fails.
+    pub async fn init_references(&self) {
+        tracing::info!("setting up references..");
+        self
+            .query("DEFINE TABLE OVERWRITE sc__reference SCHEMAFULL PERMISSIONS FOR select, create, update, delete WHERE $auth.id IS NOT NONE")
+            .query("DEFINE FIELD IF NOT EXISTS from_doc ON sc__reference TYPE string")
+            .query("DEFINE FIELD IF NOT EXISTS from_id ON sc__reference TYPE string")
+            .query("DEFINE FIELD IF NOT EXISTS to_doc ON sc__reference TYPE string")
+            .query("DEFINE FIELD IF NOT EXISTS to_id ON sc__reference TYPE string")
+            .query("DEFINE FIELD IF NOT EXISTS published ON sc__reference TYPE bool")
+            .query("DEFINE INDEX IF NOT EXISTS from_ref ON sc__reference FIELDS from_doc, from_id")
+            .query("DEFINE INDEX IF NOT EXISTS to_ref ON sc__reference FIELDS to_doc, to_id")
+            .await
+            .expect("reference setup failed");
+        tracing::info!("done");
+    }
+
+    /// Initializes the table keeping track of which document migrations have been run.
+    ///
+    /// # Panics
+    ///
+    /// Panics if initialization fails.
+    pub async fn init_migrations(&self) {
+        tracing::info!("setting up migrations..");
+        self
+            .query("DEFINE TABLE OVERWRITE sc__migration SCHEMAFULL PERMISSIONS NONE")
+            .query("DEFINE FIELD IF NOT EXISTS doc ON sc__migration TYPE string")
+            .query("DEFINE FIELD IF NOT EXISTS version ON sc__migration TYPE int")
+            .query("DEFINE FIELD IF NOT EXISTS name ON sc__migration TYPE string")
+            .query("DEFINE FIELD IF NOT EXISTS ran_at ON sc__migration TYPE datetime DEFAULT time::now()")
+            .await
+            .expect("migration setup failed");
+        tracing::info!("done");
+    }
+
     /// Initializies auth for this database. This is usually an operation that's safe to autoamtically
     /// run at startup.
     ///
@@ -705,11 +1294,15 @@ impl<C: Connection + Debug> SurrealConnection<C> {
     pub async fn init_auth(&self) {
         tracing::info!("setting up auth..");
         self
-            .query("DEFINE TABLE OVERWRITE sc__editor SCHEMAFULL PERMISSIONS FOR select, update, delete WHERE id = $auth.id OR $auth.admin = true FOR create WHERE $auth.admin = true")
+            .query("DEFINE TABLE OVERWRITE sc__editor SCHEMAFULL PERMISSIONS FOR select, update, delete WHERE id = $auth.id OR $auth.role = 'admin' FOR create WHERE $auth.role = 'admin'")
             .query("DEFINE FIELD IF NOT EXISTS name ON sc__editor TYPE string")
             .query("DEFINE FIELD IF NOT EXISTS email ON sc__editor TYPE string ASSERT string::is::email($value)")
             .query("DEFINE FIELD IF NOT EXISTS password ON sc__editor TYPE option<string>;")
-            .query("DEFINE FIELD IF NOT EXISTS admin ON sc__editor TYPE bool")
+            .query("DEFINE FIELD OVERWRITE role ON sc__editor TYPE string ASSERT $value IN ['viewer', 'editor', 'publisher', 'admin'] DEFAULT 'editor' PERMISSIONS FOR select FULL FOR create, update WHERE $auth.role = 'admin'")
+            .query("DEFINE FIELD OVERWRITE doc_roles ON sc__editor FLEXIBLE TYPE object DEFAULT {} PERMISSIONS FOR select FULL FOR create, update WHERE $auth.role = 'admin'")
+            // editors from before roles existed only had an admin flag
+            .query("UPDATE sc__editor SET role = IF admin = true THEN 'admin' ELSE 'editor' END, doc_roles = {} WHERE role IS NONE")
+            .query("REMOVE FIELD IF EXISTS admin ON sc__editor")
             .query("DEFINE FIELD IF NOT EXISTS oidc_subject ON sc__editor TYPE option<string>;")
             .query("DEFINE FIELD IF NOT EXISTS pfp_url ON sc__editor TYPE option<string>;")
             .query("DEFINE INDEX IF NOT EXISTS email ON sc__editor FIELDS email UNIQUE;")
@@ -722,7 +1315,7 @@ impl<C: Connection + Debug> SurrealConnection<C> {
 		IF $intermediate_query = []
 			{
 				RETURN (INSERT INTO sc__editor {
-					admin: true,
+					role: 'admin',
 					email: $email,
 					name: $username,
 					oidc_subject: $subject,
@@ -751,6 +1344,9 @@ impl<C: Connection + Debug> SurrealConnection<C> {
 macro_rules! doc_init {
     ($db:ident, $doc:ty) => {
         $db.init_doc::<$doc>().await;
+        ::scalar_cms::DatabaseConnection::run_migrations::<$doc>(&$db)
+            .await
+            .unwrap_or_else(|e| panic!("running migrations for {} failed: {e}", <$doc as ::scalar_cms::Document>::IDENTIFIER));
     };
     ($db:ident, $doc:ty, $($docs:ty),+) => {
         ::scalar_surreal::doc_init!($db, $doc);
@@ -762,6 +1358,8 @@ macro_rules! doc_init {
 macro_rules! init {
     ($db:ident, $($docs:ty),+) => {
         $db.init_auth().await;
+        $db.init_references().await;
+        $db.init_migrations().await;
         ::scalar_surreal::doc_init!($db, $($docs),+);
     };
 }
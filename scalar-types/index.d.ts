@@ -1,6 +1,23 @@
+export { Action } from "./types/Action";
 export { DocInfo } from "./types/DocInfo";
 export { EditorField } from "./types/EditorField";
 export { EditorType } from "./types/EditorType";
+export { EnumRepresentation } from "./types/EnumRepresentation";
 export { EnumVariant } from "./types/EnumVariant";
+export { Expression } from "./types/Expression";
+export { FieldGroup } from "./types/FieldGroup";
+export { GroupKind } from "./types/GroupKind";
+export { IncomingReference } from "./types/IncomingReference";
 export { Item } from "./types/Item";
+export { NumberKind } from "./types/NumberKind";
+export { Page } from "./types/Page";
+export { PathError } from "./types/PathError";
+export { PathSegment } from "./types/PathSegment";
+export { Revision } from "./types/Revision";
+export { Role } from "./types/Role";
+export { Rule } from "./types/Rule";
 export { Schema } from "./types/Schema";
+export { Severity } from "./types/Severity";
+export { User } from "./types/User";
+export { Value } from "./types/Value";
+export { VariantKind } from "./types/VariantKind";
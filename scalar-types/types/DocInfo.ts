@@ -1,3 +1,7 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 
-export type DocInfo = { identifier: string, title: string, };
+export type DocInfo = { identifier: string, title: string, 
+/**
+ * See [`Document::schema_hash`].
+ */
+schema_hash: string, };
@@ -1,4 +1,35 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 import type { EditorType } from "./EditorType";
+import type { Expression } from "./Expression";
+import type { Rule } from "./Rule";
 
-export type EditorField = { name: string, title: string, placeholder: string | null, validator: string | null, required: boolean, field_type: EditorType, };
+export type EditorField = { name: string, title: string, placeholder: string | null, 
+/**
+ * Help text for the editor, from the field's doc comment or `#[field(description = "...")]`.
+ */
+description: string | null, 
+/**
+ * Why the field is deprecated, if it is. Deprecated fields can still have data.
+ */
+deprecated: string | null, validator: string | null, 
+/**
+ * Rules the editor can check before anything is sent to the server, see [`crate::validations::rules`].
+ */
+rules: Array<Rule>, 
+/**
+ * Like `rules`, but these only warn, they don't stop the document from being published.
+ */
+warnings: Array<Rule>, 
+/**
+ * Hides the field when this is true, evaluated against the field's siblings.
+ * Documents skip validating hidden fields.
+ */
+hidden_if: Expression | null, 
+/**
+ * Read only fields can be set when a document is created, after that the server undoes changes to them.
+ */
+readonly: boolean, 
+/**
+ * Makes the field read only when this is true, evaluated like `hidden_if` against what's stored.
+ */
+readonly_if: Expression | null, required: boolean, field_type: EditorType, };
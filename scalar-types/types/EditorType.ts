@@ -1,5 +1,64 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 import type { EditorField } from "./EditorField";
+import type { EnumRepresentation } from "./EnumRepresentation";
 import type { EnumVariant } from "./EnumVariant";
+import type { NumberKind } from "./NumberKind";
 
-export type EditorType = { "type": "toggle", component_key: string | null, default: any | null, value: EditorType, } | { "type": "bool", component_key: string | null, default: boolean | null, } | { "type": "integer", component_key: string | null, default: number | null, } | { "type": "float", component_key: string | null, default: number | null, } | { "type": "struct", component_key: string | null, default: any | null, fields: Array<EditorField>, } | { "type": "enum", component_key: string | null, default: any | null, variants: Array<EnumVariant>, } | { "type": "array", component_key: string | null, default: any[] | null, of: EditorType, } | { "type": "single-line", component_key: string | null, default: string | null, } | { "type": "multi-line", component_key: string | null, default: string | null, } | { "type": "markdown", component_key: string | null, default: string | null, } | { "type": "date", component_key: string | null, default: string | null, } | { "type": "date-time", component_key: string | null, default: string | null, } | { "type": "null", component_key: string | null, };
+export type EditorType = { "type": "toggle", component_key: string | null, default: any | null, value: EditorType, } | { "type": "bool", component_key: string | null, default: boolean | null, } | { "type": "integer", component_key: string | null, default: number | null, kind: NumberKind, 
+/**
+ * The smallest value the kind can hold, inclusive. It's a string so 64 bit bounds stay exact,
+ * parse it with `BigInt`.
+ */
+min: string, 
+/**
+ * The largest value the kind can hold, inclusive, as a string like `min`.
+ */
+max: string, 
+/**
+ * Whether 0 isn't allowed, e.g. for `NonZeroU32`.
+ */
+non_zero: boolean, } | { "type": "float", component_key: string | null, default: number | null, kind: NumberKind, } | { "type": "decimal", component_key: string | null, default: string | null, } | { "type": "struct", component_key: string | null, 
+/**
+ * The name of the Rust type, used to name generated types. `None` for generic types, which get inlined.
+ */
+name: string | null, default: any | null, fields: Array<EditorField>, } | { "type": "enum", component_key: string | null, 
+/**
+ * The name of the Rust type, used to name generated types. `None` for generic types, which get inlined.
+ */
+name: string | null, default: any | null, 
+/**
+ * How variants are serialized, following serde.
+ */
+representation: EnumRepresentation, variants: Array<EnumVariant>, } | { "type": "array", component_key: string | null, default: any[] | null, of: EditorType, } | { "type": "single-line", component_key: string | null, default: string | null, } | { "type": "multi-line", component_key: string | null, default: string | null, } | { "type": "markdown", component_key: string | null, default: string | null, } | { "type": "date", component_key: string | null, default: string | null, } | { "type": "date-time", component_key: string | null, default: string | null, } | { "type": "localized", component_key: string | null, default: Record<string, any> | null, 
+/**
+ * Every locale, in the order they should be shown in.
+ */
+locales: Array<string>, 
+/**
+ * Locales that have to have a value.
+ */
+required: Array<string>, 
+/**
+ * Locales to fall back on, in order, when a value is missing.
+ */
+fallback: Array<string>, of: EditorType, } | { "type": "rich-text", component_key: string | null, default: any[] | null, 
+/**
+ * Block styles, the first one is the default.
+ */
+styles: Array<string>, decorators: Array<string>, lists: boolean, 
+/**
+ * What can be attached to spans.
+ */
+annotation: EditorType, 
+/**
+ * What can be put between blocks, `null` if nothing can.
+ */
+embed: EditorType, } | { "type": "reference", component_key: string | null, default: string | null, 
+/**
+ * The identifier of the referenced document.
+ */
+to: string, 
+/**
+ * Whether the referenced document has to be published.
+ */
+published: boolean, } | { "type": "null", component_key: string | null, };
@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * How an enum is serialized, matching serde's enum representations. In each, the content
+ * is the variant's content as described by [`VariantKind`].
+ */
+export type EnumRepresentation = { "kind": "external" } | { "kind": "internal", tag: string, } | { "kind": "adjacent", tag: string, content: string, } | { "kind": "untagged" };
@@ -1,4 +1,13 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 import type { EditorField } from "./EditorField";
+import type { VariantKind } from "./VariantKind";
 
-export type EnumVariant = { variant_name: string, fields: Array<EditorField> | null, };
+export type EnumVariant = { 
+/**
+ * The name the variant is serialized as.
+ */
+variant_name: string, kind: VariantKind, 
+/**
+ * `None` for unit variants. Fields of newtype and tuple variants are named by their index.
+ */
+fields: Array<EditorField> | null, };
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Value } from "./Value";
+
+export type Expression = { "op": "equals", lhs: Value, rhs: Value, } | { "op": "not_equals", lhs: Value, rhs: Value, } | { "op": "greater_than", lhs: Value, rhs: Value, } | { "op": "greater_than_or_equals", lhs: Value, rhs: Value, } | { "op": "less_than", lhs: Value, rhs: Value, } | { "op": "less_than_or_equals", lhs: Value, rhs: Value, } | { "op": "and", lhs: Expression, rhs: Expression, } | { "op": "or", lhs: Expression, rhs: Expression, };
@@ -0,0 +1,15 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { GroupKind } from "./GroupKind";
+
+/**
+ * A group of fields in the editor. Groups only change how fields are shown, not how they're stored.
+ */
+export type FieldGroup = { id: string, title: string, kind: GroupKind, collapsible: boolean, 
+/**
+ * Whether the fieldset starts collapsed.
+ */
+collapsed: boolean, 
+/**
+ * The names of the fields in this group, in order.
+ */
+fields: Array<string>, };
@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * A document that references another document, see [`types::Reference`].
+ */
+export type IncomingReference = { __sc_doc: string, __sc_id: string, 
+/**
+ * Whether the reference is in the published version of the document, rather than it's draft.
+ */
+published: boolean, };
@@ -1,3 +1,8 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 
-export type Item = { __sc_id: string, __sc_created_at: string, __sc_modified_at: string, __sc_published_at: string | null, content: any, };
+export type Item = { __sc_id: string, __sc_created_at: string, __sc_modified_at: string, __sc_published_at: string | null, 
+/**
+ * The [`Document::schema_hash`] this item was last written under.
+ * `None` if it was written before schemas were hashed.
+ */
+__sc_schema_hash: string | null, content: any, };
@@ -0,0 +1,16 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { PathSegment } from "./PathSegment";
+import type { Severity } from "./Severity";
+
+/**
+ * A validation error, addressed by where it happened in the document.
+ */
+export type PathError = { 
+/**
+ * A JSON pointer to the invalid value, e.g. `/sections/3/title`.
+ */
+path: string, 
+/**
+ * The path, including the enum variants along it (which aren't part of the pointer).
+ */
+segments: Array<PathSegment>, code: string, message: string, severity: Severity, };
@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { User } from "./User";
+
+/**
+ * An immutable snapshot of a document, taken every time it's published.
+ */
+export type Revision = { __sc_revision_id: string, __sc_id: string, __sc_created_at: string, __sc_created_by: User, content: any, };
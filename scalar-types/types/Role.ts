@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * What a user is allowed to do, each role can do everything the roles before it can.
+ */
+export type Role = "viewer" | "editor" | "publisher" | "admin";
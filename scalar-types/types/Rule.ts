@@ -0,0 +1,8 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * A rule on a document field, as it's exposed to the editor in [`crate::EditorField::rules`].
+ * The editor can check these for quick feedback, but the server always checks them too, so it never
+ * has to trust the editor.
+ */
+export type Rule = { "rule": "non_empty" } | { "rule": "length", min: number | null, max: number | null, } | { "rule": "items", min: number | null, max: number | null, } | { "rule": "range", min: number | null, max: number | null, } | { "rule": "regex", pattern: string, } | { "rule": "email", pattern: string, } | { "rule": "url", pattern: string, };
@@ -1,4 +1,13 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 import type { EditorField } from "./EditorField";
+import type { FieldGroup } from "./FieldGroup";
 
-export type Schema = { identifier: string, title: string, singleton: boolean, label: string | null, sub_label: string | null, fields: Array<EditorField>, };
+export type Schema = { identifier: string, title: string, singleton: boolean, label: string | null, sub_label: string | null, fields: Array<EditorField>, 
+/**
+ * See [`Document::groups`].
+ */
+groups: Array<FieldGroup>, 
+/**
+ * See [`Document::schema_hash`].
+ */
+hash: string, };
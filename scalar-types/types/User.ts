@@ -0,0 +1,8 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Role } from "./Role";
+
+export type User = { email: string, name: string, profile_picture_url: string, role: Role, 
+/**
+ * Roles for specific documents, by identifier. These take precedence over `role`.
+ */
+doc_roles: { [key in string]?: Role }, };
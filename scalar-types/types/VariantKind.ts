@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * The shape of an enum variant's content.
+ */
+export type VariantKind = "unit" | "struct" | "newtype" | "tuple";